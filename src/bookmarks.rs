@@ -0,0 +1,244 @@
+use raylib::prelude::Vector3;
+
+use crate::camera::Camera;
+
+// Archivo por defecto donde se guardan los marcadores, junto a la escena
+pub const DEFAULT_BOOKMARKS_PATH: &str = "bookmarks.txt";
+// Ranuras disponibles (1-9)
+pub const BOOKMARK_SLOTS: usize = 9;
+
+// Punto de vista guardado: pose de la cámara y su FOV (en radianes)
+#[derive(Clone, Copy, Debug)]
+pub struct CameraBookmark {
+    pub eye: Vector3,
+    pub center: Vector3,
+    pub up: Vector3,
+    pub fov: f32,
+}
+
+impl CameraBookmark {
+    pub fn from_camera(camera: &Camera) -> Self {
+        CameraBookmark {
+            eye: camera.eye,
+            center: camera.center,
+            up: camera.up,
+            fov: camera.fov,
+        }
+    }
+
+    // Restaura la vista; modo, proyección y apertura de la cámara se conservan
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.set_view(self.eye, self.center, self.up);
+        camera.set_fov(self.fov);
+    }
+}
+
+// Marcadores numerados persistidos en un archivo de texto:
+//   slot <n> eye <x y z> center <x y z> up <x y z> fov <grados>
+pub struct Bookmarks {
+    path: String,
+    slots: [Option<CameraBookmark>; BOOKMARK_SLOTS],
+}
+
+impl Bookmarks {
+    // Sin marcadores; se guardarán en `path`
+    pub fn new(path: &str) -> Self {
+        Bookmarks {
+            path: path.to_string(),
+            slots: [None; BOOKMARK_SLOTS],
+        }
+    }
+
+    // Carga los marcadores del archivo; si no existe se empieza sin ninguno
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Bookmarks::new(path)),
+            Err(error) => return Err(format!("No se pudo leer {}: {}", path, error)),
+        };
+        Self::parse(path, &contents).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    // Marcadores del texto de un archivo (que se guardarán en `path`); los errores dicen la línea
+    fn parse(path: &str, contents: &str) -> Result<Self, String> {
+        let mut bookmarks = Bookmarks::new(path);
+        for (line_index, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (slot, bookmark) = parse_line(line).map_err(|error| format!("línea {}: {}", line_index + 1, error))?;
+            bookmarks.slots[slot - 1] = Some(bookmark);
+        }
+        Ok(bookmarks)
+    }
+
+    // Como `load`, pero un archivo que no se puede leer no detiene el programa: se avisa y se empieza
+    // sin marcadores
+    pub fn load_or_empty(path: &str) -> Self {
+        Bookmarks::load(path).unwrap_or_else(|error| {
+            eprintln!("{}; se empieza sin marcadores", error);
+            Bookmarks::new(path)
+        })
+    }
+
+    // Ranura 1-9
+    pub fn get(&self, slot: usize) -> Option<&CameraBookmark> {
+        self.slots.get(slot.wrapping_sub(1)).and_then(|bookmark| bookmark.as_ref())
+    }
+
+    // Guarda la vista en la ranura y reescribe el archivo
+    pub fn store(&mut self, slot: usize, bookmark: CameraBookmark) -> Result<(), String> {
+        if slot == 0 || slot > BOOKMARK_SLOTS {
+            return Err(format!("Ranura fuera de rango: {}", slot));
+        }
+        self.slots[slot - 1] = Some(bookmark);
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        std::fs::write(&self.path, self.contents()).map_err(|error| format!("No se pudo escribir {}: {}", self.path, error))
+    }
+
+    // Texto del archivo con las ranuras ocupadas
+    fn contents(&self) -> String {
+        let mut contents = String::from("# Marcadores de cámara: slot <n> eye <x y z> center <x y z> up <x y z> fov <grados>\n");
+        for (index, bookmark) in self.slots.iter().enumerate() {
+            if let Some(bookmark) = bookmark {
+                contents.push_str(&format!(
+                    "slot {} eye {} {} {} center {} {} {} up {} {} {} fov {}\n",
+                    index + 1,
+                    bookmark.eye.x,
+                    bookmark.eye.y,
+                    bookmark.eye.z,
+                    bookmark.center.x,
+                    bookmark.center.y,
+                    bookmark.center.z,
+                    bookmark.up.x,
+                    bookmark.up.y,
+                    bookmark.up.z,
+                    bookmark.fov.to_degrees()
+                ));
+            }
+        }
+        contents
+    }
+}
+
+fn parse_line(line: &str) -> Result<(usize, CameraBookmark), String> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.first() != Some(&"slot") {
+        return Err(format!("se esperaba 'slot', no '{}'", tokens[0]));
+    }
+
+    let slot = tokens
+        .get(1)
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|slot| (1..=BOOKMARK_SLOTS).contains(slot))
+        .ok_or_else(|| "ranura no válida".to_string())?;
+
+    let number = |index: usize| -> Result<f32, String> {
+        tokens
+            .get(index)
+            .ok_or_else(|| "faltan valores".to_string())?
+            .parse::<f32>()
+            .map_err(|_| format!("número no válido '{}'", tokens[index]))
+    };
+    let vector = |index: usize| -> Result<Vector3, String> {
+        Ok(Vector3::new(number(index)?, number(index + 1)?, number(index + 2)?))
+    };
+
+    let mut bookmark = CameraBookmark {
+        eye: Vector3::zero(),
+        center: Vector3::zero(),
+        up: Vector3::new(0.0, 1.0, 0.0),
+        fov: std::f32::consts::PI / 3.0,
+    };
+    let mut has_eye = false;
+    let mut has_center = false;
+
+    let mut index = 2;
+    while index < tokens.len() {
+        match tokens[index] {
+            "eye" => {
+                bookmark.eye = vector(index + 1)?;
+                has_eye = true;
+                index += 4;
+            }
+            "center" => {
+                bookmark.center = vector(index + 1)?;
+                has_center = true;
+                index += 4;
+            }
+            "up" => {
+                bookmark.up = vector(index + 1)?;
+                index += 4;
+            }
+            "fov" => {
+                bookmark.fov = number(index + 1)?.to_radians();
+                index += 2;
+            }
+            other => return Err(format!("campo desconocido '{}'", other)),
+        }
+    }
+
+    if !has_eye || !has_center {
+        return Err("faltan eye o center".to_string());
+    }
+
+    Ok((slot, bookmark))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_through_text() {
+        let mut bookmarks = Bookmarks::new("marcadores.txt");
+        let view = CameraBookmark {
+            eye: Vector3::new(1.5, -2.0, 3.25),
+            center: Vector3::new(0.0, 1.0, -4.0),
+            up: Vector3::new(0.0, 0.0, 1.0),
+            fov: 42f32.to_radians(),
+        };
+        bookmarks.slots[2] = Some(view);
+        bookmarks.slots[8] = Some(CameraBookmark { fov: 1.0, ..view });
+
+        let contents = bookmarks.contents();
+        assert_eq!(contents.lines().filter(|line| line.starts_with("slot ")).count(), 2);
+        let loaded = Bookmarks::parse("marcadores.txt", &contents).unwrap();
+        for slot in 1..=BOOKMARK_SLOTS {
+            match (bookmarks.get(slot), loaded.get(slot)) {
+                (None, None) => {}
+                (Some(saved), Some(read)) => {
+                    assert_eq!((saved.eye, saved.center, saved.up), (read.eye, read.center, read.up));
+                    assert!((saved.fov - read.fov).abs() < 1e-6);
+                }
+                _ => panic!("la ranura {} no coincide", slot),
+            }
+        }
+    }
+
+    #[test]
+    fn optional_fields_and_comments() {
+        let bookmarks = Bookmarks::parse("b.txt", "# vistas\n\nslot 4 center 0 0 0 eye 1 2 3   # la del puente\n").unwrap();
+        let bookmark = bookmarks.get(4).unwrap();
+        assert_eq!(bookmark.eye, Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(bookmark.up, Vector3::new(0.0, 1.0, 0.0));
+        assert!((bookmark.fov - std::f32::consts::PI / 3.0).abs() < 1e-6);
+        assert!(bookmarks.get(0).is_none() && bookmarks.get(10).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let parse = |contents: &str| Bookmarks::parse("b.txt", contents).err();
+        assert_eq!(parse("slot 0 eye 0 0 0 center 0 0 0"), Some("línea 1: ranura no válida".to_string()));
+        assert_eq!(parse("\nslot 10 eye 0 0 0 center 0 0 0"), Some("línea 2: ranura no válida".to_string()));
+        assert_eq!(parse("slot 1 eye 0 0 0"), Some("línea 1: faltan eye o center".to_string()));
+        assert_eq!(parse("slot 1 eye 0 0"), Some("línea 1: faltan valores".to_string()));
+        assert!(parse("slot 1 eye 0 0 x center 0 0 0").is_some());
+        assert!(parse("slot 1 eye 0 0 0 center 0 0 0 zoom 2").is_some());
+        assert!(parse("vista 1").is_some());
+    }
+}
@@ -1,11 +1,87 @@
 use raylib::prelude::Vector3;
+use std::f32::consts::PI;
 
+use crate::primitives::Primitive;
+use crate::stereo::{StereoEye, StereoSettings};
+
+// Radio de la "cápsula" de la cámara en modo vuelo libre, para no atravesar bloques
+const COLLISION_RADIUS: f32 = 0.2;
+// Límite de inclinación vertical (casi 90°) para no dar la vuelta a la cámara
+const MAX_PITCH: f32 = 1.5;
+// Rango permitido para el campo de visión vertical (radianes)
+const MIN_FOV: f32 = 10.0 * PI / 180.0;
+const MAX_FOV: f32 = 120.0 * PI / 180.0;
+// Distancia de enfoque mínima, para no enfocar dentro de la lente
+const MIN_FOCUS_DISTANCE: f32 = 0.1;
+// Ángulo áureo: reparte las muestras de la lente en espiral sin que se agrupen
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+// Orbital: gira alrededor de `center`. Vuelo libre: primera persona, `center` va delante del ojo
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CameraMode {
+    Orbit,
+    FreeFly,
+}
+
+// Cómo se convierte cada píxel en un rayo primario
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Projection {
+    // Cámara estenopeica clásica
+    Perspective,
+    // Rayos paralelos; el encuadre depende de la distancia a `center` (tomas isométricas)
+    Orthographic,
+    // Panorama 360° completo (longitud × latitud)
+    Equirectangular,
+    // Panorama 360° horizontal con altura en perspectiva
+    Cylindrical,
+}
+
+impl Projection {
+    pub fn from_name(name: &str) -> Option<Projection> {
+        match name {
+            "perspective" => Some(Projection::Perspective),
+            "orthographic" | "ortho" => Some(Projection::Orthographic),
+            "equirectangular" | "panorama" => Some(Projection::Equirectangular),
+            "cylindrical" => Some(Projection::Cylindrical),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Projection::Perspective => "perspectiva",
+            Projection::Orthographic => "ortográfica",
+            Projection::Equirectangular => "panorama equirectangular",
+            Projection::Cylindrical => "panorama cilíndrico",
+        }
+    }
+
+    pub fn next(&self) -> Projection {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Equirectangular,
+            Projection::Equirectangular => Projection::Cylindrical,
+            Projection::Cylindrical => Projection::Perspective,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub eye: Vector3,
     pub center: Vector3,
     pub up: Vector3,
     pub forward: Vector3,
     pub right: Vector3,
+    pub mode: CameraMode,
+    pub projection: Projection,
+    // Campo de visión vertical en radianes
+    pub fov: f32,
+    // Lente delgada: radio de apertura (0 = estenopeica, todo enfocado) y distancia al plano enfocado
+    pub aperture: f32,
+    pub focus_distance: f32,
+    // Modo estéreo (None = una sola vista)
+    pub stereo: Option<StereoSettings>,
     changed: bool,
 }
 
@@ -17,6 +93,12 @@ impl Camera {
             up,
             forward: Vector3::zero(),
             right: Vector3::zero(),
+            mode: CameraMode::Orbit,
+            projection: Projection::Perspective,
+            fov: PI / 3.0,
+            aperture: 0.0,
+            focus_distance: (center - eye).length().max(MIN_FOCUS_DISTANCE),
+            stereo: None,
             changed: true,
         };
         camera.update_basis_vectors();
@@ -63,18 +145,272 @@ impl Camera {
 
     pub fn zoom(&mut self, delta: f32) {
         let direction = (self.center - self.eye).normalized();
-        self.eye = self.eye + direction * delta;
+        self.eye += direction * delta;
         self.changed = true;
     }
 
     pub fn move_vertical(&mut self, delta: f32) {
 
         let vertical_offset = Vector3::new(0.0, delta, 0.0);
-        self.eye = self.eye + vertical_offset;
-        self.center = self.center + vertical_offset;
+        self.eye += vertical_offset;
+        self.center += vertical_offset;
+        self.changed = true;
+    }
+
+    // Cambia entre orbital y vuelo libre sin mover la vista: el punto de órbita
+    // queda a la misma distancia delante de la cámara
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Orbit,
+        };
+        self.changed = true;
+    }
+
+    // Coloca la cámara en una pose concreta; el enfoque pasa al punto al que se mira
+    pub fn set_view(&mut self, eye: Vector3, center: Vector3, up: Vector3) {
+        self.eye = eye;
+        self.center = center;
+        self.up = up;
+        self.focus_distance = (center - eye).length().max(MIN_FOCUS_DISTANCE);
+        self.update_basis_vectors();
+    }
+
+    // Gira la vista en primera persona (yaw/pitch) dejando el ojo fijo
+    pub fn look(&mut self, yaw: f32, pitch: f32) {
+        let distance = (self.center - self.eye).length().max(1e-3);
+
+        let current_yaw = self.forward.z.atan2(self.forward.x);
+        let current_pitch = self.forward.y.clamp(-1.0, 1.0).asin();
+
+        let new_yaw = current_yaw + yaw;
+        let new_pitch = (current_pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+
+        let cos_pitch = new_pitch.cos();
+        let new_forward = Vector3::new(
+            cos_pitch * new_yaw.cos(),
+            new_pitch.sin(),
+            cos_pitch * new_yaw.sin(),
+        );
+
+        self.center = self.eye + new_forward * distance;
+        // En primera persona el "arriba" siempre es el del mundo, sin alabeo
+        self.up = Vector3::new(0.0, 1.0, 0.0);
+        self.update_basis_vectors();
+    }
+
+    // Desplaza ojo y objetivo en ejes locales (adelante, derecha, arriba del mundo).
+    // Cada eje se prueba por separado para poder deslizarse por las paredes.
+    // Devuelve true si la cámara llegó a moverse.
+    pub fn fly(&mut self, forward: f32, right: f32, up: f32, objects: &[Primitive]) -> bool {
+        let offset = self.forward * forward + self.right * right + Vector3::new(0.0, up, 0.0);
+        let start = self.eye;
+
+        for axis_offset in [
+            Vector3::new(offset.x, 0.0, 0.0),
+            Vector3::new(0.0, offset.y, 0.0),
+            Vector3::new(0.0, 0.0, offset.z),
+        ] {
+            if axis_offset == Vector3::zero() {
+                continue;
+            }
+            let candidate = self.eye + axis_offset;
+            // Si ya estaba dentro de un objeto se le deja salir
+            if !Self::collides(candidate, objects) || Self::collides(self.eye, objects) {
+                self.eye = candidate;
+                self.center += axis_offset;
+            }
+        }
+
+        let moved = self.eye != start;
+        if moved {
+            self.changed = true;
+        }
+        moved
+    }
+
+    fn collides(point: Vector3, objects: &[Primitive]) -> bool {
+        objects.iter().any(|object| object.overlaps_sphere(point, COLLISION_RADIUS))
+    }
+
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(MIN_FOV, MAX_FOV);
         self.changed = true;
     }
 
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.changed = true;
+    }
+
+    pub fn set_aperture(&mut self, aperture: f32) {
+        self.aperture = aperture.max(0.0);
+        self.changed = true;
+    }
+
+    pub fn set_focus_distance(&mut self, distance: f32) {
+        self.focus_distance = distance.max(MIN_FOCUS_DISTANCE);
+        self.changed = true;
+    }
+
+    // Distancia de enfoque que deja nítido un punto del mundo (en perspectiva el plano enfocado es paralelo a la pantalla)
+    pub fn focus_distance_to(&self, point: Vector3) -> f32 {
+        match self.projection {
+            Projection::Perspective => (point - self.eye).dot(self.forward),
+            _ => (point - self.eye).length(),
+        }
+    }
+
+    fn perspective_scale(&self) -> f32 {
+        (self.fov * 0.5).tan()
+    }
+
+    // Mitad de la altura visible en ortográfica: la misma que ve la perspectiva a la distancia de `center`
+    fn orthographic_half_height(&self) -> f32 {
+        (self.center - self.eye).length() * self.perspective_scale()
+    }
+
+    // Rayo primario (origen, dirección) para un punto de pantalla normalizado en [-1, 1]
+    pub fn primary_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32) -> (Vector3, Vector3) {
+        match self.projection {
+            Projection::Perspective => {
+                let scale = self.perspective_scale();
+                let local = Vector3::new(screen_x * aspect_ratio * scale, screen_y * scale, -1.0);
+                (self.eye, self.basis_change(&local.normalized()))
+            }
+            Projection::Orthographic => {
+                let half_height = self.orthographic_half_height();
+                let origin = self.eye
+                    + self.right * (screen_x * aspect_ratio * half_height)
+                    + self.up * (screen_y * half_height);
+                (origin, self.forward)
+            }
+            Projection::Equirectangular => {
+                let longitude = screen_x * PI;
+                let latitude = screen_y * PI * 0.5;
+                let local = Vector3::new(
+                    longitude.sin() * latitude.cos(),
+                    latitude.sin(),
+                    -longitude.cos() * latitude.cos(),
+                );
+                (self.eye, self.basis_change(&local))
+            }
+            Projection::Cylindrical => {
+                let longitude = screen_x * PI;
+                let local = Vector3::new(longitude.sin(), screen_y * self.perspective_scale(), -longitude.cos());
+                (self.eye, self.basis_change(&local.normalized()))
+            }
+        }
+    }
+
+    // Rayo primario que sale de un punto de la lente (`lens_sample` en el disco unidad)
+    // y pasa por el punto enfocado del rayo estenopeico correspondiente
+    pub fn lens_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32, lens_sample: (f32, f32)) -> (Vector3, Vector3) {
+        let (origin, direction) = self.primary_ray(screen_x, screen_y, aspect_ratio);
+        if self.aperture <= 0.0 {
+            return (origin, direction);
+        }
+
+        // En perspectiva la lente y el plano enfocado son paralelos a la pantalla;
+        // en el resto la lente se orienta con cada rayo y se enfoca a distancia constante
+        let (focus_point, lens_right, lens_up) = match self.projection {
+            Projection::Perspective => (
+                origin + direction * (self.focus_distance / direction.dot(self.forward)),
+                self.right,
+                self.up,
+            ),
+            _ => {
+                let mut lens_right = direction.cross(self.up);
+                if lens_right.length() < 1e-4 {
+                    lens_right = self.right;
+                }
+                let lens_right = lens_right.normalized();
+                (origin + direction * self.focus_distance, lens_right, lens_right.cross(direction))
+            }
+        };
+
+        let lens_origin = origin
+            + lens_right * (lens_sample.0 * self.aperture)
+            + lens_up * (lens_sample.1 * self.aperture);
+        (lens_origin, (focus_point - lens_origin).normalized())
+    }
+
+    pub fn set_stereo(&mut self, stereo: Option<StereoSettings>) {
+        self.stereo = stereo;
+        self.changed = true;
+    }
+
+    // Desplaza un rayo de la cámara central al ojo indicado.
+    // En perspectiva/ortográfica los ojos se separan en paralelo y los rayos se cruzan en el plano
+    // de convergencia; en los panoramas cada rayo sale de un círculo de diámetro igual a la
+    // separación de los ojos (estéreo omnidireccional, ODS)
+    pub fn stereo_ray(&self, origin: Vector3, direction: Vector3, eye: StereoEye) -> (Vector3, Vector3) {
+        let Some(stereo) = self.stereo else {
+            return (origin, direction);
+        };
+        let half_separation = stereo.eye_separation * 0.5 * eye.sign();
+
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic => {
+                let convergence = stereo.convergence.unwrap_or(self.focus_distance).max(MIN_FOCUS_DISTANCE);
+                let convergence_point = origin + direction * (convergence / direction.dot(self.forward).max(1e-4));
+                let eye_origin = origin + self.right * half_separation;
+                (eye_origin, (convergence_point - eye_origin).normalized())
+            }
+            Projection::Equirectangular | Projection::Cylindrical => {
+                let mut tangent = direction.cross(self.up);
+                if tangent.length() < 1e-4 {
+                    tangent = self.right;
+                }
+                (origin + tangent.normalized() * half_separation, direction)
+            }
+        }
+    }
+
+    // Inversa de primary_ray para un punto del mundo; None si queda fuera de la vista
+    pub fn project_point(&self, point: Vector3, aspect_ratio: f32) -> Option<(f32, f32)> {
+        if self.projection != Projection::Orthographic {
+            return self.project_direction(point - self.eye, aspect_ratio);
+        }
+
+        let local = self.inverse_basis_change(&(point - self.eye));
+        if local.z >= 0.0 {
+            return None;
+        }
+        let half_height = self.orthographic_half_height();
+        Some((local.x / (half_height * aspect_ratio), local.y / half_height))
+    }
+
+    // Inversa de primary_ray para una dirección (p. ej. el cielo); None si queda fuera de la vista
+    pub fn project_direction(&self, direction: Vector3, aspect_ratio: f32) -> Option<(f32, f32)> {
+        let local = self.inverse_basis_change(&direction);
+        match self.projection {
+            Projection::Perspective => {
+                if local.z >= -1e-4 {
+                    return None;
+                }
+                let scale = self.perspective_scale();
+                Some((local.x / -local.z / (aspect_ratio * scale), local.y / -local.z / scale))
+            }
+            // Todos los rayos son paralelos: una dirección no corresponde a un píxel concreto
+            Projection::Orthographic => None,
+            Projection::Equirectangular => {
+                let local = local.normalized();
+                let longitude = local.x.atan2(-local.z);
+                let latitude = local.y.clamp(-1.0, 1.0).asin();
+                Some((longitude / PI, latitude / (PI * 0.5)))
+            }
+            Projection::Cylindrical => {
+                let horizontal = (local.x * local.x + local.z * local.z).sqrt();
+                if horizontal < 1e-6 {
+                    return None;
+                }
+                let longitude = local.x.atan2(-local.z);
+                Some((longitude / PI, local.y / horizontal / self.perspective_scale()))
+            }
+        }
+    }
+
     pub fn is_changed(&mut self) -> bool {
         if self.changed {
             self.changed = false;
@@ -91,4 +427,21 @@ impl Camera {
             v.x * self.right.z + v.y * self.up.z - v.z * self.forward.z,
         )
     }
+
+    // Inversa de basis_change: lleva un vector del mundo al espacio de la cámara
+    pub fn inverse_basis_change(&self, v: &Vector3) -> Vector3 {
+        Vector3::new(
+            v.dot(self.right),
+            v.dot(self.up),
+            -v.dot(self.forward),
+        )
+    }
+}
+
+// Muestra `index` de `count` sobre el disco de la lente: espiral áurea girada por píxel
+// (`rotation`) para que el ruido no forme patrones repetidos
+pub fn lens_sample(index: u32, count: u32, rotation: f32) -> (f32, f32) {
+    let radius = ((index as f32 + 0.5) / count as f32).sqrt();
+    let angle = index as f32 * GOLDEN_ANGLE + rotation;
+    (radius * angle.cos(), radius * angle.sin())
 }
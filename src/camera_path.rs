@@ -0,0 +1,339 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use crate::camera::Camera;
+
+// Número de keyframes con los que se aproxima el círculo del turntable
+const TURNTABLE_KEYFRAMES: usize = 36;
+
+// Curva que une las posiciones de los keyframes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Interpolation {
+    Linear,
+    // Pasa por todos los keyframes con tangentes suaves
+    CatmullRom,
+}
+
+// Aceleración dentro de cada tramo (se aplica al tramo que empieza en el keyframe)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    fn from_name(name: &str) -> Option<Easing> {
+        match name {
+            "linear" => Some(Easing::Linear),
+            "in" | "ease-in" => Some(Easing::EaseIn),
+            "out" | "ease-out" => Some(Easing::EaseOut),
+            "in-out" | "ease-in-out" => Some(Easing::EaseInOut),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// Pose de la cámara en un instante del recorrido (tiempo en segundos, FOV en radianes)
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f32,
+    pub eye: Vector3,
+    pub center: Vector3,
+    pub up: Vector3,
+    pub fov: f32,
+    pub easing: Easing,
+}
+
+// Recorrido de cámara por keyframes ordenados en el tiempo
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+    // Un recorrido cerrado no repite el último frame (es igual al primero)
+    pub looping: bool,
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<Keyframe>, interpolation: Interpolation) -> Result<Self, String> {
+        if keyframes.is_empty() {
+            return Err("El recorrido no tiene keyframes".to_string());
+        }
+        keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(CameraPath {
+            keyframes,
+            interpolation,
+            looping: false,
+        })
+    }
+
+    // Lee un recorrido de un archivo de texto. Formato (una orden por línea, `#` para comentarios):
+    //   interpolation catmull-rom|linear
+    //   key <t> <eye x y z> <center x y z> [up <x y z>] [fov <grados>] [ease linear|in|out|in-out]
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+        Self::parse(&contents).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut interpolation = Interpolation::CatmullRom;
+        let mut keyframes = Vec::new();
+
+        for (line_index, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let line_error = |message: &str| format!("línea {}: {}", line_index + 1, message);
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+
+            match tokens[0] {
+                "interpolation" => {
+                    interpolation = match tokens.get(1).copied() {
+                        Some("catmull-rom") => Interpolation::CatmullRom,
+                        Some("linear") => Interpolation::Linear,
+                        _ => return Err(line_error("interpolación desconocida")),
+                    };
+                }
+                "key" => keyframes.push(parse_keyframe(&tokens[1..]).map_err(|error| line_error(&error))?),
+                other => return Err(line_error(&format!("orden desconocida '{}'", other))),
+            }
+        }
+
+        Self::new(keyframes, interpolation)
+    }
+
+    // Vuelta completa de 360° alrededor de `center`, conservando radio, altura y FOV de la cámara
+    pub fn turntable(camera: &Camera, duration: f32) -> Self {
+        let relative = camera.eye - camera.center;
+        let radius = (relative.x * relative.x + relative.z * relative.z).sqrt();
+        let start_angle = relative.z.atan2(relative.x);
+
+        let keyframes = (0..=TURNTABLE_KEYFRAMES)
+            .map(|index| {
+                let fraction = index as f32 / TURNTABLE_KEYFRAMES as f32;
+                let angle = start_angle + fraction * 2.0 * PI;
+                Keyframe {
+                    time: fraction * duration,
+                    eye: camera.center + Vector3::new(radius * angle.cos(), relative.y, radius * angle.sin()),
+                    center: camera.center,
+                    up: Vector3::new(0.0, 1.0, 0.0),
+                    fov: camera.fov,
+                    easing: Easing::Linear,
+                }
+            })
+            .collect();
+
+        let mut path = Self::new(keyframes, Interpolation::CatmullRom).expect("Turntable sin keyframes");
+        path.looping = true;
+        path
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map(|key| key.time).unwrap_or(0.0) - self.keyframes[0].time
+    }
+
+    // Tiempos de cada frame a `fps` imágenes por segundo
+    pub fn frame_times(&self, fps: f32) -> Vec<f32> {
+        let start = self.keyframes[0].time;
+        let intervals = (self.duration() * fps).round().max(0.0) as usize;
+        let frames = if self.looping { intervals.max(1) } else { intervals + 1 };
+        (0..frames).map(|index| start + index as f32 / fps).collect()
+    }
+
+    // Coloca la cámara en la pose del instante `time`; proyección y apertura se conservan,
+    // y el enfoque sigue al punto al que se mira
+    pub fn apply(&self, time: f32, camera: &mut Camera) {
+        let key = self.sample(time);
+        camera.set_view(key.eye, key.center, key.up);
+        camera.set_fov(key.fov);
+    }
+
+    fn sample(&self, time: f32) -> Keyframe {
+        let keys = &self.keyframes;
+        let last = keys.len() - 1;
+        if time <= keys[0].time {
+            return keys[0];
+        }
+        if time >= keys[last].time {
+            return keys[last];
+        }
+
+        let segment = keys.windows(2).position(|pair| time < pair[1].time).unwrap_or(last - 1);
+        let (k1, k2) = (&keys[segment], &keys[segment + 1]);
+        let span = (k2.time - k1.time).max(1e-6);
+        let t = k1.easing.apply((time - k1.time) / span);
+
+        // En un recorrido cerrado el último keyframe repite el primero: los vecinos de los extremos dan
+        // la vuelta saltándoselo, para que la tangente sea la misma a los dos lados de la costura
+        let (previous, next) = if self.looping && last >= 2 {
+            (if segment == 0 { last - 1 } else { segment - 1 }, if segment + 1 == last { 1 } else { segment + 2 })
+        } else {
+            (segment.saturating_sub(1), (segment + 2).min(last))
+        };
+        let (k0, k3) = (&keys[previous], &keys[next]);
+        let blend = |a: Vector3, b: Vector3, c: Vector3, d: Vector3| match self.interpolation {
+            Interpolation::Linear => b.lerp(c, t),
+            Interpolation::CatmullRom => catmull_rom(a, b, c, d, t),
+        };
+
+        Keyframe {
+            time,
+            eye: blend(k0.eye, k1.eye, k2.eye, k3.eye),
+            center: blend(k0.center, k1.center, k2.center, k3.center),
+            up: blend(k0.up, k1.up, k2.up, k3.up).normalized(),
+            fov: k1.fov + (k2.fov - k1.fov) * t,
+            easing: k1.easing,
+        }
+    }
+}
+
+fn catmull_rom(p0: Vector3, p1: Vector3, p2: Vector3, p3: Vector3, t: f32) -> Vector3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+fn parse_keyframe(tokens: &[&str]) -> Result<Keyframe, String> {
+    let number = |index: usize| -> Result<f32, String> {
+        tokens
+            .get(index)
+            .ok_or_else(|| "faltan valores".to_string())?
+            .parse::<f32>()
+            .map_err(|_| format!("número no válido '{}'", tokens[index]))
+    };
+    let vector = |index: usize| -> Result<Vector3, String> {
+        Ok(Vector3::new(number(index)?, number(index + 1)?, number(index + 2)?))
+    };
+
+    let mut keyframe = Keyframe {
+        time: number(0)?,
+        eye: vector(1)?,
+        center: vector(4)?,
+        up: Vector3::new(0.0, 1.0, 0.0),
+        fov: PI / 3.0,
+        easing: Easing::Linear,
+    };
+
+    let mut index = 7;
+    while index < tokens.len() {
+        match tokens[index] {
+            "up" => {
+                keyframe.up = vector(index + 1)?;
+                index += 4;
+            }
+            "fov" => {
+                keyframe.fov = number(index + 1)?.to_radians();
+                index += 2;
+            }
+            "ease" => {
+                let name = tokens.get(index + 1).copied().unwrap_or("");
+                keyframe.easing = Easing::from_name(name).ok_or_else(|| format!("easing desconocido '{}'", name))?;
+                index += 2;
+            }
+            other => return Err(format!("opción desconocida '{}'", other)),
+        }
+    }
+
+    Ok(keyframe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vector3, b: Vector3) -> bool {
+        (a - b).length() < 1e-3
+    }
+
+    #[test]
+    fn parses_keyframes_in_time_order() {
+        let path = CameraPath::parse(
+            "# recorrido\n\
+             interpolation linear\n\
+             key 2 10 0 0 0 0 0 fov 30 ease in-out   # el segundo\n\
+             key 0 0 0 10 0 1 0 up 0 0 1\n",
+        )
+        .unwrap();
+        assert_eq!(path.interpolation, Interpolation::Linear);
+        assert_eq!(path.keyframes.len(), 2);
+        let (first, second) = (&path.keyframes[0], &path.keyframes[1]);
+        assert_eq!(first.time, 0.0);
+        assert!(close(first.up, Vector3::new(0.0, 0.0, 1.0)));
+        assert!((first.fov - PI / 3.0).abs() < 1e-6);
+        assert_eq!(second.easing, Easing::EaseInOut);
+        assert!((second.fov - 30f32.to_radians()).abs() < 1e-6);
+        assert_eq!(path.duration(), 2.0);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(CameraPath::parse("").err(), Some("El recorrido no tiene keyframes".to_string()));
+        assert_eq!(CameraPath::parse("key 0 1 2 3 4 5").err(), Some("línea 1: faltan valores".to_string()));
+        assert!(CameraPath::parse("interpolation cubic").is_err());
+        assert!(CameraPath::parse("key 0 0 0 0 0 0 0 ease slow").is_err());
+        assert!(CameraPath::parse("key 0 0 0 0 0 0 0 zoom 2").is_err());
+        assert_eq!(CameraPath::parse("\nmove 0").err(), Some("línea 2: orden desconocida 'move'".to_string()));
+    }
+
+    #[test]
+    fn samples_pass_through_keyframes() {
+        let path = CameraPath::parse("key 0 0 0 0 0 0 -1\nkey 1 4 0 0 0 0 -1\nkey 3 4 2 0 0 0 -1 fov 90\nkey 4 0 2 0 0 0 -1").unwrap();
+        for key in path.keyframes.clone() {
+            let sample = path.sample(key.time);
+            assert!(close(sample.eye, key.eye), "t = {}", key.time);
+            assert!((sample.fov - key.fov).abs() < 1e-5);
+        }
+        // Fuera del recorrido se queda en los extremos
+        assert!(close(path.sample(-1.0).eye, Vector3::zero()));
+        assert!(close(path.sample(10.0).eye, Vector3::new(0.0, 2.0, 0.0)));
+
+        let linear = CameraPath::new(path.keyframes.clone(), Interpolation::Linear).unwrap();
+        assert!(close(linear.sample(0.5).eye, Vector3::new(2.0, 0.0, 0.0)));
+        assert!((linear.sample(2.0).fov - (PI / 3.0 + 90f32.to_radians()) / 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn easing_shapes_each_segment() {
+        let path = CameraPath::parse("key 0 0 0 0 0 0 -1 ease in\nkey 1 1 0 0 0 0 -1 ease out\nkey 2 2 0 0 0 0 -1").unwrap();
+        let linear = CameraPath::new(path.keyframes.clone(), Interpolation::Linear).unwrap();
+        assert!((linear.sample(0.5).eye.x - 0.25).abs() < 1e-5);
+        assert!((linear.sample(1.5).eye.x - 1.75).abs() < 1e-5);
+    }
+
+    #[test]
+    fn turntable_is_smooth_across_the_seam() {
+        let camera = Camera::new(Vector3::new(8.0, 3.0, 0.0), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0));
+        let path = CameraPath::turntable(&camera, 6.0);
+        let step = 0.01;
+        let start = path.sample(0.0).eye;
+        let end = path.sample(path.duration()).eye;
+        assert!(close(start, end));
+
+        // La velocidad justo antes de cerrar la vuelta es la de justo después de empezarla
+        let leaving = (path.sample(step).eye - start) / step;
+        let arriving = (end - path.sample(path.duration() - step).eye) / step;
+        assert!((leaving - arriving).length() < 0.05 * leaving.length(), "{:?} {:?}", leaving, arriving);
+        // Y el radio y la altura se mantienen a lo largo de la vuelta
+        for frame in path.frame_times(10.0) {
+            let eye = path.sample(frame).eye;
+            assert!(((eye.x * eye.x + eye.z * eye.z).sqrt() - 8.0).abs() < 0.01);
+            assert!((eye.y - 3.0).abs() < 1e-4);
+        }
+        assert_eq!(path.frame_times(10.0).len(), 60);
+    }
+}
@@ -0,0 +1,308 @@
+use raylib::prelude::Vector3;
+
+use crate::bookmarks::{Bookmarks, DEFAULT_BOOKMARKS_PATH};
+use crate::camera::{Camera, Projection};
+use crate::scene::{Scene, build_diorama};
+use crate::schematic::{BlockMaterials, DEFAULT_BLOCK_MATERIALS_PATH};
+use crate::stereo::{StereoLayout, StereoSettings};
+use crate::structures::BlockPos;
+use crate::terrain::{MAX_TERRAIN_SIZE, TerrainSettings};
+use crate::textures::TextureManager;
+
+// Opciones de línea de comandos, compartidas por el modo ventana y el render sin ventana
+pub struct CliOptions {
+    // Renderiza un frame a disco sin abrir ventana
+    pub headless: bool,
+    pub output: String,
+    pub width: u32,
+    pub height: u32,
+    pub quality: Option<String>,
+    pub target_fps: Option<f32>,
+    pub projection: Option<Projection>,
+    // Campo de visión vertical en grados
+    pub fov_degrees: Option<f32>,
+    // Profundidad de campo: radio de apertura y distancia de enfoque
+    pub aperture: Option<f32>,
+    pub focus_distance: Option<f32>,
+    // Animación sin ventana: recorrido desde archivo o vuelta completa (segundos)
+    pub path: Option<String>,
+    pub turntable: Option<f32>,
+    pub fps: f32,
+    pub frames_dir: String,
+    // Marcadores de cámara: archivo y ranura con la que arrancar
+    pub bookmarks_file: String,
+    pub bookmark: Option<usize>,
+    // Estéreo: disposición, distancia interpupilar y distancia de convergencia
+    pub stereo: Option<StereoLayout>,
+    pub eye_separation: Option<f32>,
+    pub convergence: Option<f32>,
+    // Modelo glTF/GLB que se añade al diorama, dónde se coloca, a qué escala y qué cámara suya usar
+    pub gltf: Option<String>,
+    pub gltf_offset: Vector3,
+    pub gltf_scale: f32,
+    pub gltf_camera: Option<usize>,
+    // Terreno procedural alrededor del diorama: lado en bloques, semilla y separación de la vegetación
+    pub terrain: Option<i32>,
+    pub seed: u32,
+    pub vegetation: Option<f32>,
+    // Modelo MagicaVoxel que se añade al diorama y bloque donde se coloca su origen
+    pub vox: Option<String>,
+    pub vox_offset: BlockPos,
+    // Exporta los bloques de la escena a este `.vox` y termina, sin renderizar
+    pub export_vox: Option<String>,
+    // Exporta los cubos de la escena a este `.obj` o `.gltf` y termina, sin renderizar
+    pub export_mesh: Option<String>,
+    // Estructura de Minecraft (`.schem` o `.nbt`), bloque donde se coloca y tabla de materiales por bloque
+    pub schematic: Option<String>,
+    pub schematic_offset: BlockPos,
+    pub block_materials: String,
+    // Mundo de bloques editado: se carga al arrancar y es donde se guardan las ediciones
+    pub world: Option<String>,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions {
+            headless: false,
+            output: "render.png".to_string(),
+            width: 1300,
+            height: 900,
+            quality: None,
+            target_fps: None,
+            projection: None,
+            fov_degrees: None,
+            aperture: None,
+            focus_distance: None,
+            path: None,
+            turntable: None,
+            fps: 30.0,
+            frames_dir: "frames".to_string(),
+            bookmarks_file: DEFAULT_BOOKMARKS_PATH.to_string(),
+            bookmark: None,
+            stereo: None,
+            eye_separation: None,
+            convergence: None,
+            gltf: None,
+            gltf_offset: Vector3::zero(),
+            gltf_scale: 1.0,
+            gltf_camera: None,
+            terrain: None,
+            seed: 1,
+            vegetation: None,
+            vox: None,
+            vox_offset: (0, 0, 0),
+            export_vox: None,
+            export_mesh: None,
+            schematic: None,
+            schematic_offset: (0, 0, 0),
+            block_materials: DEFAULT_BLOCK_MATERIALS_PATH.to_string(),
+            world: None,
+        }
+    }
+}
+
+impl CliOptions {
+    pub fn from_args() -> Self {
+        Self::parse(std::env::args().skip(1).collect())
+    }
+
+    fn parse(args: Vec<String>) -> Self {
+        let mut options = CliOptions::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .unwrap_or_else(|| invalid(&format!("Falta el valor de {}", name)))
+            };
+
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--output" | "-o" => options.output = value(&arg),
+                "--width" => options.width = parse_number(&arg, &value(&arg)),
+                "--height" => options.height = parse_number(&arg, &value(&arg)),
+                "--quality" => options.quality = Some(value(&arg)),
+                "--target-fps" => options.target_fps = Some(parse_number(&arg, &value(&arg))),
+                "--projection" => {
+                    let name = value(&arg);
+                    options.projection = Some(
+                        Projection::from_name(&name)
+                            .unwrap_or_else(|| invalid(&format!("Proyección desconocida: {}", name))),
+                    );
+                }
+                "--fov" => options.fov_degrees = Some(parse_number(&arg, &value(&arg))),
+                "--aperture" => options.aperture = Some(parse_number(&arg, &value(&arg))),
+                "--focus-distance" => options.focus_distance = Some(parse_number(&arg, &value(&arg))),
+                "--path" => options.path = Some(value(&arg)),
+                "--turntable" => options.turntable = Some(parse_number(&arg, &value(&arg))),
+                "--fps" => options.fps = parse_number(&arg, &value(&arg)),
+                "--frames-dir" => options.frames_dir = value(&arg),
+                "--bookmarks" => options.bookmarks_file = value(&arg),
+                "--bookmark" => options.bookmark = Some(parse_number(&arg, &value(&arg))),
+                "--stereo" => {
+                    let name = value(&arg);
+                    options.stereo = Some(
+                        StereoLayout::from_name(&name)
+                            .unwrap_or_else(|| invalid(&format!("Modo estéreo desconocido: {}", name))),
+                    );
+                }
+                "--ipd" => options.eye_separation = Some(parse_number(&arg, &value(&arg))),
+                "--convergence" => options.convergence = Some(parse_number(&arg, &value(&arg))),
+                "--gltf" => options.gltf = Some(value(&arg)),
+                "--gltf-at" => options.gltf_offset = parse_vector(&arg, &value(&arg)),
+                "--gltf-scale" => options.gltf_scale = parse_number(&arg, &value(&arg)),
+                "--gltf-camera" => options.gltf_camera = Some(parse_number(&arg, &value(&arg))),
+                "--terrain" => options.terrain = Some(parse_number(&arg, &value(&arg))),
+                "--seed" => options.seed = parse_number(&arg, &value(&arg)),
+                "--vegetation" => options.vegetation = Some(parse_number(&arg, &value(&arg))),
+                "--vox" => options.vox = Some(value(&arg)),
+                "--vox-at" => options.vox_offset = parse_block(&arg, &value(&arg)),
+                "--export-vox" => options.export_vox = Some(value(&arg)),
+                "--export-mesh" => options.export_mesh = Some(value(&arg)),
+                "--schematic" => options.schematic = Some(value(&arg)),
+                "--schematic-at" => options.schematic_offset = parse_block(&arg, &value(&arg)),
+                "--block-materials" => options.block_materials = value(&arg),
+                "--world" => options.world = Some(value(&arg)),
+                _ => println!("Opción ignorada: {}", arg),
+            }
+        }
+
+        if options.width == 0 || options.height == 0 {
+            invalid("La resolución debe ser mayor que cero");
+        }
+        if options.fps <= 0.0 {
+            invalid("--fps debe ser mayor que cero");
+        }
+        if options.gltf_scale <= 0.0 {
+            invalid("--gltf-scale debe ser mayor que cero");
+        }
+        if let Some(size) = options.terrain {
+            if size <= 13 {
+                invalid("--terrain debe ser mayor que 13 (el diorama ocupa 13x13 bloques)");
+            }
+            if size > MAX_TERRAIN_SIZE {
+                invalid(&format!("--terrain no puede pasar de {}", MAX_TERRAIN_SIZE));
+            }
+        }
+        if let Some(spacing) = options.vegetation {
+            if options.terrain.is_none() {
+                invalid("--vegetation necesita --terrain");
+            }
+            if spacing < 1.0 {
+                invalid("--vegetation debe ser al menos 1");
+            }
+        }
+        // Las animaciones siempre se renderizan sin ventana
+        if options.path.is_some() || options.turntable.is_some() {
+            options.headless = true;
+        }
+
+        options
+    }
+
+    // Diorama con el terreno, los modelos glTF y MagicaVoxel y la estructura de Minecraft pedidos, si los hay. Un archivo
+    // que no se puede importar se trata como una opción no válida
+    pub fn load_scene(&self, texture_manager: &mut TextureManager) -> Scene {
+        let terrain = self.terrain.map(|size| TerrainSettings {
+            vegetation: self.vegetation,
+            ..TerrainSettings::new(size, self.seed)
+        });
+        let mut scene = build_diorama(texture_manager, terrain.as_ref()).unwrap_or_else(|error| invalid(&error));
+        if let Some(file) = &self.gltf {
+            scene
+                .import_gltf(file, self.gltf_offset, self.gltf_scale, texture_manager)
+                .unwrap_or_else(|error| invalid(&error));
+        }
+        if let Some(file) = &self.vox {
+            scene.import_vox(file, self.vox_offset).unwrap_or_else(|error| invalid(&error));
+        }
+        if let Some(file) = &self.schematic {
+            let materials = BlockMaterials::load(&self.block_materials, texture_manager).unwrap_or_else(|error| invalid(&error));
+            scene
+                .import_schematic(file, self.schematic_offset, &materials)
+                .unwrap_or_else(|error| invalid(&error));
+        }
+        scene
+    }
+
+    // Aplica la cámara importada, el marcador, la proyección y el FOV pedidos a la cámara inicial
+    pub fn apply_to_camera(&self, camera: &mut Camera, bookmarks: &Bookmarks, scene_cameras: &[Camera]) {
+        if let Some(index) = self.gltf_camera {
+            let imported = scene_cameras
+                .get(index.wrapping_sub(1))
+                .unwrap_or_else(|| invalid(&format!("La escena importada no tiene cámara {}", index)));
+            camera.set_view(imported.eye, imported.center, imported.up);
+            camera.set_projection(imported.projection);
+            camera.set_fov(imported.fov);
+        }
+        if let Some(slot) = self.bookmark {
+            let bookmark = bookmarks
+                .get(slot)
+                .unwrap_or_else(|| invalid(&format!("No hay ningún marcador guardado en la ranura {}", slot)));
+            bookmark.apply(camera);
+        }
+        if let Some(projection) = self.projection {
+            camera.set_projection(projection);
+        }
+        if let Some(fov_degrees) = self.fov_degrees {
+            camera.set_fov(fov_degrees.to_radians());
+        }
+        if let Some(aperture) = self.aperture {
+            camera.set_aperture(aperture);
+        }
+        if let Some(focus_distance) = self.focus_distance {
+            camera.set_focus_distance(focus_distance);
+        }
+        if let Some(layout) = self.stereo {
+            camera.set_stereo(Some(self.stereo_settings(layout)));
+        }
+    }
+
+    // Ajustes estéreo con la separación y convergencia pedidas (o las de por defecto)
+    pub fn stereo_settings(&self, layout: StereoLayout) -> StereoSettings {
+        let mut stereo = StereoSettings::new(layout);
+        if let Some(eye_separation) = self.eye_separation {
+            stereo.eye_separation = eye_separation.max(0.0);
+        }
+        stereo.convergence = self.convergence;
+        stereo
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| invalid(&format!("Valor no válido para {}: {}", name, value)))
+}
+
+// Vector en formato `x,y,z`
+fn parse_vector(name: &str, value: &str) -> Vector3 {
+    let components: Vec<f32> = value.split(',').map(|component| parse_number(name, component.trim())).collect();
+    match components[..] {
+        [x, y, z] => Vector3::new(x, y, z),
+        _ => invalid(&format!("{} espera x,y,z: {}", name, value)),
+    }
+}
+
+// Posición de bloque en formato `x,y,z` (se redondea)
+fn parse_block(name: &str, value: &str) -> BlockPos {
+    let position = parse_vector(name, value);
+    (position.x.round() as i32, position.y.round() as i32, position.z.round() as i32)
+}
+
+fn invalid(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!(
+        "Uso: Proyect_2 [--headless] [--output archivo.png] [--width n] [--height n] \
+         [--quality potato|low|medium|high|ultra] [--target-fps n] \
+         [--projection perspective|orthographic|equirectangular|cylindrical] [--fov grados] [--aperture radio] [--focus-distance d] \
+         [--path recorrido.txt | --turntable segundos] [--fps n] [--frames-dir carpeta] \
+         [--bookmarks archivo] [--bookmark 1-9] \
+         [--stereo sbs|ou|anaglyph] [--ipd d] [--convergence d] \
+         [--gltf modelo.gltf|.glb] [--gltf-at x,y,z] [--gltf-scale s] [--gltf-camera n] \
+         [--terrain tamaño] [--seed n] [--vegetation separación] \
+         [--vox modelo.vox] [--vox-at x,y,z] [--export-vox archivo.vox] [--export-mesh archivo.obj|.gltf] \
+         [--schematic estructura.schem|.nbt] [--schematic-at x,y,z] [--block-materials tabla.txt] [--world mundo.txt]"
+    );
+    std::process::exit(2);
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+// Límites de la escala de resolución en modo automático
+const MIN_SCALE: f32 = 0.1;
+const MAX_SCALE: f32 = 1.0;
+// Suavizado exponencial del tiempo de frame medido
+const FRAME_TIME_SMOOTHING: f32 = 0.3;
+// Fracción del ajuste calculado que se aplica en cada frame (evita oscilaciones)
+const ADJUST_RATE: f32 = 0.5;
+// Cambio relativo mínimo antes de reasignar el framebuffer
+const RESIZE_THRESHOLD: f32 = 0.05;
+
+// Controlador que ajusta la escala de resolución para acercarse a un FPS objetivo
+pub struct DynamicResolution {
+    pub enabled: bool,
+    target_fps: f32,
+    scale: f32,
+    smoothed_frame_time: Option<f32>,
+}
+
+impl DynamicResolution {
+    pub fn new(target_fps: f32, initial_scale: f32) -> Self {
+        DynamicResolution {
+            enabled: false,
+            target_fps: target_fps.max(1.0),
+            scale: initial_scale.clamp(MIN_SCALE, MAX_SCALE),
+            smoothed_frame_time: None,
+        }
+    }
+
+    pub fn target_fps(&self) -> f32 {
+        self.target_fps
+    }
+
+    pub fn set_target_fps(&mut self, fps: f32) {
+        self.target_fps = fps.max(1.0);
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    // Arranca el modo automático desde la escala que se está usando en ese momento
+    pub fn start(&mut self, current_scale: f32) {
+        self.enabled = true;
+        self.scale = current_scale.clamp(MIN_SCALE, MAX_SCALE);
+        self.smoothed_frame_time = None;
+    }
+
+    // Registra la duración del último frame y devuelve la nueva escala si cambió lo suficiente
+    pub fn update(&mut self, frame_time: Duration) -> Option<f32> {
+        if !self.enabled {
+            return None;
+        }
+
+        let frame_time = frame_time.as_secs_f32().max(1e-4);
+        let smoothed = match self.smoothed_frame_time {
+            Some(previous) => previous + (frame_time - previous) * FRAME_TIME_SMOOTHING,
+            None => frame_time,
+        };
+        self.smoothed_frame_time = Some(smoothed);
+
+        // El coste del render es proporcional al número de píxeles (escala al cuadrado)
+        let target_time = 1.0 / self.target_fps;
+        let ideal_scale = (self.scale * (target_time / smoothed).sqrt()).clamp(MIN_SCALE, MAX_SCALE);
+        let new_scale = self.scale + (ideal_scale - self.scale) * ADJUST_RATE;
+
+        if ((new_scale - self.scale) / self.scale).abs() < RESIZE_THRESHOLD {
+            return None;
+        }
+
+        self.scale = new_scale;
+        // El siguiente frame se mide con la nueva resolución
+        self.smoothed_frame_time = None;
+        Some(new_scale)
+    }
+}
@@ -0,0 +1,507 @@
+use raylib::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use crate::camera::{Camera, CameraMode};
+use crate::cube::{Cube, Vec3};
+use crate::faces::{FACE_DIRECTIONS, grid_position, is_enclosed};
+use crate::history::{BlockChange, EditHistory};
+use crate::material::Material;
+use crate::primitives::{Group, Primitive, VoxelWorld};
+use crate::scene::emissive_sources_from;
+use crate::structures::BlockPos;
+use crate::textures::TextureManager;
+use crate::world_file::{load_world, save_world};
+use crate::{EmissiveSource, closest_hit};
+
+// Bloques que puede tocar como mucho una operación de caja (32³)
+const MAX_BOX_BLOCKS: i64 = 32 * 32 * 32;
+
+// Bloque y cara que hay bajo el cursor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockTarget {
+    pub position: BlockPos,
+    pub face: usize,
+}
+
+impl BlockTarget {
+    // Posición donde se coloca un bloque nuevo: pegado a la cara apuntada
+    pub fn adjacent(&self) -> BlockPos {
+        let (x, y, z) = self.position;
+        let (dx, dy, dz) = FACE_DIRECTIONS[self.face];
+        (x + dx, y + dy, z + dz)
+    }
+}
+
+// Lo que hace un clic del modo edición
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditAction {
+    Remove,
+    Place,
+    Paint,
+}
+
+impl EditAction {
+    // Bloque sobre el que actúa: el apuntado o, al poner, el hueco pegado a su cara
+    pub fn position(self, target: &BlockTarget) -> BlockPos {
+        match self {
+            EditAction::Place => target.adjacent(),
+            EditAction::Remove | EditAction::Paint => target.position,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EditAction::Remove => "quitar",
+            EditAction::Place => "poner",
+            EditAction::Paint => "pintar",
+        }
+    }
+}
+
+// Escena con sus bloques editables. Los cubos unitarios de la rejilla (también los de grupos como el
+// terreno) pasan a un mundo de vóxeles por trozos, que es lo que se edita; el resto de primitivas
+// queda tal cual
+pub struct EditableWorld {
+    pub objects: Vec<Primitive>,
+    // Fuentes que se renderizan: las de la escena seguidas de las de los bloques
+    pub emissive_sources: Vec<EmissiveSource>,
+    // Fuentes que no son de bloques de la rejilla (antorchas, luces de glTF, mallas...); no se tocan al editar
+    scene_sources: Vec<EmissiveSource>,
+    // Fuente de cada bloque emisivo que se ve
+    block_sources: BTreeMap<BlockPos, EmissiveSource>,
+    // Posición en `objects` del mundo de vóxeles
+    voxels: usize,
+    // Materiales de los bloques de la escena, en el orden en que aparecen
+    palette: Vec<Material>,
+}
+
+impl EditableWorld {
+    pub fn new(objects: Vec<Primitive>, emissive_sources: Vec<EmissiveSource>) -> Self {
+        let mut voxels = VoxelWorld::new();
+        let mut palette = Vec::new();
+        let mut extracted_sources = Vec::new();
+        let mut objects = extract_blocks(objects, &mut voxels, &mut palette, &mut extracted_sources);
+        objects.push(Primitive::from(voxels));
+
+        // Las fuentes de los bloques pasan a llevarse por posición: se quita de la escena la que generó
+        // cada cubo sacado a la rejilla, y solo esa
+        let mut scene_sources = emissive_sources;
+        for source in extracted_sources {
+            if let Some(index) = scene_sources.iter().position(|known| *known == source) {
+                scene_sources.remove(index);
+            }
+        }
+        let mut world = EditableWorld {
+            voxels: objects.len() - 1,
+            objects,
+            emissive_sources: Vec::new(),
+            scene_sources,
+            block_sources: BTreeMap::new(),
+            palette,
+        };
+
+        // Los bloques emisivos encerrados no iluminan
+        let emissive: Vec<BlockPos> = world.voxels().emissive_blocks().collect();
+        for position in emissive {
+            world.update_emitter(position);
+        }
+        world.collect_sources();
+        world
+    }
+
+    pub fn voxels(&self) -> &VoxelWorld {
+        match &self.objects[self.voxels] {
+            Primitive::Voxels(voxels) => voxels,
+            _ => unreachable!("El mundo de vóxeles no está en su sitio"),
+        }
+    }
+
+    // Si el hilo de render todavía usa una copia del mundo, esta se separa de ella; solo se duplican
+    // los trozos que se tocan
+    fn voxels_mut(&mut self) -> &mut VoxelWorld {
+        match &mut self.objects[self.voxels] {
+            Primitive::Voxels(voxels) => Arc::make_mut(voxels),
+            _ => unreachable!("El mundo de vóxeles no está en su sitio"),
+        }
+    }
+
+    // Copia de lo que se renderiza, para que el hilo de render no retenga el cerrojo durante el frame.
+    // El mundo de vóxeles, las mallas y los grupos van en Arc y no se duplican
+    pub fn snapshot(&self) -> (Vec<Primitive>, Vec<EmissiveSource>) {
+        (self.objects.clone(), self.emissive_sources.clone())
+    }
+
+    pub fn palette(&self) -> &[Material] {
+        &self.palette
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.voxels().block_count()
+    }
+
+    // Bloque y cara que toca primero el rayo; None si lo primero que toca no es un bloque
+    pub fn pick(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<BlockTarget> {
+        let intersect = closest_hit(ray_origin, ray_direction, &self.objects)?;
+        // Medio bloque hacia dentro desde el punto de impacto está el centro del bloque
+        let inside = intersect.point - intersect.normal * 0.5;
+        let position = (inside.x.round() as i32, inside.y.round() as i32, inside.z.round() as i32);
+        let normal = (
+            intersect.normal.x.round() as i32,
+            intersect.normal.y.round() as i32,
+            intersect.normal.z.round() as i32,
+        );
+        let face = FACE_DIRECTIONS.iter().position(|&direction| direction == normal)?;
+        self.voxels().get(position).is_some().then_some(BlockTarget { position, face })
+    }
+
+    // Guarda todos los bloques en un archivo de mundo. Devuelve cuántas tiras se escribieron
+    pub fn save(&self, path: &str) -> Result<usize, String> {
+        save_world(path, self.blocks())
+    }
+
+    // Sustituye los bloques por los de un mundo guardado. Devuelve cuántos bloques tiene
+    pub fn load(&mut self, path: &str, texture_manager: &mut TextureManager) -> Result<usize, String> {
+        let blocks = load_world(path, texture_manager)?;
+        let count = blocks.len();
+        self.replace_blocks(blocks);
+        Ok(count)
+    }
+
+    // Bloques del mundo con su material, en cualquier orden
+    fn blocks(&self) -> impl Iterator<Item = (BlockPos, &Material)> {
+        self.voxels().blocks()
+    }
+
+    // Cambios que hace una acción en la caja entre dos esquinas (un solo bloque si coinciden): quitar
+    // vacía la caja, poner rellena solo los huecos y pintar repinta solo los bloques que ya hay
+    pub fn box_edits(
+        &self,
+        action: EditAction,
+        from: BlockPos,
+        to: BlockPos,
+        material: Option<&Material>,
+    ) -> Result<Vec<(BlockPos, Option<Material>)>, String> {
+        let (min, max) = (
+            (from.0.min(to.0), from.1.min(to.1), from.2.min(to.2)),
+            (from.0.max(to.0), from.1.max(to.1), from.2.max(to.2)),
+        );
+        let volume = (max.0 - min.0 + 1) as i64 * (max.1 - min.1 + 1) as i64 * (max.2 - min.2 + 1) as i64;
+        if volume > MAX_BOX_BLOCKS {
+            return Err(format!("La caja tiene {} bloques; el máximo es {}", volume, MAX_BOX_BLOCKS));
+        }
+
+        let mut edits = Vec::new();
+        for y in min.1..=max.1 {
+            for z in min.2..=max.2 {
+                for x in min.0..=max.0 {
+                    let position = (x, y, z);
+                    let current = self.voxels().get(position);
+                    let edit = match (action, current, material) {
+                        (EditAction::Remove, Some(_), _) => Some(None),
+                        (EditAction::Place, None, Some(material)) => Some(Some(material.clone())),
+                        (EditAction::Paint, Some(current), Some(material)) if current != material => {
+                            Some(Some(material.clone()))
+                        }
+                        _ => None,
+                    };
+                    if let Some(edit) = edit {
+                        edits.push((position, edit));
+                    }
+                }
+            }
+        }
+        Ok(edits)
+    }
+
+    // Aplica varios cambios de una vez (None quita el bloque) y actualiza las fuentes emisivas al
+    // final. Devuelve los cambios que hubo de verdad, para poder deshacerlos
+    pub fn edit(&mut self, edits: impl IntoIterator<Item = (BlockPos, Option<Material>)>) -> Vec<BlockChange> {
+        let mut changes = Vec::new();
+        for (position, material) in edits {
+            let before = self.voxels().get(position).cloned();
+            if before == material {
+                continue;
+            }
+            if let Some(material) = &material
+                && !self.palette.contains(material)
+            {
+                self.palette.push(material.clone());
+            }
+            self.voxels_mut().set(position, material.as_ref());
+            changes.push(BlockChange { position, before, after: material });
+        }
+        self.refresh(&changes);
+        changes
+    }
+
+    fn replace_blocks(&mut self, blocks: Vec<(BlockPos, Material)>) -> Vec<BlockChange> {
+        let kept: HashSet<BlockPos> = blocks.iter().map(|(position, _)| *position).collect();
+        let removed: Vec<BlockPos> = self
+            .blocks()
+            .map(|(position, _)| position)
+            .filter(|position| !kept.contains(position))
+            .collect();
+        let edits = removed
+            .into_iter()
+            .map(|position| (position, None))
+            .chain(blocks.into_iter().map(|(position, material)| (position, Some(material))));
+        self.edit(edits)
+    }
+
+    // Un bloque cambiado puede tapar o destapar a sus vecinos: sus fuentes emisivas se actualizan.
+    // Las caras se calculan al impactar, así que no hay nada más que reconstruir
+    fn refresh(&mut self, changes: &[BlockChange]) {
+        let mut affected = HashSet::new();
+        for change in changes {
+            let (x, y, z) = change.position;
+            affected.insert(change.position);
+            affected.extend(FACE_DIRECTIONS.iter().map(|&(dx, dy, dz)| (x + dx, y + dy, z + dz)));
+        }
+
+        // Solo tienen fuente los bloques emisivos; los cambiados pueden haber perdido la suya
+        let changed: HashSet<BlockPos> = changes.iter().map(|change| change.position).collect();
+        for position in affected {
+            let emissive = self.voxels().get(position).is_some_and(|material| material.emission_strength > 0.0);
+            if emissive || changed.contains(&position) {
+                self.update_emitter(position);
+            }
+        }
+        self.collect_sources();
+    }
+
+    // Un bloque emisivo ilumina mientras se vea; si queda encerrado o se quita, su fuente desaparece
+    fn update_emitter(&mut self, position: BlockPos) {
+        self.block_sources.remove(&position);
+        let voxels = self.voxels();
+        if let Some(material) = voxels.get(position)
+            && material.emission_strength > 0.0
+            && !is_enclosed(position, |position| voxels.get(position))
+        {
+            let (x, y, z) = position;
+            let cube = Cube::new(Vec3::new(x as f32, y as f32, z as f32), Vec3::new(0.5, 0.5, 0.5), material.clone());
+            if let Some(source) = emissive_sources_from(&[Primitive::from(cube)]).pop() {
+                self.block_sources.insert(position, source);
+            }
+        }
+    }
+
+    // Vuelve a juntar las fuentes que se renderizan tras cambiar las de los bloques
+    fn collect_sources(&mut self) {
+        self.emissive_sources = self.scene_sources.iter().chain(self.block_sources.values()).cloned().collect();
+    }
+}
+
+// Saca los bloques de la rejilla de los objetos (entrando en los grupos) al mundo de vóxeles y
+// devuelve lo que queda; en `sources` deja las fuentes que tenían los bloques emisivos sacados
+fn extract_blocks(
+    objects: Vec<Primitive>,
+    voxels: &mut VoxelWorld,
+    palette: &mut Vec<Material>,
+    sources: &mut Vec<EmissiveSource>,
+) -> Vec<Primitive> {
+    objects
+        .into_iter()
+        .filter_map(|object| match object {
+            Primitive::Cube(cube) => match grid_position(&cube) {
+                Some(position) => {
+                    if !palette.contains(&cube.material) {
+                        palette.push(cube.material.clone());
+                    }
+                    voxels.set(position, Some(&cube.material));
+                    // La fuente que la escena generó para este cubo
+                    sources.extend(emissive_sources_from(&[Primitive::Cube(cube)]));
+                    None
+                }
+                None => Some(Primitive::Cube(cube)),
+            },
+            Primitive::Group(group) => {
+                let rest = extract_blocks(group.objects().cloned().collect(), voxels, palette, sources);
+                (!rest.is_empty()).then(|| Primitive::from(Group::new(rest)))
+            }
+            object => Some(object),
+        })
+        .collect()
+}
+
+// Estado del modo edición de la ventana: material elegido, bloque apuntado e historial de cambios
+pub struct BlockEditor {
+    pub enabled: bool,
+    pub selected: usize,
+    pub target: Option<BlockTarget>,
+    pub history: EditHistory,
+    // Primera esquina de una operación de caja (Mayús+clic) y la acción con la que se marcó
+    pub corner: Option<(EditAction, BlockPos)>,
+    // Trazo de pintura en curso (botón central o R mantenidos)
+    pub painting: bool,
+}
+
+impl BlockEditor {
+    pub fn new() -> Self {
+        BlockEditor {
+            enabled: false,
+            selected: 0,
+            target: None,
+            history: EditHistory::new(),
+            corner: None,
+            painting: false,
+        }
+    }
+
+    // Al salir del modo edición se cierra el trazo y se olvida la esquina marcada
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.target = None;
+        self.corner = None;
+        self.painting = false;
+        self.history.end_stroke();
+    }
+
+    // Avanza (o retrocede) por la paleta de materiales
+    pub fn cycle_material(&mut self, step: i32, palette_size: usize) {
+        if palette_size > 0 {
+            self.selected = (self.selected as i32 + step).rem_euclid(palette_size as i32) as usize;
+        }
+    }
+
+    // Resalta el bloque y la cara apuntados y muestra el material elegido
+    pub fn draw_overlay(
+        &self,
+        renderer: &mut RaylibDrawHandle,
+        camera: &Camera,
+        palette: &[Material],
+        texture_manager: &TextureManager,
+        width: i32,
+        height: i32,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let aspect_ratio = width as f32 / height as f32;
+        let to_screen = |point: Vector3| {
+            camera.project_point(point, aspect_ratio).map(|(screen_x, screen_y)| {
+                Vector2::new((screen_x + 1.0) * 0.5 * width as f32, (1.0 - screen_y) * 0.5 * height as f32)
+            })
+        };
+
+        // En vuelo libre se apunta con el centro de la pantalla
+        if camera.mode == CameraMode::FreeFly {
+            let (center_x, center_y) = (width / 2, height / 2);
+            renderer.draw_line(center_x - 8, center_y, center_x + 8, center_y, Color::WHITE);
+            renderer.draw_line(center_x, center_y - 8, center_x, center_y + 8, Color::WHITE);
+        }
+
+        if let Some(target) = self.target {
+            let (x, y, z) = target.position;
+            let corner = |dx: f32, dy: f32, dz: f32| Vector3::new(x as f32 + dx, y as f32 + dy, z as f32 + dz);
+            draw_box_edges(renderer, &to_screen, corner(-0.5, -0.5, -0.5), corner(0.5, 0.5, 0.5), 2.0, Color::WHITE);
+
+            // Caja que se editará al hacer Mayús+clic aquí
+            if let Some((action, (cx, cy, cz))) = self.corner {
+                let (tx, ty, tz) = action.position(&target);
+                let min = Vector3::new(cx.min(tx) as f32, cy.min(ty) as f32, cz.min(tz) as f32) - Vector3::one() * 0.5;
+                let max = Vector3::new(cx.max(tx) as f32, cy.max(ty) as f32, cz.max(tz) as f32) + Vector3::one() * 0.5;
+                draw_box_edges(renderer, &to_screen, min, max, 2.0, Color::SKYBLUE);
+            }
+
+            // Contorno de la cara apuntada, un poco hacia fuera para que no se pierda en la arista
+            let (nx, ny, nz) = FACE_DIRECTIONS[target.face];
+            let normal = Vector3::new(nx as f32, ny as f32, nz as f32);
+            let (t1, t2) = if nx != 0 {
+                (Vector3::new(0.0, 0.5, 0.0), Vector3::new(0.0, 0.0, 0.5))
+            } else if ny != 0 {
+                (Vector3::new(0.5, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.5))
+            } else {
+                (Vector3::new(0.5, 0.0, 0.0), Vector3::new(0.0, 0.5, 0.0))
+            };
+            let center = corner(0.0, 0.0, 0.0) + normal * 0.51;
+            let face: Vec<Option<Vector2>> = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .iter()
+                .map(|&(s1, s2)| to_screen(center + t1 * s1 + t2 * s2))
+                .collect();
+            for index in 0..4 {
+                if let (Some(a), Some(b)) = (face[index], face[(index + 1) % 4]) {
+                    renderer.draw_line_ex(a, b, 3.0, Color::YELLOW);
+                }
+            }
+        }
+
+        let material = palette
+            .get(self.selected)
+            .map(material_label)
+            .unwrap_or_else(|| "sin materiales".to_string());
+        let mut status = format!("EDICIÓN - material {}/{}: {}", self.selected + 1, palette.len(), material);
+        if let Some((action, (x, y, z))) = self.corner {
+            status.push_str(&format!(" | caja para {} desde ({}, {}, {})", action.label(), x, y, z));
+        }
+        if self.history.has_unsaved() {
+            status.push_str(" | cambios sin guardar");
+        }
+        let help = format!(
+            "clic izq.: quitar  der.: poner  central/R: pintar  Mayús+clic: caja  rueda: material  Ctrl+Z/Y: deshacer{}/rehacer{}  Ctrl+S: guardar",
+            if self.history.can_undo() { "" } else { " (nada)" },
+            if self.history.can_redo() { "" } else { " (nada)" },
+        );
+        renderer.draw_rectangle(0, height - 56, width, 56, Color::new(0, 0, 0, 160));
+        renderer.draw_text(&status, 10, height - 50, 20, Color::WHITE);
+        renderer.draw_text(&help, 10, height - 26, 18, Color::LIGHTGRAY);
+        if let Some(material) = palette.get(self.selected) {
+            let swatch = material_color(material, texture_manager);
+            renderer.draw_rectangle(width - 34, height - 50, 26, 26, swatch);
+        }
+    }
+}
+
+// Aristas de la caja entre dos esquinas: las que parten de cuatro esquinas alternas hacia +X, +Y y +Z
+fn draw_box_edges(
+    renderer: &mut RaylibDrawHandle,
+    to_screen: &impl Fn(Vector3) -> Option<Vector2>,
+    min: Vector3,
+    max: Vector3,
+    thickness: f32,
+    color: Color,
+) {
+    let pick = |high: bool, low: f32, high_value: f32| if high { high_value } else { low };
+    let corner = |hx: bool, hy: bool, hz: bool| Vector3::new(pick(hx, min.x, max.x), pick(hy, min.y, max.y), pick(hz, min.z, max.z));
+    for &(hx, hy, hz) in &[(false, false, false), (true, false, true), (true, true, false), (false, true, true)] {
+        let start = corner(hx, hy, hz);
+        for end in [corner(!hx, hy, hz), corner(hx, !hy, hz), corner(hx, hy, !hz)] {
+            if let (Some(a), Some(b)) = (to_screen(start), to_screen(end)) {
+                renderer.draw_line_ex(a, b, thickness, color);
+            }
+        }
+    }
+}
+
+// Nombre legible de un material: su textura o su color
+pub fn material_label(material: &Material) -> String {
+    let base = match &material.texture_id {
+        Some(texture) => std::path::Path::new(texture)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| texture.clone()),
+        None => {
+            let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            let diffuse = material.diffuse;
+            format!("#{:02x}{:02x}{:02x}", channel(diffuse.x), channel(diffuse.y), channel(diffuse.z))
+        }
+    };
+    if material.emission_strength > 0.0 {
+        format!("{} (emisivo)", base)
+    } else if material.albedo[3] > 0.0 {
+        format!("{} (transparente)", base)
+    } else {
+        base
+    }
+}
+
+// Color de la muestra de la paleta: el medio de la textura, o el difuso si no tiene
+fn material_color(material: &Material, texture_manager: &TextureManager) -> Color {
+    let color = material
+        .texture_id
+        .as_deref()
+        .and_then(|texture| texture_manager.average_color(texture))
+        .unwrap_or(material.diffuse);
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::new(channel(color.x), channel(color.y), channel(color.z), 255)
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::cube::Cube;
+use crate::material::Material;
+use crate::structures::BlockPos;
+
+// Las seis caras de un bloque, como desplazamiento hacia el vecino que la tapa.
+// El bit i de una máscara de caras corresponde a FACE_DIRECTIONS[i]
+pub const FACE_DIRECTIONS: [BlockPos; 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+pub const ALL_FACES: u8 = 0b11_1111;
+
+// Posición del cubo en la rejilla si es un bloque unitario alineado con ella
+pub fn grid_position(cube: &Cube) -> Option<BlockPos> {
+    let Cube { center, half, .. } = cube;
+    let on_grid = |value: f32| (value - value.round()).abs() < 1e-3;
+    ([half.x, half.y, half.z].iter().all(|&half| (half - 0.5).abs() < 1e-3)
+        && [center.x, center.y, center.z].into_iter().all(on_grid))
+    .then(|| (center.x.round() as i32, center.y.round() as i32, center.z.round() as i32))
+}
+
+// Un material tapa lo que tiene detrás si no deja pasar la luz (agua, hielo y portal sí la dejan)
+pub fn is_opaque(material: &Material) -> bool {
+    material.albedo[3] <= 0.0
+}
+
+// Qué bloque ocupa cada posición de la rejilla. Los cubos que no están en ella ni tapan ni se tapan
+pub struct Occupancy<'a> {
+    blocks: HashMap<BlockPos, &'a Material>,
+}
+
+impl<'a> Occupancy<'a> {
+    // Si dos cubos comparten posición cuenta el último
+    pub fn new(cubes: &[&'a Cube]) -> Self {
+        let blocks = cubes
+            .iter()
+            .filter_map(|cube| grid_position(cube).map(|position| (position, &cube.material)))
+            .collect();
+        Occupancy { blocks }
+    }
+
+    pub fn exposed_faces(&self, cube: &Cube) -> u8 {
+        match grid_position(cube) {
+            Some(position) => exposed_faces(&cube.material, position, |position| self.blocks.get(&position).copied()),
+            None => ALL_FACES,
+        }
+    }
+
+    pub fn is_enclosed(&self, cube: &Cube) -> bool {
+        grid_position(cube).is_some_and(|position| is_enclosed(position, |position| self.blocks.get(&position).copied()))
+    }
+}
+
+// Caras visibles de un bloque, con `neighbour` dando el material de cada posición ocupada. Una cara
+// queda oculta si el vecino es opaco, o si ambos son del mismo material transparente (el agua no
+// muestra las caras entre sus bloques)
+pub fn exposed_faces<'a>(material: &Material, position: BlockPos, neighbour: impl Fn(BlockPos) -> Option<&'a Material>) -> u8 {
+    let mut mask = 0;
+    for (face, neighbour) in neighbours(position, neighbour).into_iter().enumerate() {
+        if !hides_face(neighbour, material) {
+            mask |= 1 << face;
+        }
+    }
+    mask
+}
+
+// Si el bloque vecino (None es aire) tapa la cara que le toca de un bloque de ese material
+pub fn hides_face(neighbour: Option<&Material>, material: &Material) -> bool {
+    neighbour.is_some_and(|neighbour| is_opaque(neighbour) || neighbour == material)
+}
+
+// Bloque rodeado de vecinos opacos por sus seis caras: no se ve desde ningún sitio
+pub fn is_enclosed<'a>(position: BlockPos, neighbour: impl Fn(BlockPos) -> Option<&'a Material>) -> bool {
+    neighbours(position, neighbour)
+        .into_iter()
+        .all(|neighbour| neighbour.is_some_and(is_opaque))
+}
+
+fn neighbours<'a>((x, y, z): BlockPos, neighbour: impl Fn(BlockPos) -> Option<&'a Material>) -> [Option<&'a Material>; 6] {
+    FACE_DIRECTIONS.map(|(dx, dy, dz)| neighbour((x + dx, y + dy, z + dz)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Vec3;
+    use crate::test_support::plain;
+    use raylib::prelude::Vector3;
+
+    fn transparent(red: f32) -> Material {
+        Material::new(Vector3::new(red, 0.4, 0.9), 50.0, [0.1, 0.3, 0.0, 0.6], 1.33, None)
+    }
+
+    fn block(position: BlockPos, material: &Material) -> Cube {
+        let (x, y, z) = position;
+        Cube::new(Vec3::new(x as f32, y as f32, z as f32), Vec3::new(0.5, 0.5, 0.5), material.clone())
+    }
+
+    fn face(direction: BlockPos) -> u8 {
+        1 << FACE_DIRECTIONS.iter().position(|&face| face == direction).unwrap()
+    }
+
+    #[test]
+    fn opaque_neighbours_hide_faces() {
+        let stone = plain(0.5, 0.5, 0.5);
+        let water = transparent(0.1);
+        let cubes = [block((0, 0, 0), &stone), block((1, 0, 0), &stone), block((0, -1, 0), &stone), block((0, 1, 0), &water)];
+        let occupancy = Occupancy::new(&cubes.iter().collect::<Vec<_>>());
+        // El agua de encima no tapa la piedra
+        assert_eq!(occupancy.exposed_faces(&cubes[0]), ALL_FACES & !face((1, 0, 0)) & !face((0, -1, 0)));
+        // La piedra de debajo sí tapa la cara inferior del agua
+        assert_eq!(occupancy.exposed_faces(&cubes[3]), ALL_FACES & !face((0, -1, 0)));
+    }
+
+    #[test]
+    fn same_transparent_material_hides_shared_faces() {
+        let water = transparent(0.1);
+        let glass = transparent(0.9);
+        let cubes = [block((0, 0, 0), &water), block((0, 0, 1), &water), block((-1, 0, 0), &glass)];
+        let occupancy = Occupancy::new(&cubes.iter().collect::<Vec<_>>());
+        // Entre dos bloques de agua no hay cara; entre agua y cristal, sí
+        assert_eq!(occupancy.exposed_faces(&cubes[0]), ALL_FACES & !face((0, 0, 1)));
+        assert_eq!(occupancy.exposed_faces(&cubes[1]), ALL_FACES & !face((0, 0, -1)));
+        assert_eq!(occupancy.exposed_faces(&cubes[2]), ALL_FACES);
+    }
+
+    #[test]
+    fn enclosed_only_behind_six_opaque_neighbours() {
+        let stone = plain(0.5, 0.5, 0.5);
+        let water = transparent(0.1);
+        let mut cubes = vec![block((0, 0, 0), &stone)];
+        cubes.extend(FACE_DIRECTIONS.iter().map(|&position| block(position, &stone)));
+        let occupancy = Occupancy::new(&cubes.iter().collect::<Vec<_>>());
+        assert!(occupancy.is_enclosed(&cubes[0]));
+        assert_eq!(occupancy.exposed_faces(&cubes[0]), 0);
+        assert!(!occupancy.is_enclosed(&cubes[1]));
+
+        // Un vecino de agua deja ver el bloque
+        cubes[1] = block(FACE_DIRECTIONS[0], &water);
+        let occupancy = Occupancy::new(&cubes.iter().collect::<Vec<_>>());
+        assert!(!occupancy.is_enclosed(&cubes[0]));
+        assert_eq!(occupancy.exposed_faces(&cubes[0]), face(FACE_DIRECTIONS[0]));
+
+        // Agua rodeada de agua: no muestra ninguna cara, pero no cuenta como encerrada
+        let pool: Vec<Cube> = std::iter::once((0, 0, 0)).chain(FACE_DIRECTIONS).map(|position| block(position, &water)).collect();
+        let occupancy = Occupancy::new(&pool.iter().collect::<Vec<_>>());
+        assert_eq!(occupancy.exposed_faces(&pool[0]), 0);
+        assert!(!occupancy.is_enclosed(&pool[0]));
+    }
+
+    #[test]
+    fn off_grid_cubes_neither_hide_nor_are_hidden() {
+        let stone = plain(0.5, 0.5, 0.5);
+        let slab = Cube::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.5, 0.25, 0.5), stone.clone());
+        let shifted = Cube::new(Vec3::new(-1.0, 0.5, 0.0), Vec3::new(0.5, 0.5, 0.5), stone.clone());
+        let center = block((0, 0, 0), &stone);
+        assert_eq!(grid_position(&slab), None);
+        assert_eq!(grid_position(&shifted), None);
+        assert_eq!(grid_position(&center), Some((0, 0, 0)));
+
+        let cubes = [center, slab, shifted];
+        let occupancy = Occupancy::new(&cubes.iter().collect::<Vec<_>>());
+        assert_eq!(occupancy.exposed_faces(&cubes[0]), ALL_FACES);
+        assert_eq!(occupancy.exposed_faces(&cubes[1]), ALL_FACES);
+        assert!(!occupancy.is_enclosed(&cubes[1]));
+    }
+}
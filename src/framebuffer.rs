@@ -1,39 +1,87 @@
 use raylib::prelude::*;
 
+// Buffer de color en CPU; la textura de GPU solo se crea en el hilo de la ventana al presentar
 pub struct Framebuffer {
     pub width: u32,
     pub height: u32,
-    pub color_buffer: Image,
+    pub color_buffer: Vec<Color>,
     background_color: Color,
     current_color: Color,
+    texture: Option<Texture2D>,
+}
+
+// Las copias solo llevan los píxeles; cada una crea su propia textura si llega a presentarse
+impl Clone for Framebuffer {
+    fn clone(&self) -> Self {
+        Framebuffer {
+            width: self.width,
+            height: self.height,
+            color_buffer: self.color_buffer.clone(),
+            background_color: self.background_color,
+            current_color: self.current_color,
+            texture: None,
+        }
+    }
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32) -> Self {
-        let color_buffer = Image::gen_image_color(width as i32, height as i32, Color::BLACK);
+        let color_buffer = vec![Color::BLACK; (width * height) as usize];
         Framebuffer {
             width,
             height,
             color_buffer,
             background_color: Color::BLACK,
             current_color: Color::WHITE,
+            texture: None,
+        }
+    }
+
+    // Reasigna el buffer si la resolución cambió (el contenido anterior se descarta)
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == self.width && height == self.height {
+            return;
         }
+        self.width = width;
+        self.height = height;
+        self.color_buffer = vec![self.background_color; (width * height) as usize];
+    }
+
+    // Copia los píxeles de otro framebuffer conservando la textura propia
+    pub fn copy_from(&mut self, other: &Framebuffer) {
+        self.resize(other.width, other.height);
+        self.color_buffer.copy_from_slice(&other.color_buffer);
     }
 
     pub fn clear(&mut self) {
 
-        self.color_buffer.clear_background(self.background_color);
+        self.color_buffer.fill(self.background_color);
     }
 
     pub fn set_pixel(&mut self, x: u32, y: u32) {
         if x < self.width && y < self.height {
-            self.color_buffer.draw_pixel(x as i32, y as i32, self.current_color);
+            self.color_buffer[(y * self.width + x) as usize] = self.current_color;
         }
     }
 
     pub fn set_pixel_color(&mut self, x: u32, y: u32, color: Color) {
         if x < self.width && y < self.height {
-            self.color_buffer.draw_pixel(x as i32, y as i32, color);
+            self.color_buffer[(y * self.width + x) as usize] = color;
+        }
+    }
+
+    // Copia un bloque rectangular de píxeles (un tile ya terminado)
+    pub fn write_tile(&mut self, x: u32, y: u32, width: u32, height: u32, colors: &[Color]) {
+        for row in 0..height {
+            let target_y = y + row;
+            if target_y >= self.height {
+                break;
+            }
+            let visible = width.min(self.width.saturating_sub(x)) as usize;
+            let source = (row * width) as usize;
+            let target = (target_y * self.width + x) as usize;
+            self.color_buffer[target..target + visible]
+                .copy_from_slice(&colors[source..source + visible]);
         }
     }
 
@@ -45,40 +93,77 @@ impl Framebuffer {
         self.current_color = color;
     }
 
-    pub fn _render_to_file(&self, file_path: &str) {
-        self.color_buffer.export_image(file_path);
+    // Construye una imagen de Raylib con el contenido actual del buffer
+    pub fn to_image(&self) -> Image {
+        let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+        // gen_image_color crea una imagen R8G8B8A8, mismo layout que Color
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                self.color_buffer.as_ptr() as *const u8,
+                image.data() as *mut u8,
+                self.color_buffer.len() * 4,
+            );
+        }
+        image
+    }
+
+    pub fn render_to_file(&self, file_path: &str) {
+        self.to_image().export_image(file_path);
+    }
+
+    // Sube el buffer a la textura persistente; solo se vuelve a crear si cambió la resolución
+    fn upload_texture(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
+        let size_matches = self
+            .texture
+            .as_ref()
+            .is_some_and(|texture| texture.width() as u32 == self.width && texture.height() as u32 == self.height);
+
+        if !size_matches {
+            self.texture = window.load_texture_from_image(raylib_thread, &self.to_image()).ok();
+            return;
+        }
+
+        // Color es RGBA de 4 bytes, igual que el formato R8G8B8A8 de la textura
+        let pixels = unsafe {
+            std::slice::from_raw_parts(self.color_buffer.as_ptr() as *const u8, self.color_buffer.len() * 4)
+        };
+        if let Some(texture) = self.texture.as_mut() {
+            texture.update_texture(pixels).expect("No se pudo actualizar la textura del framebuffer");
+        }
     }
 
     pub fn swap_buffers(
-        &self,
+        &mut self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
-        fps: bool,
+        fps: Option<f32>,
         target_width: i32,
         target_height: i32,
+        overlay: impl FnOnce(&mut RaylibDrawHandle),
     ) {
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
+        self.upload_texture(window, raylib_thread);
+
+        if let Some(texture) = &self.texture {
             let mut renderer = window.begin_drawing(raylib_thread);
-            
+
             let dest_rect = Rectangle {
                 x: 0.0,
                 y: 0.0,
                 width: target_width as f32,
                 height: target_height as f32,
             };
-            
+
             let src_rect = Rectangle {
                 x: 0.0,
                 y: 0.0,
                 width: self.width as f32,
                 height: self.height as f32,
             };
-            
-            renderer.draw_texture_pro(&texture, src_rect, dest_rect, Vector2::zero(), 0.0, Color::WHITE);
 
-            if fps {
-                let num_fps = renderer.get_fps();
-                let fps_text = format!("FPS: {} | Resolution: {}x{}", num_fps, self.width, self.height);
+            renderer.draw_texture_pro(texture, src_rect, dest_rect, Vector2::zero(), 0.0, Color::WHITE);
+
+            if let Some(num_fps) = fps {
+                let fps_text = format!("FPS: {:.1} | Resolution: {}x{}", num_fps, self.width, self.height);
                 let font_size = 20;
                 let text_width = renderer.measure_text(&fps_text, font_size);
 
@@ -86,6 +171,9 @@ impl Framebuffer {
                 let y = 10;
                 renderer.draw_text(&fps_text, x, y, font_size, Color::WHITE);
             }
+
+            // Dibujo extra por encima del frame (p. ej. el resaltado del editor)
+            overlay(&mut renderer);
         }
     }
-}
\ No newline at end of file
+}
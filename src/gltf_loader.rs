@@ -0,0 +1,476 @@
+use base64::Engine;
+use gltf::camera::Projection as GltfProjection;
+use gltf::khr_lights_punctual::Kind;
+use gltf::material::AlphaMode;
+use raylib::prelude::{Color, Vector3};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::EmissiveSource;
+use crate::camera::{Camera, Projection};
+use crate::cube::Vec3;
+use crate::light::Light;
+use crate::material::Material;
+use crate::obj_loader::join_path;
+use crate::primitives::{Mesh, Primitive, Transform, Triangle};
+use crate::textures::TextureManager;
+
+// Distancia a la que se coloca el objetivo de las cámaras importadas (glTF solo da la orientación)
+const CAMERA_TARGET_DISTANCE: f32 = 10.0;
+// Distancia a la que se coloca la luz principal cuando viene de una luz direccional
+const SUN_DISTANCE: f32 = 40.0;
+// Alcance de las luces puntuales que no lo indican
+const DEFAULT_LIGHT_RANGE: f32 = 8.0;
+// Las intensidades de glTF van en candelas; este factor las lleva a la escala de las fuentes emisivas
+const LIGHT_INTENSITY_SCALE: f32 = 0.05;
+
+// Lo que aporta un archivo glTF al diorama, ya convertido a los tipos del trazador
+pub struct GltfImport {
+    // Una malla por cada nodo con geometría (las transformaciones ya van aplicadas)
+    pub objects: Vec<Primitive>,
+    // Luces puntuales y focos (los focos se tratan como puntuales)
+    pub emissive_sources: Vec<EmissiveSource>,
+    // Luz direccional, si la hay: sustituye a la luz principal
+    pub sun: Option<Light>,
+    pub cameras: Vec<Camera>,
+}
+
+// Carga un .gltf (con buffers e imágenes externos o embebidos) o un .glb. Toda la escena se
+// escala por `scale` y se desplaza a `offset`; las texturas se registran en el TextureManager
+pub fn load_gltf(path: &str, offset: Vector3, scale: f32, texture_manager: &mut TextureManager) -> Result<GltfImport, String> {
+    let bytes = std::fs::read(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+    import_gltf(&bytes, path, offset, scale, texture_manager)
+}
+
+// Convierte el contenido de un .gltf o .glb; `path` sitúa los archivos externos y da nombre a los errores
+fn import_gltf(
+    bytes: &[u8],
+    path: &str,
+    offset: Vector3,
+    scale: f32,
+    texture_manager: &mut TextureManager,
+) -> Result<GltfImport, String> {
+    let gltf = gltf::Gltf::from_slice(bytes).map_err(|error| format!("{}: {}", path, error))?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let buffers = gltf
+        .document
+        .buffers()
+        .map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or_else(|| format!("{}: falta el bloque binario del .glb", path)),
+            gltf::buffer::Source::Uri(uri) => read_uri(directory, uri),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let textures = load_images(&gltf.document, &buffers, path, directory, texture_manager)?;
+    let materials: Vec<Material> = gltf
+        .document
+        .materials()
+        .map(|material| convert_material(&material, &textures))
+        .collect();
+
+    let mut import = GltfImport {
+        objects: Vec::new(),
+        emissive_sources: Vec::new(),
+        sun: None,
+        cameras: Vec::new(),
+    };
+
+    let scene = gltf
+        .document
+        .default_scene()
+        .or_else(|| gltf.document.scenes().next())
+        .ok_or_else(|| format!("{}: el archivo no tiene escenas", path))?;
+    let placement = Transform::scale(Vector3::new(scale, scale, scale)).then(Transform::translation(offset));
+    for node in scene.nodes() {
+        visit_node(&node, &placement, &buffers, &materials, &mut import)?;
+    }
+
+    Ok(import)
+}
+
+fn visit_node(
+    node: &gltf::Node,
+    parent: &Transform,
+    buffers: &[Vec<u8>],
+    materials: &[Material],
+    import: &mut GltfImport,
+) -> Result<(), String> {
+    let world = Transform::from_columns(node.transform().matrix()).then(*parent);
+
+    if let Some(mesh) = node.mesh()
+        && let Some(mesh) = convert_mesh(&mesh, &world, buffers, materials)?
+    {
+        import.objects.push(mesh.into());
+    }
+    if let Some(camera) = node.camera() {
+        import.cameras.push(convert_camera(&camera, &world));
+    }
+    if let Some(light) = node.light() {
+        let position = world.transform_point(Vector3::zero());
+        let color = Vector3::new(light.color()[0], light.color()[1], light.color()[2]);
+        match light.kind() {
+            Kind::Directional => {
+                // Las luces de glTF apuntan hacia su -Z local
+                let direction = world.transform_vector(Vector3::new(0.0, 0.0, -1.0)).normalized();
+                import.sun = Some(Light::new(
+                    Vec3::from_vector3(position - direction * SUN_DISTANCE),
+                    Color::new(
+                        (color.x.clamp(0.0, 1.0) * 255.0) as u8,
+                        (color.y.clamp(0.0, 1.0) * 255.0) as u8,
+                        (color.z.clamp(0.0, 1.0) * 255.0) as u8,
+                        255,
+                    ),
+                    light.intensity(),
+                ));
+            }
+            Kind::Point | Kind::Spot { .. } => import.emissive_sources.push(EmissiveSource {
+                position,
+                color,
+                strength: light.intensity() * LIGHT_INTENSITY_SCALE,
+                radius: light.range().unwrap_or(DEFAULT_LIGHT_RANGE),
+            }),
+        }
+    }
+
+    for child in node.children() {
+        visit_node(&child, &world, buffers, materials, import)?;
+    }
+    Ok(())
+}
+
+// Todas las primitivas triangulares de una malla, en coordenadas del mundo, como una sola `Mesh`
+fn convert_mesh(mesh: &gltf::Mesh, world: &Transform, buffers: &[Vec<u8>], materials: &[Material]) -> Result<Option<Mesh>, String> {
+    let mut triangles = Vec::new();
+    let mut mesh_materials: Vec<Material> = Vec::new();
+    // Índice del material de glTF (None = material por defecto) -> índice dentro de la malla
+    let mut material_slots: HashMap<Option<usize>, usize> = HashMap::new();
+    let mesh_name = mesh.name().unwrap_or("sin nombre").to_string();
+
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            println!("Malla '{}': se ignora una primitiva que no es de triángulos", mesh_name);
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| data.as_slice()));
+        let positions: Vec<Vector3> = reader
+            .read_positions()
+            .ok_or_else(|| format!("Malla '{}': primitiva sin posiciones", mesh_name))?
+            .map(|[x, y, z]| world.transform_point(Vector3::new(x, y, z)))
+            .collect();
+        let normals: Option<Vec<Vector3>> = reader
+            .read_normals()
+            .map(|normals| normals.map(|[x, y, z]| world.transform_normal(Vector3::new(x, y, z))).collect());
+        // glTF tiene el origen de las UV arriba a la izquierda; la malla espera el de OBJ (abajo)
+        let uvs: Option<Vec<(f32, f32)>> = reader
+            .read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(|[u, v]| (u, 1.0 - v)).collect());
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+
+        let gltf_material = primitive.material().index();
+        let material = *material_slots.entry(gltf_material).or_insert_with(|| {
+            mesh_materials.push(gltf_material.map_or_else(|| convert_material(&primitive.material(), &[]), |index| materials[index].clone()));
+            mesh_materials.len() - 1
+        });
+
+        for corners in indices.chunks_exact(3) {
+            let corners = [corners[0] as usize, corners[1] as usize, corners[2] as usize];
+            if corners.iter().any(|index| *index >= positions.len()) {
+                return Err(format!("Malla '{}': índice de vértice fuera de rango", mesh_name));
+            }
+            triangles.push(Triangle {
+                positions: corners.map(|index| positions[index]),
+                normals: normals.as_ref().map(|normals| corners.map(|index| normals[index])),
+                uvs: uvs.as_ref().map(|uvs| corners.map(|index| uvs[index])),
+                material,
+            });
+        }
+    }
+
+    Ok((!triangles.is_empty()).then(|| Mesh::new(triangles, mesh_materials)))
+}
+
+// Metal/rugosidad de glTF aproximado con los pesos de Phong del trazador
+// [difuso, especular, reflexión, transparencia]
+fn convert_material(material: &gltf::Material, textures: &[String]) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, alpha] = pbr.base_color_factor();
+    let metallic = pbr.metallic_factor().clamp(0.0, 1.0);
+    let roughness = pbr.roughness_factor().clamp(0.0, 1.0);
+
+    let transmission = material
+        .transmission()
+        .map(|transmission| transmission.transmission_factor())
+        .unwrap_or(0.0);
+    let blended = if material.alpha_mode() == AlphaMode::Blend { 1.0 - alpha } else { 0.0 };
+    let transparency = transmission.max(blended).clamp(0.0, 1.0);
+    let reflectivity = metallic * (1.0 - roughness);
+    let albedo = [
+        0.9 * (1.0 - metallic * 0.8) * (1.0 - transparency),
+        (1.0 - roughness) * (0.2 + 0.6 * metallic),
+        reflectivity,
+        transparency,
+    ];
+    // Equivalencia habitual entre rugosidad y exponente de Blinn-Phong (alfa = rugosidad²)
+    let shininess = (2.0 / roughness.powi(4).max(1e-4) - 2.0).clamp(1.0, 200.0);
+    let refractive_index = if transparency > 0.0 { material.ior().unwrap_or(1.5) } else { 0.0 };
+
+    // Con textura, el trazador usa su color tal cual: el factor de color base solo se aplica sin ella
+    let texture = pbr
+        .base_color_texture()
+        .and_then(|info| textures.get(info.texture().source().index()).cloned());
+    let diffuse = Vector3::new(r, g, b);
+
+    let [er, eg, eb] = material.emissive_factor();
+    let emission = Vector3::new(er, eg, eb) * material.emissive_strength().unwrap_or(1.0);
+    let emission_strength = emission.x.max(emission.y).max(emission.z);
+    if emission_strength > 0.0 {
+        Material::new_emissive(
+            diffuse,
+            shininess,
+            albedo,
+            refractive_index,
+            texture,
+            emission / emission_strength,
+            emission_strength,
+        )
+    } else {
+        Material::new(diffuse, shininess, albedo, refractive_index, texture)
+    }
+}
+
+fn convert_camera(camera: &gltf::Camera, world: &Transform) -> Camera {
+    let eye = world.transform_point(Vector3::zero());
+    // Las cámaras de glTF miran hacia su -Z local con +Y arriba
+    let forward = world.transform_vector(Vector3::new(0.0, 0.0, -1.0)).normalized();
+    let up = world.transform_vector(Vector3::new(0.0, 1.0, 0.0)).normalized();
+    let mut imported = Camera::new(eye, eye + forward * CAMERA_TARGET_DISTANCE, up);
+
+    match camera.projection() {
+        GltfProjection::Perspective(perspective) => imported.set_fov(perspective.yfov()),
+        GltfProjection::Orthographic(orthographic) => {
+            // La ortográfica usa como media altura la que vería la perspectiva a la distancia del objetivo
+            let half_height = orthographic.ymag() * world.transform_vector(Vector3::new(0.0, 1.0, 0.0)).length();
+            imported.set_projection(Projection::Orthographic);
+            imported.set_fov(2.0 * (half_height / CAMERA_TARGET_DISTANCE).atan());
+        }
+    }
+    imported
+}
+
+// Registra cada imagen en el TextureManager y devuelve su clave (índice = índice de la imagen)
+fn load_images(
+    document: &gltf::Document,
+    buffers: &[Vec<u8>],
+    path: &str,
+    directory: &Path,
+    texture_manager: &mut TextureManager,
+) -> Result<Vec<String>, String> {
+    document
+        .images()
+        .map(|image| match image.source() {
+            gltf::image::Source::View { view, mime_type } => {
+                let start = view.offset();
+                let data = buffers[view.buffer().index()]
+                    .get(start..start + view.length())
+                    .ok_or_else(|| format!("{}: imagen {} fuera del buffer", path, image.index()))?;
+                let key = format!("{}#imagen{}", path, image.index());
+                texture_manager.load_cpu_texture_from_memory(&key, file_type(mime_type, ""), data)?;
+                Ok(key)
+            }
+            gltf::image::Source::Uri { uri, mime_type } => {
+                let data = read_uri(directory, uri)?;
+                let key = if uri.starts_with("data:") {
+                    format!("{}#imagen{}", path, image.index())
+                } else {
+                    join_path(directory, &percent_decode(uri))
+                };
+                texture_manager.load_cpu_texture_from_memory(&key, file_type(mime_type.unwrap_or(""), uri), &data)?;
+                Ok(key)
+            }
+        })
+        .collect()
+}
+
+// Extensión que Raylib necesita para decodificar una imagen en memoria
+fn file_type(mime_type: &str, uri: &str) -> &'static str {
+    let uri = uri.to_ascii_lowercase();
+    if mime_type == "image/jpeg" || uri.ends_with(".jpg") || uri.ends_with(".jpeg") {
+        ".jpg"
+    } else {
+        ".png"
+    }
+}
+
+// Contenido de una URI de glTF: `data:` en base64 o un archivo relativo al .gltf
+fn read_uri(directory: &Path, uri: &str) -> Result<Vec<u8>, String> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data
+            .split_once(";base64,")
+            .ok_or_else(|| "URI de datos sin base64".to_string())?;
+        return base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|error| format!("URI de datos no válida: {}", error));
+    }
+
+    let file = join_path(directory, &percent_decode(uri));
+    std::fs::read(&file).map_err(|error| format!("No se pudo leer {}: {}", file, error))
+}
+
+// Las URI pueden traer espacios y otros caracteres escapados (%20)
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        // Solo dos cifras hexadecimales: `from_str_radix` también aceptaría un signo (`%+1`)
+        if bytes[index] == b'%'
+            && let Some(hex) = bytes.get(index + 1..index + 3).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            && let Ok(value) = u8::from_str_radix(std::str::from_utf8(hex).unwrap(), 16)
+        {
+            decoded.push(value);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray_intersect::RayIntersect;
+    use crate::test_support::assert_hit;
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(percent_decode("mis%20texturas/ladrillo.png"), "mis texturas/ladrillo.png");
+        assert_eq!(percent_decode("%C3%B1and%c3%BA"), "ñandú");
+        // Lo que no es un escape válido se deja tal cual
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%2"), "%2");
+        assert_eq!(percent_decode("%zz%+1%-1"), "%zz%+1%-1");
+        assert_eq!(percent_decode("%ñ1"), "%ñ1");
+    }
+
+    // Un triángulo en el plano XZ, un material metálico y emisivo, una luz puntual hija, un sol y una cámara
+    fn scene_json() -> String {
+        let mut buffer: Vec<u8> = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        buffer.extend([0u16, 1, 2, 0].iter().flat_map(|index| index.to_le_bytes()));
+        let data = base64::engine::general_purpose::STANDARD.encode(&buffer);
+        format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "extensionsUsed": ["KHR_lights_punctual"],
+                "extensions": {{"KHR_lights_punctual": {{"lights": [
+                    {{"type": "point", "color": [1, 0.5, 0], "intensity": 20, "range": 3}},
+                    {{"type": "directional", "intensity": 1.5}}
+                ]}}}},
+                "buffers": [{{"byteLength": {}, "uri": "data:application/octet-stream;base64,{}"}}],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+                    {{"buffer": 0, "byteOffset": 36, "byteLength": 6}}
+                ],
+                "accessors": [
+                    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 0, 1]}},
+                    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+                ],
+                "materials": [{{
+                    "pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 1], "metallicFactor": 1, "roughnessFactor": 0}},
+                    "emissiveFactor": [0, 0.5, 0.25]
+                }}],
+                "meshes": [{{"name": "suelo", "primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}}],
+                "cameras": [{{"type": "perspective", "perspective": {{"yfov": 0.8, "znear": 0.1}}}}],
+                "nodes": [
+                    {{"mesh": 0, "translation": [2, 1, 0], "scale": [2, 2, 2], "children": [1]}},
+                    {{"translation": [0, 1, 0], "extensions": {{"KHR_lights_punctual": {{"light": 0}}}}}},
+                    {{"extensions": {{"KHR_lights_punctual": {{"light": 1}}}}}},
+                    {{"camera": 0, "translation": [0, 0, 5]}}
+                ],
+                "scenes": [{{"nodes": [0, 2, 3]}}],
+                "scene": 0
+            }}"#,
+            buffer.len(),
+            data
+        )
+    }
+
+    #[test]
+    fn converts_meshes_materials_lights_and_cameras() {
+        let import = import_gltf(
+            scene_json().as_bytes(),
+            "memoria.gltf",
+            Vector3::new(0.0, 10.0, 0.0),
+            1.0,
+            &mut TextureManager::new(),
+        )
+        .unwrap();
+
+        // El nodo escala por 2 y traslada (2, 1, 0); la importación sube 10: el triángulo queda en y = 11
+        assert_eq!(import.objects.len(), 1);
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        let hit = import.objects[0].ray_intersect(&Vector3::new(2.5, 20.0, 0.5), &down);
+        assert_hit(&hit, 9.0, -down);
+        assert!(!import.objects[0].ray_intersect(&Vector3::new(3.9, 20.0, 1.9), &down).is_intersecting);
+
+        let material = &hit.material;
+        assert_eq!(material.diffuse, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(material.albedo[2], 1.0);
+        assert_eq!(material.albedo[3], 0.0);
+        assert_eq!(material.emission_strength, 0.5);
+        assert_eq!(material.emission, Vector3::new(0.0, 1.0, 0.5));
+
+        // La luz hija hereda la transformación del nodo de la malla
+        assert_eq!(import.emissive_sources.len(), 1);
+        let source = &import.emissive_sources[0];
+        assert!((source.position - Vector3::new(2.0, 13.0, 0.0)).length() < 1e-5);
+        assert_eq!(source.color, Vector3::new(1.0, 0.5, 0.0));
+        assert!((source.strength - 20.0 * LIGHT_INTENSITY_SCALE).abs() < 1e-6);
+        assert_eq!(source.radius, 3.0);
+
+        // El sol apunta hacia -Z: se coloca detrás, en +Z
+        let sun = import.sun.expect("falta la luz direccional");
+        assert!((sun.position.to_vector3() - Vector3::new(0.0, 10.0, SUN_DISTANCE)).length() < 1e-4);
+        assert_eq!(sun.intensity, 1.5);
+
+        assert_eq!(import.cameras.len(), 1);
+        let camera = &import.cameras[0];
+        assert!((camera.eye - Vector3::new(0.0, 10.0, 5.0)).length() < 1e-5);
+        assert!((camera.center - Vector3::new(0.0, 10.0, 5.0 - CAMERA_TARGET_DISTANCE)).length() < 1e-4);
+        assert!((camera.fov - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn scale_applies_before_offset() {
+        let import = import_gltf(scene_json().as_bytes(), "memoria.gltf", Vector3::zero(), 0.5, &mut TextureManager::new()).unwrap();
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        // Vértices en (1, 0.5, 0), (2, 0.5, 0) y (1, 0.5, 1)
+        assert_hit(&import.objects[0].ray_intersect(&Vector3::new(1.2, 3.0, 0.2), &down), 2.5, -down);
+        assert!((import.emissive_sources[0].position - Vector3::new(1.0, 1.5, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn reports_broken_files() {
+        let mut texture_manager = TextureManager::new();
+        let error = import_gltf(b"{}", "roto.gltf", Vector3::zero(), 1.0, &mut texture_manager).err().unwrap();
+        assert!(error.starts_with("roto.gltf: "), "{}", error);
+        let no_scenes = r#"{"asset": {"version": "2.0"}}"#;
+        assert_eq!(
+            import_gltf(no_scenes.as_bytes(), "vacio.gltf", Vector3::zero(), 1.0, &mut texture_manager).err(),
+            Some("vacio.gltf: el archivo no tiene escenas".to_string())
+        );
+    }
+}
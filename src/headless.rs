@@ -0,0 +1,168 @@
+use raylib::consts::TraceLogLevel;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::bookmarks::Bookmarks;
+use crate::camera::Camera;
+use crate::camera_path::CameraPath;
+use crate::cli::CliOptions;
+use crate::editor::EditableWorld;
+use crate::framebuffer::Framebuffer;
+use crate::scene::{Scene, TEXTURE_PATHS, default_camera};
+use crate::temporal::TemporalAccumulator;
+use crate::textures::TextureManager;
+use crate::tiles::CancellationToken;
+use crate::{QualitySettings, render};
+
+// Escena y recursos cargados una sola vez para todos los frames que se rendericen
+struct HeadlessRenderer {
+    scene: Scene,
+    texture_manager: TextureManager,
+    quality: QualitySettings,
+    width: u32,
+    height: u32,
+}
+
+impl HeadlessRenderer {
+    fn render_frame(&self, camera: &Camera) -> Framebuffer {
+        // Cada frame es independiente: sin historial temporal el resultado es determinista
+        let framebuffer = Mutex::new(Framebuffer::new(self.width, self.height));
+        let mut temporal = TemporalAccumulator::new();
+        temporal.enabled = false;
+
+        render(
+            &framebuffer,
+            &self.scene.objects,
+            &self.scene.emissive_sources,
+            camera,
+            &self.scene.light,
+            &self.texture_manager,
+            &self.quality,
+            &mut temporal,
+            &CancellationToken::new(),
+        );
+
+        framebuffer.into_inner().unwrap()
+    }
+}
+
+// Exporta los bloques de la escena a MagicaVoxel (`--export-vox`) sin renderizar; las texturas
+// solo hacen falta para sacar el color de cada material
+pub fn export_vox(options: &CliOptions, file: &str) {
+    let mut texture_manager = cpu_texture_manager();
+    let scene = options.load_scene(&mut texture_manager);
+    let count = scene.export_vox(file, &texture_manager).unwrap_or_else(|error| panic!("{}", error));
+    println!("{} bloques exportados a {}", count, file);
+}
+
+// Exporta los cubos de la escena a OBJ o glTF (`--export-mesh`) sin renderizar
+pub fn export_mesh(options: &CliOptions, file: &str) {
+    let mut texture_manager = cpu_texture_manager();
+    let scene = options.load_scene(&mut texture_manager);
+    let count = scene.export_mesh(file).unwrap_or_else(|error| panic!("{}", error));
+    println!("{} caras exportadas a {}", count, file);
+}
+
+// Sin ventana no hay contexto de GPU: solo se cargan las copias en CPU de las texturas
+fn cpu_texture_manager() -> TextureManager {
+    // Mismo nivel de log que el modo ventana (sin el handle de Raylib hay que llamar a la FFI)
+    unsafe {
+        raylib::ffi::SetTraceLogLevel(TraceLogLevel::LOG_WARNING as i32);
+    }
+
+    let mut texture_manager = TextureManager::new();
+    for path in TEXTURE_PATHS {
+        texture_manager.load_cpu_texture(path);
+    }
+    texture_manager
+}
+
+// Renderiza a disco sin abrir ventana (`--headless`): un frame, o una secuencia si hay recorrido
+pub fn run(options: &CliOptions) {
+    let quality_name = options.quality.as_deref().unwrap_or("ultra");
+    let quality = QualitySettings::from_name(quality_name)
+        .unwrap_or_else(|| panic!("Calidad desconocida: {}", quality_name));
+
+    let mut texture_manager = cpu_texture_manager();
+    let mut scene = options.load_scene(&mut texture_manager);
+    // Sin edición no hace falta guardar lo que ningún rayo puede ver: se descartan los cubos encerrados
+    let culled = scene.cull_hidden_cubes();
+    println!("Cubos ocultos descartados: {}", culled);
+    // Los bloques pasan al mundo de vóxeles, igual que en la ventana; con --world, los guardados
+    // sustituyen a los de la escena
+    let mut world = EditableWorld::new(scene.objects, scene.emissive_sources);
+    if let Some(file) = &options.world {
+        let count = world.load(file, &mut texture_manager).unwrap_or_else(|error| {
+            eprintln!("No se pudo cargar el mundo: {}", error);
+            std::process::exit(1);
+        });
+        println!("Mundo cargado de {}: {} bloques", file, count);
+    }
+    let voxels = world.voxels();
+    println!("Bloques: {} en {} trozos", voxels.block_count(), voxels.chunk_count());
+    scene.objects = world.objects;
+    scene.emissive_sources = world.emissive_sources;
+
+    // La resolución de salida es la pedida; de la calidad solo se usan rebotes, sombras, refracción y muestras
+    let renderer = HeadlessRenderer {
+        scene,
+        texture_manager,
+        quality,
+        width: options.width,
+        height: options.height,
+    };
+
+    let bookmarks = Bookmarks::load_or_empty(&options.bookmarks_file);
+    let mut camera = default_camera();
+    options.apply_to_camera(&mut camera, &bookmarks, &renderer.scene.cameras);
+
+    let path = match (&options.path, options.turntable) {
+        (Some(file), _) => Some(CameraPath::load(file).unwrap_or_else(|error| panic!("{}", error))),
+        (None, Some(duration)) => Some(CameraPath::turntable(&camera, duration)),
+        (None, None) => None,
+    };
+
+    match path {
+        Some(path) => render_sequence(&renderer, &path, camera, options, quality_name),
+        None => {
+            println!(
+                "Renderizando {}x{} ({}, proyección {}) ...",
+                options.width,
+                options.height,
+                quality_name,
+                camera.projection.label()
+            );
+            let start = Instant::now();
+            renderer.render_frame(&camera).render_to_file(&options.output);
+            println!("Imagen guardada en {} ({:.2?})", options.output, start.elapsed());
+        }
+    }
+}
+
+// Renderiza cada frame del recorrido como frame_0001.png, frame_0002.png, ...
+fn render_sequence(renderer: &HeadlessRenderer, path: &CameraPath, mut camera: Camera, options: &CliOptions, quality_name: &str) {
+    std::fs::create_dir_all(&options.frames_dir)
+        .unwrap_or_else(|error| panic!("No se pudo crear {}: {}", options.frames_dir, error));
+
+    let frame_times = path.frame_times(options.fps);
+    println!(
+        "Renderizando {} frames {}x{} ({}, {:.2}s a {} FPS) en {}/ ...",
+        frame_times.len(),
+        options.width,
+        options.height,
+        quality_name,
+        path.duration(),
+        options.fps,
+        options.frames_dir
+    );
+
+    let start = Instant::now();
+    for (index, time) in frame_times.iter().enumerate() {
+        path.apply(*time, &mut camera);
+        let file = format!("{}/frame_{:04}.png", options.frames_dir, index + 1);
+        let frame_start = Instant::now();
+        renderer.render_frame(&camera).render_to_file(&file);
+        println!("  [{}/{}] {} ({:.2?})", index + 1, frame_times.len(), file, frame_start.elapsed());
+    }
+    println!("Secuencia terminada ({:.2?})", start.elapsed());
+}
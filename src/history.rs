@@ -0,0 +1,107 @@
+use crate::editor::EditableWorld;
+use crate::material::Material;
+use crate::structures::BlockPos;
+
+// Operaciones que se pueden deshacer; las más antiguas se olvidan
+const MAX_UNDO_STEPS: usize = 200;
+
+// Cambio de un bloque: qué había antes y qué hay después (None es aire)
+#[derive(Clone, Debug)]
+pub struct BlockChange {
+    pub position: BlockPos,
+    pub before: Option<Material>,
+    pub after: Option<Material>,
+}
+
+// Historial de ediciones del mundo. Cada operación es la lista de cambios que se deshacen juntos:
+// un clic, o todo un trazo o una caja
+pub struct EditHistory {
+    undo: Vec<Vec<BlockChange>>,
+    redo: Vec<Vec<BlockChange>>,
+    // Trazo abierto: los cambios se acumulan en una sola operación hasta cerrarlo
+    stroke: Option<Vec<BlockChange>>,
+    unsaved: bool,
+}
+
+impl EditHistory {
+    pub fn new() -> Self {
+        EditHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            stroke: None,
+            unsaved: false,
+        }
+    }
+
+    // Anota cambios recién aplicados al mundo; una edición nueva invalida lo que se podía rehacer
+    pub fn record(&mut self, changes: Vec<BlockChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.unsaved = true;
+        self.redo.clear();
+        match &mut self.stroke {
+            Some(stroke) => stroke.extend(changes),
+            None => self.push_undo(changes),
+        }
+    }
+
+    pub fn begin_stroke(&mut self) {
+        self.end_stroke();
+        self.stroke = Some(Vec::new());
+    }
+
+    pub fn end_stroke(&mut self) {
+        if let Some(stroke) = self.stroke.take()
+            && !stroke.is_empty()
+        {
+            self.push_undo(stroke);
+        }
+    }
+
+    fn push_undo(&mut self, changes: Vec<BlockChange>) {
+        self.undo.push(changes);
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    // Deshace la última operación. Devuelve cuántos bloques cambiaron, o None si no había nada
+    pub fn undo(&mut self, world: &mut EditableWorld) -> Option<usize> {
+        self.end_stroke();
+        let changes = self.undo.pop()?;
+        // En orden inverso: si un bloque cambió dos veces en la operación queda como al principio
+        world.edit(changes.iter().rev().map(|change| (change.position, change.before.clone())));
+        self.unsaved = true;
+        let count = changes.len();
+        self.redo.push(changes);
+        Some(count)
+    }
+
+    pub fn redo(&mut self, world: &mut EditableWorld) -> Option<usize> {
+        self.end_stroke();
+        let changes = self.redo.pop()?;
+        world.edit(changes.iter().map(|change| (change.position, change.after.clone())));
+        self.unsaved = true;
+        let count = changes.len();
+        self.push_undo(changes);
+        Some(count)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.stroke.as_ref().is_some_and(|stroke| !stroke.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // Hay ediciones (o deshechos) posteriores al último guardado
+    pub fn has_unsaved(&self) -> bool {
+        self.unsaved
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.unsaved = false;
+    }
+}
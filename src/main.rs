@@ -4,35 +4,85 @@ use rayon::prelude::*;
 use std::collections::HashMap;
 use std::f32::consts::PI;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 
 mod framebuffer;
 mod cube;
+mod primitives;
 mod camera;
 mod light;
 mod textures;
 mod material;
 mod ray_intersect;
 mod structures;
+mod temporal;
+mod dynamic_resolution;
+mod tiles;
+mod render_worker;
+mod scene;
+mod cli;
+mod headless;
+mod camera_path;
+mod bookmarks;
+mod stereo;
+mod obj_loader;
+mod gltf_loader;
+mod noise;
+mod terrain;
+mod vegetation;
+mod vox;
+mod nbt;
+mod schematic;
+mod faces;
+mod mesh_export;
+mod editor;
+mod history;
+mod world_file;
+#[cfg(test)]
+mod test_support;
 
 // Estructuras y utilidades propias del proyecto
-use structures::house::house_structure;
 use framebuffer::Framebuffer;
-use cube::{Vec3, Cube};
-use camera::Camera;
+use primitives::Primitive;
+use camera::{Camera, CameraMode, lens_sample};
 use light::Light;
 use textures::TextureManager;
-use material::{Material, vector3_to_color};
+use material::vector3_to_color;
 use ray_intersect::{Intersect, RayIntersect};
+use temporal::{PixelSample, PrimaryHit, ResolvedTile, TemporalAccumulator};
+use tiles::{CancellationToken, split_into_tiles};
+use render_worker::{RenderRequest, RenderScene, RenderWorker};
+use dynamic_resolution::DynamicResolution;
+use scene::{Scene, TEXTURE_PATHS, default_camera};
+use cli::CliOptions;
+use bookmarks::{Bookmarks, CameraBookmark};
+use stereo::{StereoEye, StereoLayout, StereoSettings, anaglyph, eye_viewport};
+use editor::{BlockEditor, EditAction, EditableWorld};
 
-use crate::structures::{house_peak, house_roof, house_roof_peak, tree_structure,cave,portal_structure, farm};
 
 // Constantes globales que controlan ajustes del trazado
 const ORIGIN_BIAS: f32 = 1e-4;
 const SKY_TEXTURE_PATH: &str = "assets/sky.png";
+// Frecuencia de refresco de la ventana (independiente del render)
+const UI_TARGET_FPS: u32 = 60;
+// A partir de cuánto tiempo de cálculo se muestra el frame parcial en lugar del último completo
+const PROGRESSIVE_PRESENT_DELAY: Duration = Duration::from_millis(250);
+// Vuelo libre: velocidad base (unidades/segundo), modificadores y sensibilidad del ratón (rad/píxel)
+const FLY_SPEED: f32 = 4.0;
+const FLY_FAST_MULTIPLIER: f32 = 3.0;
+const FLY_SLOW_MULTIPLIER: f32 = 0.25;
+const MOUSE_SENSITIVITY: f32 = 0.003;
+// Paso de ajuste del campo de visión con [ y ] (5°)
+const FOV_STEP: f32 = PI / 36.0;
+// Apertura de la lente: primer paso al abrirla, factor entre pasos y máximo
+const MIN_APERTURE: f32 = 0.02;
+const APERTURE_FACTOR: f32 = 1.5;
+const MAX_APERTURE: f32 = 1.0;
 
 // Fuente de iluminación secundaria utilizada para bloques emisivos (lava, portal, etc.)
-#[derive(Clone)]
-struct EmissiveSource {
+#[derive(Clone, PartialEq)]
+pub struct EmissiveSource {
     position: Vector3,
     color: Vector3,
     strength: f32,
@@ -130,17 +180,17 @@ fn calculate_emissive_lighting(
             .powf(1.1);
         let intensity = source.strength * attenuation * falloff;
 
-        total_light = total_light + source.color * intensity * dot_product;
+        total_light += source.color * intensity * dot_product;
     }
 
     total_light
 }
 
-// Verifica si un rayo hacia la luz queda bloqueado por algún cubo
+// Verifica si un rayo hacia la luz queda bloqueado por algún objeto
 fn cast_shadow(
     intersect: &Intersect,
     light: &Light,
-    objects: &[Cube],
+    objects: &[Primitive],
 ) -> f32 {
     let light_pos = Vector3::new(light.position.x, light.position.y, light.position.z);
     let light_dir = (light_pos - intersect.point).normalized();
@@ -159,10 +209,11 @@ fn cast_shadow(
 }
 
 // Núcleo del trazador: dispara un rayo y devuelve el color resultante
+#[allow(clippy::too_many_arguments)]
 pub fn cast_ray(
     ray_origin: &Vector3,
     ray_direction: &Vector3,
-    objects: &[Cube],
+    objects: &[Primitive],
     emissive_sources: &[EmissiveSource],
     light: &Light,
     texture_manager: &TextureManager,
@@ -173,25 +224,53 @@ pub fn cast_ray(
         return procedural_sky(*ray_direction, texture_manager);
     }
 
+    let intersect = match closest_hit(ray_origin, ray_direction, objects) {
+        Some(i) => i,
+        None => return procedural_sky(*ray_direction, texture_manager),
+    };
 
-    let closest_intersection = objects
+    shade(
+        &intersect,
+        ray_origin,
+        ray_direction,
+        objects,
+        emissive_sources,
+        light,
+        texture_manager,
+        depth,
+        quality,
+    )
+}
+
+// Busca el objeto más cercano que intersecta el rayo
+fn closest_hit(ray_origin: &Vector3, ray_direction: &Vector3, objects: &[Primitive]) -> Option<Intersect> {
+    objects
         .par_iter()
         .map(|object| object.ray_intersect(ray_origin, ray_direction))
         .filter(|i| i.is_intersecting)
-        .min_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(std::cmp::Ordering::Equal));
-
-    let intersect = match closest_intersection {
-        Some(i) => i,
-        None => return procedural_sky(*ray_direction, texture_manager),
-    };
+        .min_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(std::cmp::Ordering::Equal))
+}
 
+// Calcula el color de un punto de impacto ya conocido
+#[allow(clippy::too_many_arguments)]
+fn shade(
+    intersect: &Intersect,
+    ray_origin: &Vector3,
+    ray_direction: &Vector3,
+    objects: &[Primitive],
+    emissive_sources: &[EmissiveSource],
+    light: &Light,
+    texture_manager: &TextureManager,
+    depth: u32,
+    quality: &QualitySettings,
+) -> Vector3 {
     let light_pos = Vector3::new(light.position.x, light.position.y, light.position.z);
     let light_dir = (light_pos - intersect.point).normalized();
     let view_dir = (*ray_origin - intersect.point).normalized();
     let reflect_dir = reflect(&-light_dir, &intersect.normal).normalized();
 
     let shadow_intensity = if quality.shadow_quality > 0.0 {
-        cast_shadow(&intersect, light, objects) * quality.shadow_quality
+        cast_shadow(intersect, light, objects) * quality.shadow_quality
     } else {
         0.0  
     };
@@ -213,16 +292,16 @@ pub fn cast_ray(
     let mut diffuse = diffuse_color * diffuse_intensity;
     
 
-    if let Some(texture_path) = &intersect.material.texture_id {
-        if texture_path.contains("lava") {
-            diffuse = diffuse + diffuse_color * 0.6; 
-        }
+    if let Some(texture_path) = &intersect.material.texture_id
+        && texture_path.contains("lava")
+    {
+        diffuse += diffuse_color * 0.6;
     }
 
 
     // Añade la contribución de emisores locales (lava, portal, etc.)
-    let emissive_light = calculate_emissive_lighting(&intersect, emissive_sources, quality);
-    diffuse = diffuse + diffuse_color * emissive_light;
+    let emissive_light = calculate_emissive_lighting(intersect, emissive_sources, quality);
+    diffuse += diffuse_color * emissive_light;
 
     // Componente especular del modelo de iluminación
     let specular_intensity = view_dir.dot(reflect_dir).max(0.0).powf(intersect.material.specular * 0.8) * light_intensity;
@@ -240,7 +319,7 @@ pub fn cast_ray(
     // Calcula reflejos recursivos si el material lo requiere
     let reflect_color = if reflectivity > 0.0 && depth < quality.max_ray_depth {
         let reflect_dir = reflect(ray_direction, &intersect.normal).normalized();
-        let reflect_origin = offset_origin(&intersect, &reflect_dir);
+        let reflect_origin = offset_origin(intersect, &reflect_dir);
         cast_ray(
             &reflect_origin,
             &reflect_dir,
@@ -266,7 +345,7 @@ pub fn cast_ray(
         let refr_index = intersect.material.refractive_index.max(1.0);
         let incident_dir = ray_direction.normalized();
         if let Some(refract_dir) = refract(&incident_dir, &intersect.normal, refr_index) {
-            let refract_origin = offset_origin(&intersect, &refract_dir);
+            let refract_origin = offset_origin(intersect, &refract_dir);
             refract_color = cast_ray(
                 &refract_origin,
                 &refract_dir,
@@ -289,81 +368,526 @@ pub fn cast_ray(
     phong_color * base_weight + reflect_color * reflectivity + refract_color * transparency + emission
 }
 
-// Genera la imagen final iterando por cada píxel de la pantalla virtual
+// Genera la imagen final por tiles; cada tile terminado se escribe de inmediato en el framebuffer.
+// Devuelve false si el frame se canceló antes de terminar.
+#[allow(clippy::too_many_arguments)]
 pub fn render(
-    framebuffer: &mut Framebuffer,
-    objects: &[Cube],
+    framebuffer: &Mutex<Framebuffer>,
+    objects: &[Primitive],
     emissive_sources: &[EmissiveSource],
     camera: &Camera,
     light: &Light,
     texture_manager: &TextureManager,
     quality: &QualitySettings,
-) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+    temporal: &mut TemporalAccumulator,
+    cancel: &CancellationToken,
+) -> bool {
+    let (frame_width, frame_height) = {
+        let framebuffer = framebuffer.lock().unwrap();
+        (framebuffer.width, framebuffer.height)
+    };
+    // En estéreo cada ojo ocupa otra región de la imagen y la reproyección no aplica:
+    // se descarta el historial y cada frame se calcula desde cero
+    let use_history = camera.stereo.is_none();
+    if !use_history {
+        temporal.reset();
+    }
+
+    temporal.begin_frame(frame_width, frame_height);
+    let (jitter_x, jitter_y) = if use_history { temporal.jitter() } else { (0.0, 0.0) };
+    let history: &TemporalAccumulator = temporal;
 
     static THREAD_COUNTER: AtomicUsize = AtomicUsize::new(0);
-    
-    let total_pixels = framebuffer.width * framebuffer.height;
-    let pixels: Vec<(u32, Color)> = (0..total_pixels)
-        .into_par_iter()
-        .map(|pixel_index| {
-            THREAD_COUNTER.fetch_add(1, Ordering::Relaxed);
-            
-            let x = pixel_index % framebuffer.width;
-            let y = pixel_index / framebuffer.width;
-            
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-
-            let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
-            let rotated_direction = camera.basis_change(&ray_direction);
-
-            let pixel_color_v3 = cast_ray(
-                &camera.eye,
-                &rotated_direction,
-                objects,
-                emissive_sources,
-                light,
-                texture_manager,
-                0,
-                quality,
-            );
-            let pixel_color = vector3_to_color(pixel_color_v3);
 
-            (pixel_index, pixel_color)
+    // Con la lente abierta cada píxel promedia varias muestras; si no, basta el rayo estenopeico
+    let lens_samples = quality.samples_per_pixel.max(1);
+    let frame_seed = history.frame_index();
+
+    let trace_ray = |ray_origin: Vector3, rotated_direction: Vector3| -> PixelSample {
+        // El primer impacto se guarda aparte para poder reproyectarlo en el siguiente frame
+        match closest_hit(&ray_origin, &rotated_direction, objects) {
+            Some(intersect) => PixelSample {
+                color: shade(
+                    &intersect,
+                    &ray_origin,
+                    &rotated_direction,
+                    objects,
+                    emissive_sources,
+                    light,
+                    texture_manager,
+                    0,
+                    quality,
+                ),
+                direction: rotated_direction,
+                hit: Some(PrimaryHit {
+                    point: intersect.point,
+                    normal: intersect.normal,
+                }),
+            },
+            None => PixelSample {
+                color: procedural_sky(rotated_direction, texture_manager),
+                direction: rotated_direction,
+                hit: None,
+            },
+        }
+    };
+
+    // Traza el píxel (x, y) de una vista de `view_width`×`view_height`; con `eye` se desplaza al ojo indicado
+    let trace_view = |x: u32, y: u32, view_width: u32, view_height: u32, eye: Option<StereoEye>| -> PixelSample {
+        let screen_x = (2.0 * (x as f32 + jitter_x)) / view_width as f32 - 1.0;
+        let screen_y = -(2.0 * (y as f32 + jitter_y)) / view_height as f32 + 1.0;
+        let aspect_ratio = view_width as f32 / view_height as f32;
+
+        let to_eye = |(ray_origin, ray_direction): (Vector3, Vector3)| match eye {
+            Some(eye) => camera.stereo_ray(ray_origin, ray_direction, eye),
+            None => (ray_origin, ray_direction),
+        };
+
+        if camera.aperture <= 0.0 {
+            let (ray_origin, rotated_direction) = to_eye(camera.primary_ray(screen_x, screen_y, aspect_ratio));
+            return trace_ray(ray_origin, rotated_direction);
+        }
+
+        // Giro aleatorio del patrón de la lente por píxel y por frame (la acumulación temporal lo promedia)
+        let rotation = pixel_hash(x, y, frame_seed) as f32 / u32::MAX as f32 * 2.0 * PI;
+        let mut first_sample: Option<PixelSample> = None;
+        let mut color = Vector3::zero();
+        for index in 0..lens_samples {
+            let lens = lens_sample(index, lens_samples, rotation);
+            let (ray_origin, rotated_direction) = to_eye(camera.lens_ray(screen_x, screen_y, aspect_ratio, lens));
+            let sample = trace_ray(ray_origin, rotated_direction);
+            color += sample.color;
+            first_sample.get_or_insert(sample);
+        }
+
+        // El impacto de la primera muestra sirve de referencia para la reproyección
+        PixelSample {
+            color: color / lens_samples as f32,
+            ..first_sample.expect("Al menos una muestra por píxel")
+        }
+    };
+
+    let trace_pixel = |x: u32, y: u32| -> PixelSample {
+        THREAD_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        let Some(stereo) = camera.stereo else {
+            return trace_view(x, y, frame_width, frame_height, None);
+        };
+
+        match stereo.layout {
+            StereoLayout::Anaglyph => {
+                let left = trace_view(x, y, frame_width, frame_height, Some(StereoEye::Left));
+                let right = trace_view(x, y, frame_width, frame_height, Some(StereoEye::Right));
+                PixelSample {
+                    color: anaglyph(left.color, right.color),
+                    ..left
+                }
+            }
+            layout => {
+                let view = eye_viewport(layout, x, y, frame_width, frame_height);
+                trace_view(view.x, view.y, view.width, view.height, Some(view.eye))
+            }
+        }
+    };
+
+    // par_bridge reparte los tiles en el orden de la lista (del centro hacia afuera)
+    let resolved_tiles: Vec<ResolvedTile> = split_into_tiles(frame_width, frame_height)
+        .into_iter()
+        .par_bridge()
+        .filter_map(|tile| {
+            let mut samples = Vec::with_capacity(tile.pixel_count());
+            for local_y in 0..tile.height {
+                // Se revisa por fila para no tener que esperar a que termine el tile
+                if cancel.is_cancelled() {
+                    return None;
+                }
+                for local_x in 0..tile.width {
+                    samples.push(trace_pixel(tile.x + local_x, tile.y + local_y));
+                }
+            }
+
+            let tile_history = if use_history {
+                history.resolve_tile(&tile, &mut samples, camera)
+            } else {
+                vec![0; samples.len()]
+            };
+            let colors: Vec<Color> = samples.iter().map(|s| vector3_to_color(s.color)).collect();
+            framebuffer
+                .lock()
+                .unwrap()
+                .write_tile(tile.x, tile.y, tile.width, tile.height, &colors);
+
+            Some(ResolvedTile {
+                tile,
+                samples,
+                history: tile_history,
+            })
         })
         .collect();
 
+    if cancel.is_cancelled() {
+        return false;
+    }
 
-    for (pixel_index, color) in pixels {
-        let x = pixel_index % framebuffer.width;
-        let y = pixel_index / framebuffer.width;
-        framebuffer.set_pixel_color(x, y, color);
+    if use_history {
+        temporal.end_frame(camera, &resolved_tiles);
     }
+    true
+}
+
+const BOOKMARK_KEYS: [KeyboardKey; 9] = [
+    KeyboardKey::KEY_F1,
+    KeyboardKey::KEY_F2,
+    KeyboardKey::KEY_F3,
+    KeyboardKey::KEY_F4,
+    KeyboardKey::KEY_F5,
+    KeyboardKey::KEY_F6,
+    KeyboardKey::KEY_F7,
+    KeyboardKey::KEY_F8,
+    KeyboardKey::KEY_F9,
+];
+
+fn handle_bookmark_keys(window: &RaylibHandle, camera: &mut Camera, bookmarks: &mut Bookmarks) {
+    let saving = window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || window.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+
+    for (index, key) in BOOKMARK_KEYS.iter().enumerate() {
+        if !window.is_key_pressed(*key) {
+            continue;
+        }
+        let slot = index + 1;
+
+        if saving {
+            match bookmarks.store(slot, CameraBookmark::from_camera(camera)) {
+                Ok(()) => println!("Vista guardada en el marcador {}", slot),
+                Err(error) => println!("{}", error),
+            }
+        } else if let Some(bookmark) = bookmarks.get(slot) {
+            bookmark.apply(camera);
+            println!("Marcador {} recuperado", slot);
+        } else {
+            println!("El marcador {} está vacío (Ctrl+F{} para guardarlo)", slot, slot);
+        }
+    }
+}
+
+// Lanza un rayo por el cursor (o por el centro en vuelo libre) y enfoca a lo que toque
+fn focus_under_cursor(window: &RaylibHandle, camera: &mut Camera, objects: &[Primitive], window_width: i32, window_height: i32) {
+    let (ray_origin, ray_direction) = cursor_ray(window, camera, window_width, window_height);
+
+    match closest_hit(&ray_origin, &ray_direction, objects) {
+        Some(intersect) => {
+            camera.set_focus_distance(camera.focus_distance_to(intersect.point));
+            println!("Enfoque: {:.2}", camera.focus_distance);
+        }
+        None => println!("Enfoque: no hay nada bajo el cursor"),
+    }
+}
+
+// Rayo que pasa por el cursor (en vuelo libre, por el centro de la pantalla)
+fn cursor_ray(window: &RaylibHandle, camera: &Camera, window_width: i32, window_height: i32) -> (Vector3, Vector3) {
+    let (screen_x, screen_y) = match camera.mode {
+        CameraMode::Orbit => {
+            let mouse = window.get_mouse_position();
+            (
+                2.0 * mouse.x / window_width as f32 - 1.0,
+                1.0 - 2.0 * mouse.y / window_height as f32,
+            )
+        }
+        CameraMode::FreeFly => (0.0, 0.0),
+    };
+    let aspect_ratio = window_width as f32 / window_height as f32;
+    camera.primary_ray(screen_x, screen_y, aspect_ratio)
+}
+
+// Modo edición: apunta al bloque bajo el cursor y, según el botón, lo quita, pone uno nuevo sobre la
+// cara apuntada o lo repinta con el material elegido. Con Mayús el clic marca una esquina y el segundo
+// aplica la acción a toda la caja; mantener el botón central o R pinta todo lo que se recorra como una
+// sola operación. Ctrl+Z deshace y Ctrl+Y (o Ctrl+Mayús+Z) rehace. Devuelve true si la escena cambió
+fn handle_editor_input(
+    window: &RaylibHandle,
+    camera: &Camera,
+    editor: &mut BlockEditor,
+    world: &RwLock<EditableWorld>,
+    worker: &RenderWorker,
+    window_width: i32,
+    window_height: i32,
+) -> bool {
+    let control = window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || window.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+    let shift = window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+    let mut changed = false;
+
+    let undo = control && !shift && window.is_key_pressed(KeyboardKey::KEY_Z);
+    let redo = control && (window.is_key_pressed(KeyboardKey::KEY_Y) || (shift && window.is_key_pressed(KeyboardKey::KEY_Z)));
+    if undo || redo {
+        editor.painting = false;
+        // El frame en curso ya no sirve; el hilo de render trabaja con su copia, así que no hay que esperarlo
+        worker.cancel_current();
+        let mut world = world.write().unwrap();
+        let result = if undo { editor.history.undo(&mut world) } else { editor.history.redo(&mut world) };
+        match (result, undo) {
+            (Some(count), true) => println!("Deshecho: {} bloques", count),
+            (Some(count), false) => println!("Rehecho: {} bloques", count),
+            (None, true) => println!("No hay nada que deshacer"),
+            (None, false) => println!("No hay nada que rehacer"),
+        }
+        changed = result.is_some();
+    }
+
+    let wheel = window.get_mouse_wheel_move();
+    if wheel != 0.0 {
+        let palette = world.read().unwrap();
+        editor.cycle_material(if wheel > 0.0 { -1 } else { 1 }, palette.palette().len());
+        if let Some(material) = palette.palette().get(editor.selected) {
+            println!("Material: {}", editor::material_label(material));
+        }
+    }
+
+    // El trazo de pintura termina al soltar el botón (o la tecla)
+    let paint_held = window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_MIDDLE) || window.is_key_down(KeyboardKey::KEY_R);
+    if editor.painting && !paint_held {
+        editor.painting = false;
+        editor.history.end_stroke();
+    }
+
+    let (ray_origin, ray_direction) = cursor_ray(window, camera, window_width, window_height);
+    editor.target = world.read().unwrap().pick(&ray_origin, &ray_direction);
+    let Some(target) = editor.target else {
+        return changed;
+    };
+
+    let pressed = if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+        Some(EditAction::Remove)
+    } else if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+        Some(EditAction::Place)
+    } else if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_MIDDLE)
+        || (!control && window.is_key_pressed(KeyboardKey::KEY_R))
+    {
+        Some(EditAction::Paint)
+    } else {
+        None
+    };
+    let action = match pressed {
+        Some(action) => action,
+        None if editor.painting => EditAction::Paint,
+        None => return changed,
+    };
+    let position = action.position(&target);
+
+    // Mayús+clic: el primero marca una esquina y el segundo (con el mismo botón) la opuesta
+    let mut from = position;
+    if pressed.is_some() && shift {
+        match editor.corner.take() {
+            Some((corner_action, corner)) if corner_action == action => from = corner,
+            _ => {
+                editor.corner = Some((action, position));
+                let (x, y, z) = position;
+                println!("Esquina de la caja para {}: ({}, {}, {})", action.label(), x, y, z);
+                return changed;
+            }
+        }
+    } else if action == EditAction::Paint && pressed.is_some() {
+        editor.painting = true;
+        editor.history.begin_stroke();
+    }
+
+    let edits = {
+        let world = world.read().unwrap();
+        world.box_edits(action, from, position, world.palette().get(editor.selected))
+    };
+    let edits = match edits {
+        Ok(edits) if !edits.is_empty() => edits,
+        Ok(_) => return changed,
+        Err(error) => {
+            println!("{}", error);
+            return changed;
+        }
+    };
+
+    worker.cancel_current();
+    let mut world = world.write().unwrap();
+    let changes = world.edit(edits);
+    if from != position {
+        println!("Caja: {} bloques", changes.len());
+    }
+    editor.history.record(changes);
+    editor.target = world.pick(&ray_origin, &ray_direction);
+    true
+}
+
+// Guarda el mundo editado; el historial recuerda que ya no hay cambios pendientes
+fn save_world(world: &RwLock<EditableWorld>, editor: &mut BlockEditor, path: &str) {
+    match world.read().unwrap().save(path) {
+        Ok(runs) => {
+            editor.history.mark_saved();
+            println!("Mundo guardado en {} ({} tiras de bloques)", path, runs);
+        }
+        Err(error) => println!("{}", error),
+    }
+}
+
+// Hash entero barato para obtener números pseudoaleatorios estables por píxel
+fn pixel_hash(x: u32, y: u32, seed: u32) -> u32 {
+    let mut h = x.wrapping_mul(73_856_093) ^ y.wrapping_mul(19_349_663) ^ seed.wrapping_mul(83_492_791);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    h
+}
+
+// Aplica los controles de cámara; devuelve true si la cámara se movió. `consumed_key` es la tecla de
+// un atajo (la S de Ctrl+S) que no mueve la cámara hasta que se suelta
+fn apply_camera_input(
+    window: &RaylibHandle,
+    camera: &mut Camera,
+    rotation_speed: f32,
+    zoom_speed: f32,
+    consumed_key: Option<KeyboardKey>,
+) -> bool {
+    let mut moved = false;
+    let is_key_down = |key: KeyboardKey| window.is_key_down(key) && consumed_key != Some(key);
+
+    if is_key_down(KeyboardKey::KEY_LEFT) {
+        camera.orbit(rotation_speed, 0.0);
+        moved = true;
+    }
+    if is_key_down(KeyboardKey::KEY_RIGHT) {
+        camera.orbit(-rotation_speed, 0.0);
+        moved = true;
+    }
+    if is_key_down(KeyboardKey::KEY_UP) {
+        camera.orbit(0.0, -rotation_speed);
+        moved = true;
+    }
+    if is_key_down(KeyboardKey::KEY_DOWN) {
+        camera.orbit(0.0, rotation_speed);
+        moved = true;
+    }
+    if is_key_down(KeyboardKey::KEY_W) {
+        camera.zoom(zoom_speed);
+        moved = true;
+    }
+    if is_key_down(KeyboardKey::KEY_S) {
+        camera.zoom(-zoom_speed);
+        moved = true;
+    }
+    if is_key_down(KeyboardKey::KEY_Q) {
+        camera.move_vertical(zoom_speed);
+        moved = true;
+    }
+    if is_key_down(KeyboardKey::KEY_A) {
+        camera.move_vertical(-zoom_speed);
+        moved = true;
+    }
+
+    moved
+}
+
+// Controles del modo vuelo libre: WASD para desplazarse, ratón/flechas para mirar,
+// Espacio/C para subir y bajar, Shift más rápido y Ctrl más lento. Como en órbita, `consumed_key` no mueve
+fn apply_fly_input(
+    window: &RaylibHandle,
+    camera: &mut Camera,
+    objects: &[Primitive],
+    rotation_speed: f32,
+    consumed_key: Option<KeyboardKey>,
+) -> bool {
+    let mut moved = false;
+
+    let mouse_delta = window.get_mouse_delta();
+    let mut yaw = mouse_delta.x * MOUSE_SENSITIVITY;
+    let mut pitch = -mouse_delta.y * MOUSE_SENSITIVITY;
+
+    if window.is_key_down(KeyboardKey::KEY_LEFT) {
+        yaw -= rotation_speed;
+    }
+    if window.is_key_down(KeyboardKey::KEY_RIGHT) {
+        yaw += rotation_speed;
+    }
+    if window.is_key_down(KeyboardKey::KEY_UP) {
+        pitch += rotation_speed;
+    }
+    if window.is_key_down(KeyboardKey::KEY_DOWN) {
+        pitch -= rotation_speed;
+    }
+    if yaw != 0.0 || pitch != 0.0 {
+        camera.look(yaw, pitch);
+        moved = true;
+    }
+
+    let mut speed = FLY_SPEED;
+    if window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT) {
+        speed *= FLY_FAST_MULTIPLIER;
+    }
+    if window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || window.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL) {
+        speed *= FLY_SLOW_MULTIPLIER;
+    }
+    // Velocidad en unidades por segundo, independiente del refresco de la ventana
+    let step = speed * window.get_frame_time();
+
+    let is_key_down = |key: KeyboardKey| window.is_key_down(key) && consumed_key != Some(key);
+    let axis = |positive: KeyboardKey, negative: KeyboardKey| -> f32 {
+        (is_key_down(positive) as i32 - is_key_down(negative) as i32) as f32
+    };
+    let forward = axis(KeyboardKey::KEY_W, KeyboardKey::KEY_S);
+    let right = axis(KeyboardKey::KEY_D, KeyboardKey::KEY_A);
+    let up = axis(KeyboardKey::KEY_SPACE, KeyboardKey::KEY_C);
+
+    if (forward != 0.0 || right != 0.0 || up != 0.0)
+        && camera.fly(forward * step, right * step, up * step, objects)
+    {
+        moved = true;
+    }
+
+    moved
 }
 
 // Configuración de calidad para el motor, permite escalado y límites de profundidad
-#[derive(Clone, Copy)]
-struct QualitySettings {
+#[derive(Clone, Copy, PartialEq)]
+pub struct QualitySettings {
     resolution_scale: f32,  
     max_ray_depth: u32,     
     shadow_quality: f32,    
     enable_refraction: bool,
+    // Muestras de lente por píxel cuando la profundidad de campo está activa
+    samples_per_pixel: u32,
 }
 
 impl QualitySettings {
-    fn ultra() -> Self { Self { resolution_scale: 1.0, max_ray_depth: 4, shadow_quality: 1.0, enable_refraction: true } }
-    fn high() -> Self { Self { resolution_scale: 0.75, max_ray_depth: 2, shadow_quality: 1.0, enable_refraction: false } }
-    fn medium() -> Self { Self { resolution_scale: 0.5, max_ray_depth: 1, shadow_quality: 0.7, enable_refraction: false } }
-    fn low() -> Self { Self { resolution_scale: 0.33, max_ray_depth: 1, shadow_quality: 0.3, enable_refraction: false } }
-    fn potato() -> Self { Self { resolution_scale: 0.15, max_ray_depth: 0, shadow_quality: 0.0, enable_refraction: false } }
+    fn ultra() -> Self { Self { resolution_scale: 1.0, max_ray_depth: 4, shadow_quality: 1.0, enable_refraction: true, samples_per_pixel: 6 } }
+    fn high() -> Self { Self { resolution_scale: 0.75, max_ray_depth: 2, shadow_quality: 1.0, enable_refraction: false, samples_per_pixel: 4 } }
+    fn medium() -> Self { Self { resolution_scale: 0.5, max_ray_depth: 1, shadow_quality: 0.7, enable_refraction: false, samples_per_pixel: 2 } }
+    fn low() -> Self { Self { resolution_scale: 0.33, max_ray_depth: 1, shadow_quality: 0.3, enable_refraction: false, samples_per_pixel: 1 } }
+    fn potato() -> Self { Self { resolution_scale: 0.15, max_ray_depth: 0, shadow_quality: 0.0, enable_refraction: false, samples_per_pixel: 1 } }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "potato" => Some(Self::potato()),
+            "low" => Some(Self::low()),
+            "medium" => Some(Self::medium()),
+            "high" => Some(Self::high()),
+            "ultra" => Some(Self::ultra()),
+            _ => None,
+        }
+    }
+
+    // Calidad para el modo automático: toma el preset más alto que cabe en la escala pedida
+    fn for_scale(scale: f32) -> Self {
+        let mut quality = [Self::ultra(), Self::high(), Self::medium(), Self::low()]
+            .into_iter()
+            .find(|preset| scale >= preset.resolution_scale)
+            .unwrap_or_else(Self::potato);
+        quality.resolution_scale = scale;
+        quality
+    }
+}
+
+// Tamaño del framebuffer para una escala de resolución dada
+fn render_size(window_width: i32, window_height: i32, scale: f32) -> (u32, u32) {
+    let width = ((window_width as f32 * scale) as u32).max(1);
+    let height = ((window_height as f32 * scale) as u32).max(1);
+    (width, height)
 }
 
 fn quality_label_for_key(key: &str) -> &'static str {
@@ -373,44 +897,45 @@ fn quality_label_for_key(key: &str) -> &'static str {
         "medium" => "MEDIUM",
         "high" => "HIGH",
         "ultra" => "ULTRA (máxima calidad)",
+        "auto" => "AUTO (resolución dinámica)",
         _ => "DESCONOCIDA",
     }
 }
 
 // Punto de entrada: prepara Raylib, carga recursos y ejecuta el bucle principal
 fn main() {
-    let window_width = 1300;
-    let window_height = 900;
+    let options = CliOptions::from_args();
+    if let Some(file) = &options.export_vox {
+        headless::export_vox(&options, file);
+        return;
+    }
+    if let Some(file) = &options.export_mesh {
+        headless::export_mesh(&options, file);
+        return;
+    }
+    if options.headless {
+        headless::run(&options);
+        return;
+    }
+
+    let window_width = options.width as i32;
+    let window_height = options.height as i32;
     
     let (mut window, thread) = raylib::init()
         .size(window_width, window_height)
-        .title("Raytracer 3D - Arrow keys: orbit | W/S: zoom | Q/A: vertical | 1-5: quality")
+        .title("Raytracer 3D - Arrow keys: orbit | W/S: zoom | Q/A: vertical | 1-5: quality | 0: auto | T: temporal | F: fly mode | P: projection | [/]: FOV | ,/.: aperture | Click: focus | F1-F9: bookmarks | V: stereo")
         .log_level(TraceLogLevel::LOG_WARNING)
         .build();
 
     let mut texture_manager = TextureManager::new();
 
     // Importación de los assets de texturas que se usarán en los materiales
-    texture_manager.load_texture(&mut window, &thread, "assets/wood.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/rock.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/log.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/log2.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/leaf.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/grass.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/soil.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/obs.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/lava.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/bamboo.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/diamond.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/bush.png");
-    texture_manager.load_texture(&mut window, &thread, "assets/face.png");
-    texture_manager.load_texture(&mut window, &thread, SKY_TEXTURE_PATH);
+    for path in TEXTURE_PATHS {
+        texture_manager.load_texture(&mut window, &thread, path);
+    }
     
-    // Pre-crear framebuffers para cada calidad para cambios instantáneos
-    let mut framebuffers: HashMap<&'static str, Framebuffer> = HashMap::new();
     let mut quality_lookup: HashMap<&'static str, QualitySettings> = HashMap::new();
     
-    // Crear framebuffers para todas las calidades
     let qualities = [
         ("potato", QualitySettings::potato()),
         ("low", QualitySettings::low()),
@@ -420,265 +945,236 @@ fn main() {
     ];
     
     for (name, quality) in &qualities {
-        let render_width = (window_width as f32 * quality.resolution_scale) as u32;
-        let render_height = (window_height as f32 * quality.resolution_scale) as u32;
-        framebuffers.insert(*name, Framebuffer::new(render_width, render_height));
         quality_lookup.insert(*name, *quality);
-        println!("Pre-creado framebuffer {}: {}x{}", name, render_width, render_height);
     }
 
-    let default_quality_key = "potato";
+    let default_quality_key = options
+        .quality
+        .as_deref()
+        .and_then(|name| quality_lookup.keys().find(|key| **key == name).copied())
+        .unwrap_or("potato");
     let mut current_framebuffer_key = default_quality_key;
     let mut current_quality = *quality_lookup
         .get(current_framebuffer_key)
         .expect("Calidad inicial no encontrada");
 
-
-    // Definición de materiales principales usados en las estructuras
-    let log_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0), 
-        8.0,                         
-        [0.9, 0.1, 0.0, 0.0],       
-        0.0,
-        Some("assets/log.png".to_string()),
-    );
-    
-    let face_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0),
-        8.0,
-        [1.35, 0.12, 0.0, 0.0],
-        0.0,
-        Some("assets/face.png".to_string()),
-    );
-
-    let bamboo_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0),
-        8.0,                         
-        [0.9, 0.1, 0.0, 0.0], 
-        0.0,      
-        Some("assets/bamboo.png".to_string()),
-    );
-
-    let log2_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0), 
-        8.0,
-        [0.9, 0.1, 0.0, 0.0],
-        0.0,
-        Some("assets/log2.png".to_string()),
-    );
-
-    let soil_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0), 
-        3.0,                          
-        [0.95, 0.05, 0.0, 0.0],      
-        0.0,
-        Some("assets/soil.png".to_string()),
-    );
-
-
-    let leaf_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0), 
-        12.0,                        
-        [0.85, 0.15, 0.0, 0.0],      
-        0.0,
-        Some("assets/leaf.png".to_string()),
-    );
-
-    let bush_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0), 
-        12.0,                        
-        [0.85, 0.15, 0.0, 0.0],      
-        0.0,
-        Some("assets/bush.png".to_string()),
+    // Último frame terminado; es lo que se muestra mientras el hilo de render trabaja
+    let (render_width, render_height) = render_size(window_width, window_height, current_quality.resolution_scale);
+    let mut displayed_frame = Framebuffer::new(render_width, render_height);
+    // Copia del frame en progreso; tiene su propia textura para no invalidar la del frame completo
+    let mut preview_frame = Framebuffer::new(render_width, render_height);
+
+    // Modo automático: ajusta la resolución para mantener el FPS objetivo
+    let requested_target_fps = options.target_fps;
+    let mut dynamic_resolution = DynamicResolution::new(
+        requested_target_fps.unwrap_or(30.0),
+        current_quality.resolution_scale,
     );
+    if requested_target_fps.is_some() {
+        dynamic_resolution.start(current_quality.resolution_scale);
+        current_framebuffer_key = "auto";
+        println!("Resolución dinámica activa: objetivo {} FPS", dynamic_resolution.target_fps());
+    }
 
-    let lava_material = Material::new_emissive(
-        Vector3::new(1.5, 1.3, 1.0),  
-        5.0,                          
-        [1.0, 0.0, 0.0, 0.0],         
-        0.0,
-        Some("assets/lava.png".to_string()),
-        Vector3::new(0.8, 0.3, 0.05), 
-        2.0,                          
-    );
 
-    let grass_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0), 
-        10.0,                        
-        [0.9, 0.1, 0.0, 0.0],        
-        0.0,
-        Some("assets/grass.png".to_string()),
-    );
-
-    let diamond_material = Material::new(
-        Vector3::new(0.65, 0.92, 1.0),  
-        10.0,                          
-        [1.1, 0.55, 0.0, 0.0],        
-        0.0,                           
-        Some("assets/diamond.png".to_string()),
-    );
+    let Scene { objects, emissive_sources, light, cameras: scene_cameras } = options.load_scene(&mut texture_manager);
+    // Los bloques quedan en un mundo editable (E) que comparten la ventana y el hilo de render
+    let mut world = EditableWorld::new(objects, emissive_sources);
+    // --world: se sigue con el mundo guardado; si aún no existe se creará al guardar. Sin --world se
+    // elige un archivo nuevo al guardar por primera vez y después se sigue usando ese
+    let mut world_path = options.world.clone();
+    if let Some(world_path) = &world_path {
+        if std::path::Path::new(world_path).exists() {
+            // Un archivo que no se entiende no se sustituye por el mundo de la escena: se avisa y se sale
+            let count = world.load(world_path, &mut texture_manager).unwrap_or_else(|error| {
+                eprintln!("No se pudo cargar el mundo: {}", error);
+                std::process::exit(1);
+            });
+            println!("Mundo cargado de {}: {} bloques", world_path, count);
+        } else {
+            println!("{} no existe todavía: se creará al guardar (Ctrl+S)", world_path);
+        }
+    }
+    println!("Bloques editables: {}", world.block_count());
+    let world = RwLock::new(world);
+    let mut editor = BlockEditor::new();
+    let mut scene_version = 0;
+    // Tecla de un atajo con Ctrl que no debe mover la cámara mientras siga pulsada
+    let mut consumed_key: Option<KeyboardKey> = None;
 
-    let rock_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0), 
-        6.0,                          
-        [0.95, 0.05, 0.0, 0.0],      
-        0.0,
-        Some("assets/rock.png".to_string()),
-    );
 
-    let metal_material = Material::new(
-        Vector3::new(0.7, 0.7, 0.7), 
-        100.0,
-        [0.2, 0.6, 0.8, 0.0],       
-        0.0,
-        None,
-    );
+    // Configuración inicial de la cámara orbital
+    let mut bookmarks = Bookmarks::load_or_empty(&options.bookmarks_file);
+    let mut camera = default_camera();
+    options.apply_to_camera(&mut camera, &bookmarks, &scene_cameras);
+    // Separación y convergencia que se usan al activar el estéreo con V
+    let stereo_defaults = options.stereo_settings(StereoLayout::SideBySide);
+    let rotation_speed = PI / 100.0;
+    let zoom_speed = 0.1;
 
-    let obs_material = Material::new(
-        Vector3::new(0.1, 0.1, 0.15), 
-        120.0,                        
-        [0.3, 0.4, 0.0, 0.0],         
-        0.0,
-        Some("assets/obs.png".to_string()),
-    );
+    // Reproyección temporal (estabiliza la imagen al orbitar); el historial vive en el hilo de render
+    let mut temporal_enabled = true;
 
+    // La ventana refresca a ritmo fijo; el render corre aparte y nunca bloquea la entrada
+    window.set_target_fps(UI_TARGET_FPS);
 
-    let ice_material = Material::new(
-        Vector3::new(0.6, 0.8, 1.0),  
-        50.0,                         
-        [0.85, 0.1, 0.25, 0.0],     
-        0.0,                          
-        None,
-    );
+    std::thread::scope(|scope| {
+        let worker = RenderWorker::spawn(
+            scope,
+            RenderScene {
+                world: &world,
+                light: &light,
+                texture_manager: &texture_manager,
+            },
+        );
+        let mut last_request: Option<RenderRequest> = None;
+        // La vista quieta ya llenó el historial temporal: no hace falta seguir renderizándola
+        let mut converged = false;
+        let mut render_fps: Option<f32> = None;
+
+        // Bucle principal: gestiona entrada, envía peticiones de render y presenta el último frame
+        while !window.window_should_close() {
+
+            // F alterna entre cámara orbital y vuelo libre conservando la vista actual
+            if window.is_key_pressed(KeyboardKey::KEY_F) {
+                camera.toggle_mode();
+                match camera.mode {
+                    CameraMode::FreeFly => {
+                        window.disable_cursor();
+                        println!("Cámara: vuelo libre");
+                    }
+                    CameraMode::Orbit => {
+                        window.enable_cursor();
+                        println!("Cámara: orbital");
+                    }
+                }
+            }
 
-    let snow_material = Material::new(
-        Vector3::new(1.0, 1.0, 1.0),  
-        12.0,                         
-        [0.85, 0.1, 0.05, 0.0],       
-        0.0,                          
-        Some("assets/snow.png".to_string()),
-    );
+            // , y . cierran/abren la apertura de la lente; clic izquierdo enfoca lo que hay bajo el cursor
+            if window.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+                let aperture = if camera.aperture <= 0.0 { MIN_APERTURE } else { camera.aperture * APERTURE_FACTOR };
+                camera.set_aperture(aperture.min(MAX_APERTURE));
+                println!("Apertura: {:.3}", camera.aperture);
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_COMMA) && camera.aperture > 0.0 {
+                let aperture = camera.aperture / APERTURE_FACTOR;
+                camera.set_aperture(if aperture < MIN_APERTURE { 0.0 } else { aperture });
+                println!("Apertura: {:.3}", camera.aperture);
+            }
+            // E activa el modo edición: los clics quitan, ponen o repintan bloques en vez de enfocar
+            if window.is_key_pressed(KeyboardKey::KEY_E) {
+                editor.toggle();
+                println!("Modo edición: {}", if editor.enabled { "activado" } else { "desactivado" });
+            }
+            if editor.enabled {
+                if handle_editor_input(&window, &camera, &mut editor, &world, &worker, window_width, window_height) {
+                    scene_version += 1;
+                }
+                // Ctrl+S guarda el mundo editado
+                let control = window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || window.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+                if control && window.is_key_pressed(KeyboardKey::KEY_S) {
+                    consumed_key = Some(KeyboardKey::KEY_S);
+                    save_world(&world, &mut editor, world_path.get_or_insert_with(world_file::unused_world_path));
+                }
+            } else if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                focus_under_cursor(&window, &mut camera, &world.read().unwrap().objects, window_width, window_height);
+            }
 
-    let portal_material = Material::new(
-        Vector3::new(0.4, 0.1, 0.8), 
-        80.0,                        
-        [0.25, 0.2, 0.15, 0.55],     
-        1.45,
-        None,                         
-    );
+            // F1-F9 recuperan un marcador de cámara; Ctrl+F1-F9 guardan la vista actual
+            handle_bookmark_keys(&window, &mut camera, &mut bookmarks);
+
+            // V recorre los modos estéreo (apagado → lado a lado → arriba/abajo → anáglifo)
+            if window.is_key_pressed(KeyboardKey::KEY_V) {
+                let next_layout = match camera.stereo.map(|stereo| stereo.layout) {
+                    None => Some(StereoLayout::SideBySide),
+                    Some(StereoLayout::SideBySide) => Some(StereoLayout::OverUnder),
+                    Some(StereoLayout::OverUnder) => Some(StereoLayout::Anaglyph),
+                    Some(StereoLayout::Anaglyph) => None,
+                };
+                // Se conservan la separación y la convergencia configuradas al cambiar de disposición
+                let stereo = next_layout.map(|layout| StereoSettings {
+                    layout,
+                    ..camera.stereo.unwrap_or(stereo_defaults)
+                });
+                camera.set_stereo(stereo);
+                match stereo {
+                    Some(stereo) => println!("Estéreo: {}", stereo.layout.label()),
+                    None => println!("Estéreo: desactivado"),
+                }
+            }
 
-    let water_material = Material::new(
-        Vector3::new(0.18, 0.34, 0.48),  
-        70.0,                            
-        [0.35, 0.18, 0.12, 0.55],        
-        1.33,                            
-        None,
-    );
+            // P cambia de proyección; [ y ] ajustan el campo de visión
+            if window.is_key_pressed(KeyboardKey::KEY_P) {
+                camera.set_projection(camera.projection.next());
+                println!("Proyección: {}", camera.projection.label());
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_LEFT_BRACKET) {
+                camera.set_fov(camera.fov - FOV_STEP);
+                println!("FOV: {:.0}°", camera.fov.to_degrees());
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_RIGHT_BRACKET) {
+                camera.set_fov(camera.fov + FOV_STEP);
+                println!("FOV: {:.0}°", camera.fov.to_degrees());
+            }
 
-    // Colección de cubos que componen el mundo voxel
-    let mut objects = Vec::new();
-
-
-//house base 
-    house_structure(&mut objects, rock_material.clone());
-    house_roof(&mut objects, log_material.clone());
-    house_roof_peak(&mut objects, log_material.clone());
-    house_peak(&mut objects, log_material.clone());
-    cave(
-        &mut objects,
-        grass_material.clone(),
-        soil_material.clone(),
-        rock_material.clone(),
-        ice_material.clone(),
-        lava_material.clone(),
-        diamond_material.clone(),
-        snow_material.clone(),
-    );
-    portal_structure(&mut objects, obs_material.clone(), snow_material.clone(), portal_material.clone());
-    tree_structure(&mut objects, leaf_material.clone(), log2_material.clone());
-    farm(&mut objects, bamboo_material.clone(), soil_material.clone(), water_material.clone(), bush_material.clone(), snow_material.clone(), face_material.clone());
-
-    // Extraemos los bloques emisivos para acelerar el cálculo de luz secundaria
-    let emissive_sources: Vec<EmissiveSource> = objects
-        .iter()
-        .filter(|cube| cube.material.emission_strength > 0.0)
-        .map(|cube| {
-            let position = cube.center.to_vector3();
-            let color = cube.material.emission;
-            let strength = cube.material.emission_strength * 1.1;
-            let radius = (cube.half.length() * 9.0).max(5.0);
-
-            EmissiveSource {
-                position,
-                color,
-                strength,
-                radius,
+            // Controles de cámara; la tecla de un atajo deja de estar consumida al soltarla
+            if consumed_key.is_some_and(|key| window.is_key_up(key)) {
+                consumed_key = None;
+            }
+            match camera.mode {
+                CameraMode::Orbit => {
+                    apply_camera_input(&window, &mut camera, rotation_speed, zoom_speed, consumed_key);
+                }
+                CameraMode::FreeFly => {
+                    apply_fly_input(&window, &mut camera, &world.read().unwrap().objects, rotation_speed, consumed_key);
+                }
             }
-        })
-        .collect();
 
+            if window.is_key_pressed(KeyboardKey::KEY_T) {
+                temporal_enabled = !temporal_enabled;
+                println!(
+                    "Acumulación temporal: {}",
+                    if temporal_enabled { "activada" } else { "desactivada" }
+                );
+            }
 
-    // Configuración inicial de la cámara orbital
-    let mut camera = Camera::new(
-        Vector3::new(0.0, 3.0, 12.0),
-        Vector3::new(0.0, 0.0, 0.0),
-        Vector3::new(0.0, 1.0, 0.0),
-    );
-    let rotation_speed = PI / 100.0;
-    let zoom_speed = 0.1;
+            let mut requested_quality_key: Option<&'static str> = None;
+
+            if window.is_key_pressed(KeyboardKey::KEY_ONE) {
+                requested_quality_key = Some("potato");
+            } else if window.is_key_pressed(KeyboardKey::KEY_TWO) {
+                requested_quality_key = Some("low");
+            } else if window.is_key_pressed(KeyboardKey::KEY_THREE) {
+                requested_quality_key = Some("medium");
+            } else if window.is_key_pressed(KeyboardKey::KEY_FOUR) {
+                requested_quality_key = Some("high");
+            } else if window.is_key_pressed(KeyboardKey::KEY_FIVE) {
+                requested_quality_key = Some("ultra");
+            }
 
-    let light = Light::new(
-        Vec3::new(8.0, 22.0, 20.0),  
-        Color::new(255, 255, 255, 255),
-        3.0,  
-    );
+            if window.is_key_pressed(KeyboardKey::KEY_ZERO) && !dynamic_resolution.enabled {
+                dynamic_resolution.start(current_quality.resolution_scale);
+                current_framebuffer_key = "auto";
+                println!(
+                    "Calidad activa: {} - objetivo {} FPS",
+                    quality_label_for_key(current_framebuffer_key),
+                    dynamic_resolution.target_fps()
+                );
+            }
 
-    // Bucle principal: gestiona entrada, render y presentación en pantalla
-    while !window.window_should_close() {
-        
-        // Controles de cámara
-        if window.is_key_down(KeyboardKey::KEY_LEFT) {
-            camera.orbit(rotation_speed, 0.0);
-        }
-        if window.is_key_down(KeyboardKey::KEY_RIGHT) {
-            camera.orbit(-rotation_speed, 0.0);
-        }
-        if window.is_key_down(KeyboardKey::KEY_UP) {
-            camera.orbit(0.0, -rotation_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_DOWN) {
-            camera.orbit(0.0, rotation_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_W) {
-            camera.zoom(zoom_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_S) {
-            camera.zoom(-zoom_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_Q) {
-            camera.move_vertical(zoom_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_A) {
-            camera.move_vertical(-zoom_speed);
-        }
-        
-       
-        let mut requested_quality_key: Option<&'static str> = None;
-
-        if window.is_key_pressed(KeyboardKey::KEY_ONE) {
-            requested_quality_key = Some("potato");
-        } else if window.is_key_pressed(KeyboardKey::KEY_TWO) {
-            requested_quality_key = Some("low");
-        } else if window.is_key_pressed(KeyboardKey::KEY_THREE) {
-            requested_quality_key = Some("medium");
-        } else if window.is_key_pressed(KeyboardKey::KEY_FOUR) {
-            requested_quality_key = Some("high");
-        } else if window.is_key_pressed(KeyboardKey::KEY_FIVE) {
-            requested_quality_key = Some("ultra");
-        }
+            if window.is_key_pressed(KeyboardKey::KEY_EQUAL) || window.is_key_pressed(KeyboardKey::KEY_KP_ADD) {
+                dynamic_resolution.set_target_fps(dynamic_resolution.target_fps() + 5.0);
+                println!("FPS objetivo: {}", dynamic_resolution.target_fps());
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_MINUS) || window.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT) {
+                dynamic_resolution.set_target_fps(dynamic_resolution.target_fps() - 5.0);
+                println!("FPS objetivo: {}", dynamic_resolution.target_fps());
+            }
 
-        if let Some(new_key) = requested_quality_key {
-            if new_key != current_framebuffer_key {
+            if let Some(new_key) = requested_quality_key
+                && new_key != current_framebuffer_key
+            {
+                dynamic_resolution.enabled = false;
                 current_framebuffer_key = new_key;
                 current_quality = *quality_lookup
                     .get(current_framebuffer_key)
@@ -688,22 +1184,69 @@ fn main() {
                     quality_label_for_key(current_framebuffer_key)
                 );
             }
+
+            if dynamic_resolution.enabled {
+                current_quality = QualitySettings::for_scale(dynamic_resolution.scale());
+            }
+
+            let (render_width, render_height) = render_size(window_width, window_height, current_quality.resolution_scale);
+            let request = RenderRequest {
+                camera: camera.clone(),
+                quality: current_quality,
+                width: render_width,
+                height: render_height,
+                temporal: temporal_enabled,
+                scene_version,
+            };
+
+            // Si la vista cambió, el frame en curso ya no sirve; si no, se sigue
+            // renderizando la misma vista (solo con acumulación temporal, hasta que converja)
+            let view_changed = last_request
+                .as_ref()
+                .is_none_or(|previous| !previous.same_view(&request));
+            if view_changed {
+                worker.cancel_current();
+                worker.submit(request.clone());
+                last_request = Some(request);
+                converged = false;
+            } else if temporal_enabled && !converged && !worker.is_busy() {
+                worker.submit(request);
+            }
+
+            if let Some(frame) = worker.take_latest_frame() {
+                converged = frame.converged && last_request.as_ref().is_some_and(|request| request.same_view(&frame.request));
+                dynamic_resolution.update(frame.render_time);
+                let frame_fps = 1.0 / frame.render_time.as_secs_f32().max(1e-4);
+                render_fps = Some(match render_fps {
+                    Some(previous) => previous + (frame_fps - previous) * 0.2,
+                    None => frame_fps,
+                });
+                displayed_frame.copy_from(&frame.framebuffer);
+            }
+
+            // En frames lentos (calidad alta) se muestra el progreso tile a tile
+            let slow_frame = worker
+                .busy_for()
+                .is_some_and(|elapsed| elapsed > PROGRESSIVE_PRESENT_DELAY);
+            let overlay = |renderer: &mut RaylibDrawHandle| {
+                let world = world.read().unwrap();
+                editor.draw_overlay(renderer, &camera, world.palette(), &texture_manager, window_width, window_height);
+            };
+            if slow_frame {
+                worker.copy_preview_into(&mut preview_frame);
+                preview_frame.swap_buffers(&mut window, &thread, render_fps, window_width, window_height, overlay);
+            } else {
+                displayed_frame.swap_buffers(&mut window, &thread, render_fps, window_width, window_height, overlay);
+            }
         }
 
-        // Obtener el framebuffer correspondiente a la calidad actual
-        let current_framebuffer = framebuffers.get_mut(current_framebuffer_key).unwrap();
-        
-        render(
-            current_framebuffer,
-            &objects,
-            &emissive_sources,
-            &camera,
-            &light,
-            &texture_manager,
-            &current_quality,
-        );
-        
-    
-        current_framebuffer.swap_buffers(&mut window, &thread, true, window_width, window_height);
+        worker.shutdown();
+    });
+
+    // Lo editado no se pierde al cerrar la ventana
+    if editor.history.has_unsaved() {
+        let world_path = world_path.get_or_insert_with(world_file::unused_world_path);
+        save_world(&world, &mut editor, world_path);
+        println!("Para seguir editándolo: --world {}", world_path);
     }
 }
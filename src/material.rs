@@ -1,6 +1,6 @@
 use raylib::prelude::{Color, Vector3};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Material {
     pub diffuse: Vector3,
     pub albedo: [f32; 4],
@@ -65,9 +65,9 @@ impl Material {
 
 pub fn vector3_to_color(v: Vector3) -> Color {
     Color::new(
-        (v.x * 255.0).min(255.0).max(0.0) as u8,
-        (v.y * 255.0).min(255.0).max(0.0) as u8,
-        (v.z * 255.0).min(255.0).max(0.0) as u8,
+        (v.x * 255.0).clamp(0.0, 255.0) as u8,
+        (v.y * 255.0).clamp(0.0, 255.0) as u8,
+        (v.z * 255.0).clamp(0.0, 255.0) as u8,
         255,
     )
 }
@@ -0,0 +1,459 @@
+use base64::Engine;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::cube::{Cube, Vec3};
+use crate::faces::{FACE_DIRECTIONS, Occupancy};
+use crate::material::Material;
+
+// Ejes de cada cara (en el orden de FACE_DIRECTIONS) con t1 × t2 = normal, para que las esquinas
+// -t1-t2, +t1-t2, +t1+t2, -t1+t2 queden en sentido antihorario vistas desde fuera
+const FACE_TANGENTS: [(Vec3, Vec3); 6] = [
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(0.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 0.0)),
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+];
+const CORNER_SIGNS: [(f32, f32); 4] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+
+// Cara visible de un cubo: esquinas, índice de la cara y UVs con v hacia abajo (como la imagen)
+struct Quad {
+    corners: [Vec3; 4],
+    face: usize,
+    uvs: [(f32, f32); 4],
+}
+
+// Caras visibles de los cubos agrupadas por material, en el orden en que aparecen
+struct Surface {
+    materials: Vec<Material>,
+    quads: Vec<Vec<Quad>>,
+}
+
+impl Surface {
+    fn build(cubes: &[&Cube]) -> Self {
+        let occupancy = Occupancy::new(cubes);
+        let mut surface = Surface { materials: Vec::new(), quads: Vec::new() };
+        for cube in cubes {
+            let mask = occupancy.exposed_faces(cube);
+            let material = match surface.materials.iter().position(|material| *material == cube.material) {
+                Some(index) => index,
+                None => {
+                    surface.materials.push(cube.material.clone());
+                    surface.quads.push(Vec::new());
+                    surface.materials.len() - 1
+                }
+            };
+            for face in (0..6).filter(|face| mask & (1 << face) != 0) {
+                surface.quads[material].push(face_quad(cube, face));
+            }
+        }
+        surface
+    }
+
+    fn face_count(&self) -> usize {
+        self.quads.iter().map(Vec::len).sum()
+    }
+}
+
+fn face_quad(cube: &Cube, face: usize) -> Quad {
+    let (dx, dy, dz) = FACE_DIRECTIONS[face];
+    let normal = Vec3::new(dx as f32, dy as f32, dz as f32);
+    let (t1, t2) = FACE_TANGENTS[face];
+    let mut corners = [Vec3::default(); 4];
+    let mut uvs = [(0.0, 0.0); 4];
+    for (corner, (s1, s2)) in CORNER_SIGNS.into_iter().enumerate() {
+        // Esquina en coordenadas locales normalizadas a [-1, 1]
+        let local = normal + t1 * s1 + t2 * s2;
+        corners[corner] = cube.center + local * cube.half;
+        uvs[corner] = face_uv(normal, local);
+    }
+    Quad { corners, face, uvs }
+}
+
+// Mismas coordenadas de textura que `Cube::intersect`, para que el modelo exportado se vea igual
+fn face_uv(normal: Vec3, local: Vec3) -> (f32, f32) {
+    if normal.x != 0.0 {
+        ((local.z + 1.0) * 0.5, (1.0 - local.y) * 0.5)
+    } else if normal.y != 0.0 {
+        ((local.x + 1.0) * 0.5, (local.z + 1.0) * 0.5)
+    } else if normal.z > 0.0 {
+        ((local.x + 1.0) * 0.5, (1.0 - local.y) * 0.5)
+    } else {
+        ((1.0 - local.x) * 0.5, (1.0 - local.y) * 0.5)
+    }
+}
+
+// Escribe los cubos como Wavefront OBJ con su MTL al lado (mismo nombre, extensión `.mtl`).
+// Las texturas se referencian con rutas relativas al OBJ. Devuelve cuántas caras se escribieron
+pub fn export_obj(path: &str, cubes: &[&Cube]) -> Result<usize, String> {
+    let mtl_path = Path::new(path).with_extension("mtl");
+    let mtl_name = mtl_path.file_name().unwrap_or_default().to_string_lossy();
+    let (obj, mtl, faces) = format_obj(cubes, &mtl_name, &output_directory(path))?;
+    std::fs::write(path, obj).map_err(|error| format!("No se pudo escribir {}: {}", path, error))?;
+    std::fs::write(&mtl_path, mtl).map_err(|error| format!("No se pudo escribir {}: {}", mtl_path.display(), error))?;
+    Ok(faces)
+}
+
+// Contenido del OBJ y de su MTL (que el OBJ cita como `mtl_name`) y número de caras; las texturas
+// se escriben relativas a `directory`
+fn format_obj(cubes: &[&Cube], mtl_name: &str, directory: &Path) -> Result<(String, String, usize), String> {
+    let surface = Surface::build(cubes);
+    if surface.face_count() == 0 {
+        return Err("No hay bloques que exportar".to_string());
+    }
+
+    let mut obj = String::new();
+    let _ = writeln!(obj, "mtllib {}", mtl_name);
+    let _ = writeln!(obj, "o diorama");
+    for &(dx, dy, dz) in &FACE_DIRECTIONS {
+        let _ = writeln!(obj, "vn {} {} {}", dx, dy, dz);
+    }
+    let mut vertex = 1;
+    for (material, quads) in surface.quads.iter().enumerate() {
+        let _ = writeln!(obj, "usemtl material_{}", material);
+        for quad in quads {
+            for (corner, (u, v)) in quad.corners.iter().zip(quad.uvs) {
+                let _ = writeln!(obj, "v {} {} {}", corner.x, corner.y, corner.z);
+                // En OBJ la v crece hacia arriba
+                let _ = writeln!(obj, "vt {} {}", u, 1.0 - v);
+            }
+            let normal = quad.face + 1;
+            let corners: Vec<String> = (vertex..vertex + 4).map(|index| format!("{0}/{0}/{1}", index, normal)).collect();
+            let _ = writeln!(obj, "f {}", corners.join(" "));
+            vertex += 4;
+        }
+    }
+
+    let mut mtl = String::new();
+    for (index, material) in surface.materials.iter().enumerate() {
+        let [_, specular, reflect, transparency] = material.albedo;
+        // Con textura, el trazador ignora el difuso
+        let diffuse = if material.texture_id.is_some() { Vec3::new(1.0, 1.0, 1.0) } else { Vec3::from_vector3(material.diffuse) };
+        let texture = texture_file(material);
+        let emission = Vec3::from_vector3(material.emission) * material.emission_strength;
+        let _ = writeln!(mtl, "newmtl material_{}", index);
+        let _ = writeln!(mtl, "Kd {} {} {}", diffuse.x, diffuse.y, diffuse.z);
+        let _ = writeln!(mtl, "Ks {0} {0} {0}", specular);
+        let _ = writeln!(mtl, "Ns {}", material.specular);
+        let _ = writeln!(mtl, "illum {}", if reflect > 0.0 { 3 } else { 2 });
+        if reflect > 0.0 {
+            let _ = writeln!(mtl, "Pm {}", reflect);
+        }
+        if transparency > 0.0 {
+            let _ = writeln!(mtl, "d {}", 1.0 - transparency);
+            let _ = writeln!(mtl, "Ni {}", material.refractive_index);
+        }
+        if material.emission_strength > 0.0 {
+            let _ = writeln!(mtl, "Ke {} {} {}", emission.x, emission.y, emission.z);
+        }
+        if let Some(texture) = texture {
+            let _ = writeln!(mtl, "map_Kd {}", relative_path(directory, texture));
+        }
+        let _ = writeln!(mtl);
+    }
+    Ok((obj, mtl, surface.face_count()))
+}
+
+// Escribe los cubos como glTF 2.0 autocontenido: la geometría y las texturas van embebidas en base64.
+// Un primitivo por material, con muestreo sin filtrar como en el trazador. Devuelve cuántas caras se escribieron
+pub fn export_gltf(path: &str, cubes: &[&Cube]) -> Result<usize, String> {
+    let (gltf, faces) = format_gltf(cubes)?;
+    std::fs::write(path, gltf).map_err(|error| format!("No se pudo escribir {}: {}", path, error))?;
+    Ok(faces)
+}
+
+// Documento glTF completo y número de caras
+fn format_gltf(cubes: &[&Cube]) -> Result<(String, usize), String> {
+    let surface = Surface::build(cubes);
+    if surface.face_count() == 0 {
+        return Err("No hay bloques que exportar".to_string());
+    }
+
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    // Añade un bloque al búfer con su vista y su accesor; devuelve el índice del accesor
+    let mut push_accessor = |data: Vec<u8>, count: usize, kind: &str, component: u32, target: u32, bounds: &str| {
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            buffer.len(),
+            data.len(),
+            target
+        ));
+        buffer.extend(data);
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"{}"{}}}"#,
+            buffer_views.len() - 1,
+            component,
+            count,
+            kind,
+            bounds
+        ));
+        accessors.len() - 1
+    };
+
+    let mut primitives = Vec::new();
+    for (material, quads) in surface.quads.iter().enumerate() {
+        if quads.is_empty() {
+            continue;
+        }
+        let (mut positions, mut normals, mut uvs, mut indices) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+        for (quad_index, quad) in quads.iter().enumerate() {
+            let (dx, dy, dz) = FACE_DIRECTIONS[quad.face];
+            for (corner, (u, v)) in quad.corners.iter().zip(quad.uvs) {
+                for (axis, value) in [corner.x, corner.y, corner.z].into_iter().enumerate() {
+                    min[axis] = min[axis].min(value);
+                    max[axis] = max[axis].max(value);
+                    positions.extend(value.to_le_bytes());
+                }
+                for value in [dx as f32, dy as f32, dz as f32] {
+                    normals.extend(value.to_le_bytes());
+                }
+                uvs.extend(u.to_le_bytes());
+                uvs.extend(v.to_le_bytes());
+            }
+            let first = (quad_index * 4) as u32;
+            for index in [0, 1, 2, 0, 2, 3] {
+                indices.extend((first + index).to_le_bytes());
+            }
+        }
+
+        let vertex_count = quads.len() * 4;
+        let bounds = format!(r#","min":[{},{},{}],"max":[{},{},{}]"#, min[0], min[1], min[2], max[0], max[1], max[2]);
+        let position = push_accessor(positions, vertex_count, "VEC3", FLOAT, ARRAY_BUFFER, &bounds);
+        let normal = push_accessor(normals, vertex_count, "VEC3", FLOAT, ARRAY_BUFFER, "");
+        let uv = push_accessor(uvs, vertex_count, "VEC2", FLOAT, ARRAY_BUFFER, "");
+        let index = push_accessor(indices, quads.len() * 6, "SCALAR", UNSIGNED_INT, ELEMENT_ARRAY_BUFFER, "");
+        primitives.push(format!(
+            r#"{{"attributes":{{"POSITION":{},"NORMAL":{},"TEXCOORD_0":{}}},"indices":{},"material":{}}}"#,
+            position, normal, uv, index, material
+        ));
+    }
+
+    // Cada textura se embebe una sola vez aunque la usen varios materiales
+    let mut texture_paths: Vec<&str> = Vec::new();
+    let mut images = Vec::new();
+    let mut materials = Vec::new();
+    let mut extensions_used: Vec<&str> = Vec::new();
+    for (index, material) in surface.materials.iter().enumerate() {
+        let [_, _, reflect, transparency] = material.albedo;
+        let mut pbr = String::new();
+        let base_color = match texture_file(material) {
+            Some(texture) => {
+                let texture_index = match texture_paths.iter().position(|&path| path == texture) {
+                    Some(texture_index) => texture_index,
+                    None => {
+                        images.push(embedded_image(texture)?);
+                        texture_paths.push(texture);
+                        texture_paths.len() - 1
+                    }
+                };
+                let _ = write!(pbr, r#""baseColorTexture":{{"index":{}}},"#, texture_index);
+                Vec3::new(1.0, 1.0, 1.0)
+            }
+            None if material.texture_id.is_some() => Vec3::new(1.0, 1.0, 1.0),
+            None => Vec3::from_vector3(material.diffuse),
+        };
+        // Inversa de la equivalencia rugosidad ↔ exponente de Blinn-Phong que usa la importación
+        let roughness = (2.0 / (material.specular.max(0.0) + 2.0)).powf(0.25);
+        let _ = write!(
+            pbr,
+            r#""baseColorFactor":[{},{},{},1],"metallicFactor":{},"roughnessFactor":{}"#,
+            base_color.x, base_color.y, base_color.z, reflect, roughness
+        );
+
+        let mut extensions: Vec<(&str, String)> = Vec::new();
+        let mut emissive = String::new();
+        if material.emission_strength > 0.0 {
+            let emission = material.emission;
+            let _ = write!(emissive, r#","emissiveFactor":[{},{},{}]"#, emission.x, emission.y, emission.z);
+            if material.emission_strength != 1.0 {
+                let strength = format!(r#"{{"emissiveStrength":{}}}"#, material.emission_strength);
+                extensions.push(("KHR_materials_emissive_strength", strength));
+            }
+        }
+        if transparency > 0.0 {
+            extensions.push(("KHR_materials_transmission", format!(r#"{{"transmissionFactor":{}}}"#, transparency)));
+            extensions.push(("KHR_materials_ior", format!(r#"{{"ior":{}}}"#, material.refractive_index)));
+        }
+        for (name, _) in &extensions {
+            if !extensions_used.contains(name) {
+                extensions_used.push(name);
+            }
+        }
+        let extensions: Vec<String> = extensions.iter().map(|(name, value)| format!(r#""{}":{}"#, name, value)).collect();
+        let extensions = if extensions.is_empty() { String::new() } else { format!(r#","extensions":{{{}}}"#, extensions.join(",")) };
+        materials.push(format!(
+            r#"{{"name":"material_{}","pbrMetallicRoughness":{{{}}}{}{}}}"#,
+            index, pbr, emissive, extensions
+        ));
+    }
+    let extensions_used: Vec<String> = extensions_used.iter().map(|name| format!(r#""{}""#, name)).collect();
+    let extensions_used = extensions_used.join(",");
+    let extensions_used = if extensions_used.is_empty() { String::new() } else { format!(r#""extensionsUsed":[{}],"#, extensions_used) };
+
+    // Las listas de primer nivel no pueden ir vacías: sin texturas no se escriben
+    let textures: Vec<String> = (0..images.len()).map(|image| format!(r#"{{"sampler":0,"source":{}}}"#, image)).collect();
+    let textures = if images.is_empty() {
+        String::new()
+    } else {
+        format!(
+            concat!(
+                r#""textures":[{}],"images":[{}],"#,
+                // Filtro más cercano y sin repetición: los píxeles se ven como en el trazador
+                r#""samplers":[{{"magFilter":9728,"minFilter":9728,"wrapS":33071,"wrapT":33071}}],"#
+            ),
+            textures.join(","),
+            images.join(",")
+        )
+    };
+    let byte_length = buffer.len();
+    let buffer = base64::engine::general_purpose::STANDARD.encode(&buffer);
+    let gltf = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"Proyect_2"}},{}"#,
+            r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"name":"diorama","mesh":0}}],"#,
+            r#""meshes":[{{"primitives":[{}]}}],"materials":[{}],{}"#,
+            r#""accessors":[{}],"bufferViews":[{}],"#,
+            r#""buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}]}}"#
+        ),
+        extensions_used,
+        primitives.join(","),
+        materials.join(","),
+        textures,
+        accessors.join(","),
+        buffer_views.join(","),
+        byte_length,
+        buffer
+    );
+    Ok((gltf, surface.face_count()))
+}
+
+// Tipos de componente y destinos de las vistas de búfer (constantes de OpenGL)
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+// Textura del material si el archivo existe; si falta, el trazador pinta el bloque de blanco
+fn texture_file(material: &Material) -> Option<&str> {
+    material.texture_id.as_deref().filter(|texture| Path::new(texture).is_file())
+}
+
+fn embedded_image(texture: &str) -> Result<String, String> {
+    let bytes = std::fs::read(texture).map_err(|error| format!("No se pudo leer {}: {}", texture, error))?;
+    let lowercase = texture.to_lowercase();
+    let mime = if lowercase.ends_with(".jpg") || lowercase.ends_with(".jpeg") { "image/jpeg" } else { "image/png" };
+    Ok(format!(
+        r#"{{"uri":"data:{};base64,{}"}}"#,
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+fn output_directory(path: &str) -> PathBuf {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+// Ruta de `file` vista desde `directory` (si alguno no existe, la ruta tal cual)
+fn relative_path(directory: &Path, file: &str) -> String {
+    let (Ok(base), Ok(target)) = (directory.canonicalize(), Path::new(file).canonicalize()) else {
+        return file.to_string();
+    };
+    let common = base.components().zip(target.components()).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.components().count() {
+        relative.push("..");
+    }
+    for component in target.components().skip(common) {
+        relative.push(component);
+    }
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::plain;
+    use raylib::prelude::Vector3;
+
+    fn block(x: f32, material: &Material) -> Cube {
+        Cube::new(Vec3::new(x, 0.0, 0.0), Vec3::new(0.5, 0.5, 0.5), material.clone())
+    }
+
+    fn water() -> Material {
+        Material::new(Vector3::new(0.2, 0.4, 0.9), 50.0, [0.1, 0.3, 0.0, 0.5], 1.33, None)
+    }
+
+    fn obj_faces(cubes: &[Cube]) -> (String, String, usize) {
+        format_obj(&cubes.iter().collect::<Vec<_>>(), "prueba.mtl", Path::new(".")).unwrap()
+    }
+
+    // Triángulos de cada primitivo del glTF, leídos con el mismo crate que usa la importación
+    fn gltf_triangles(cubes: &[Cube]) -> (Vec<usize>, usize) {
+        let (document, faces) = format_gltf(&cubes.iter().collect::<Vec<_>>()).unwrap();
+        let gltf = gltf::Gltf::from_slice(document.as_bytes()).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let triangles = mesh.primitives().map(|primitive| primitive.indices().unwrap().count() / 3).collect();
+        (triangles, faces)
+    }
+
+    #[test]
+    fn adjacent_cubes_share_no_faces() {
+        let stone = plain(0.5, 0.5, 0.5);
+        let cubes = [block(0.0, &stone), block(1.0, &stone)];
+
+        let (obj, mtl, faces) = obj_faces(&cubes);
+        assert_eq!(faces, 10);
+        assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 10);
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 40);
+        assert!(obj.starts_with("mtllib prueba.mtl\n"));
+        assert_eq!(mtl.matches("newmtl").count(), 1);
+        // Cada cara son cuatro vértices seguidos; ninguna queda en el plano x = 0.5 que separa los cubos
+        let vertices: Vec<&str> = obj.lines().filter(|line| line.starts_with("v ")).collect();
+        assert!(!vertices.chunks(4).any(|face| face.iter().all(|vertex| vertex.starts_with("v 0.5 "))));
+
+        assert_eq!(gltf_triangles(&cubes), (vec![20], 10));
+    }
+
+    #[test]
+    fn each_material_gets_its_own_group() {
+        let cubes = [block(0.0, &plain(1.0, 0.0, 0.0)), block(1.0, &plain(0.0, 0.0, 1.0))];
+        let (obj, mtl, faces) = obj_faces(&cubes);
+        assert_eq!(faces, 10);
+        assert_eq!(obj.matches("usemtl").count(), 2);
+        assert!(mtl.contains("newmtl material_0\nKd 1 0 0\n") && mtl.contains("newmtl material_1\nKd 0 0 1\n"));
+        assert_eq!(gltf_triangles(&cubes), (vec![10, 10], 10));
+    }
+
+    #[test]
+    fn water_hides_only_faces_against_water() {
+        let water = water();
+        assert_eq!(obj_faces(&[block(0.0, &water), block(1.0, &water)]).2, 10);
+        // La piedra tapa la cara del agua, pero el agua no tapa la de la piedra
+        let cubes = [block(0.0, &water), block(1.0, &plain(0.5, 0.5, 0.5))];
+        assert_eq!(obj_faces(&cubes).2, 11);
+        assert_eq!(gltf_triangles(&cubes), (vec![10, 12], 11));
+        let (_, mtl, _) = obj_faces(&cubes);
+        assert!(mtl.contains("\nd 0.5\nNi 1.33\n"), "{}", mtl);
+    }
+
+    #[test]
+    fn nothing_to_export() {
+        let stone = plain(0.5, 0.5, 0.5);
+        let mut cubes = vec![block(0.0, &stone)];
+        cubes.extend(FACE_DIRECTIONS.iter().map(|&(x, y, z)| {
+            Cube::new(Vec3::new(x as f32, y as f32, z as f32), Vec3::new(0.5, 0.5, 0.5), stone.clone())
+        }));
+        assert!(format_gltf(&[]).is_err());
+        assert_eq!(format_obj(&[], "vacío.mtl", Path::new(".")).err(), Some("No hay bloques que exportar".to_string()));
+        // El bloque central está rodeado y no aporta caras
+        assert_eq!(obj_faces(&cubes).2, 6 * 5);
+    }
+}
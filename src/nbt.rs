@@ -0,0 +1,246 @@
+use flate2::read::{GzDecoder, ZlibDecoder};
+use std::collections::HashMap;
+use std::io::Read;
+
+// Profundidad máxima de anidamiento: un archivo corrupto no debe desbordar la pila. Las estructuras
+// reales no pasan de unos pocos niveles; con 512 la pila de un hilo de 2 MB ya no alcanza sin optimizar
+const MAX_DEPTH: usize = 128;
+
+// Valor NBT (el formato binario de Minecraft). Se conservan todos los tipos aunque las
+// estructuras solo lean enteros, cadenas, listas y compuestos
+#[derive(Clone, Debug, PartialEq)]
+#[allow(dead_code)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.get(key),
+            _ => None,
+        }
+    }
+
+    // Cualquier entero como i32 (las dimensiones se guardan como Short en unos formatos e Int en otros)
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self {
+            Tag::Byte(value) => Some(value as i32),
+            Tag::Short(value) => Some(value as i32),
+            Tag::Int(value) => Some(value),
+            Tag::Long(value) => i32::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&HashMap<String, Tag>> {
+        match self {
+            Tag::Compound(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[i8]> {
+        match self {
+            Tag::ByteArray(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    // Lista de enteros o IntArray, p. ej. una posición [x, y, z]
+    pub fn as_ints(&self) -> Option<Vec<i32>> {
+        match self {
+            Tag::IntArray(values) => Some(values.clone()),
+            Tag::List(values) => values.iter().map(Tag::as_i32).collect(),
+            _ => None,
+        }
+    }
+}
+
+// Lee un archivo NBT, comprimido con gzip o zlib o sin comprimir, y devuelve la etiqueta raíz
+pub fn read_nbt(bytes: &[u8]) -> Result<Tag, String> {
+    let mut data = Vec::new();
+    let decompressed = match bytes {
+        [0x1f, 0x8b, ..] => GzDecoder::new(bytes).read_to_end(&mut data).map(|_| data.as_slice()),
+        [0x78, ..] => ZlibDecoder::new(bytes).read_to_end(&mut data).map(|_| data.as_slice()),
+        _ => Ok(bytes),
+    };
+    let bytes = decompressed.map_err(|error| format!("no se pudo descomprimir: {}", error))?;
+
+    let mut reader = Reader { bytes, position: 0 };
+    let kind = reader.u8()?;
+    if kind != 10 {
+        return Err("la raíz NBT no es un compuesto".to_string());
+    }
+    let _name = reader.string()?;
+    reader.payload(kind, 0)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or("el archivo NBT está truncado")?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        let length = i32::from_be_bytes(self.array()?);
+        usize::try_from(length).map_err(|_| format!("longitud negativa en NBT: {}", length))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = u16::from_be_bytes(self.array()?) as usize;
+        Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+    }
+
+    fn payload(&mut self, kind: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_DEPTH {
+            return Err("NBT demasiado anidado".to_string());
+        }
+        Ok(match kind {
+            1 => Tag::Byte(self.u8()? as i8),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.array()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let length = self.length()?;
+                Tag::ByteArray(self.take(length)?.iter().map(|&byte| byte as i8).collect())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let element = self.u8()?;
+                let length = self.length()?;
+                if element == 0 && length > 0 {
+                    return Err("lista NBT de etiquetas vacías".to_string());
+                }
+                let values = (0..length).map(|_| self.payload(element, depth + 1)).collect::<Result<_, _>>()?;
+                Tag::List(values)
+            }
+            10 => {
+                let mut entries = HashMap::new();
+                loop {
+                    let kind = self.u8()?;
+                    if kind == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.insert(name, self.payload(kind, depth + 1)?);
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let length = self.length()?;
+                let values = self.take(length.checked_mul(4).ok_or("IntArray demasiado largo")?)?;
+                Tag::IntArray(values.chunks_exact(4).map(|value| i32::from_be_bytes(value.try_into().unwrap())).collect())
+            }
+            12 => {
+                let length = self.length()?;
+                let values = self.take(length.checked_mul(8).ok_or("LongArray demasiado largo")?)?;
+                Tag::LongArray(values.chunks_exact(8).map(|value| i64::from_be_bytes(value.try_into().unwrap())).collect())
+            }
+            _ => return Err(format!("tipo de etiqueta NBT desconocido: {}", kind)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{compound, nbt_bytes};
+    use flate2::Compression;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use std::io::Write;
+
+    fn every_kind() -> Tag {
+        compound(vec![
+            ("byte", Tag::Byte(-3)),
+            ("short", Tag::Short(-300)),
+            ("int", Tag::Int(70000)),
+            ("long", Tag::Long(-5_000_000_000)),
+            ("float", Tag::Float(1.5)),
+            ("double", Tag::Double(-0.25)),
+            ("bytes", Tag::ByteArray(vec![1, -1, 0])),
+            ("string", Tag::String("minecraft:stone".to_string())),
+            ("list", Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+            ("empty", Tag::List(Vec::new())),
+            ("nested", compound(vec![("name", Tag::String("ñandú".to_string()))])),
+            ("ints", Tag::IntArray(vec![1, -2, 3])),
+            ("longs", Tag::LongArray(vec![i64::MIN, 0])),
+        ])
+    }
+
+    #[test]
+    fn reads_every_kind_plain_and_compressed() {
+        let root = every_kind();
+        let bytes = nbt_bytes(&root);
+        assert_eq!(read_nbt(&bytes), Ok(root.clone()));
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&bytes).unwrap();
+        assert_eq!(read_nbt(&gzip.finish().unwrap()), Ok(root.clone()));
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&bytes).unwrap();
+        assert_eq!(read_nbt(&zlib.finish().unwrap()), Ok(root));
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let bytes = nbt_bytes(&every_kind());
+        assert!(read_nbt(&bytes[..bytes.len() - 1]).is_err());
+        assert!(read_nbt(&nbt_bytes(&Tag::Int(1))).is_err());
+        // Longitud negativa de un ByteArray
+        let mut negative = vec![10, 0, 0, 7, 0, 1, b'a'];
+        negative.extend((-1i32).to_be_bytes());
+        assert!(read_nbt(&negative).is_err());
+
+        // Listas de listas más allá del límite de anidamiento
+        let mut deep = vec![10, 0, 0, 9, 0, 1, b'a'];
+        for _ in 0..MAX_DEPTH + 2 {
+            deep.extend([9, 0, 0, 0, 1]);
+        }
+        assert_eq!(read_nbt(&deep), Err("NBT demasiado anidado".to_string()));
+    }
+}
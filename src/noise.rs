@@ -0,0 +1,146 @@
+// Generador pseudoaleatorio pequeño y reproducible (SplitMix64) para todo lo que depende de una semilla
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Rng { state: u64::from(seed) ^ 0x9E37_79B9_7F4A_7C15 }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 32) as u32
+    }
+
+    // Valor uniforme en [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    // Entero uniforme en [min, max]
+    pub fn range_i32(&mut self, min: i32, max: i32) -> i32 {
+        min + (self.next_u32() % (max - min + 1) as u32) as i32
+    }
+}
+
+// Ruido de Perlin mejorado con la tabla de permutaciones barajada a partir de una semilla.
+// Devuelve valores aproximadamente en [-1, 1]
+pub struct Perlin {
+    permutation: [u8; 256],
+}
+
+impl Perlin {
+    pub fn new(seed: u32) -> Self {
+        let mut permutation: [u8; 256] = std::array::from_fn(|i| i as u8);
+        let mut rng = Rng::new(seed);
+        for i in (1..permutation.len()).rev() {
+            let j = rng.next_u32() as usize % (i + 1);
+            permutation.swap(i, j);
+        }
+        Perlin { permutation }
+    }
+
+    fn hash(&self, i: i32) -> usize {
+        self.permutation[(i & 255) as usize] as usize
+    }
+
+    pub fn noise2(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor() as i32, y.floor() as i32);
+        let (xf, yf) = (x - x.floor(), y - y.floor());
+        let (u, v) = (fade(xf), fade(yf));
+
+        let corner = |dx: i32, dy: i32| {
+            let hash = self.hash(self.hash(xi + dx) as i32 + yi + dy);
+            gradient2(hash, xf - dx as f32, yf - dy as f32)
+        };
+        lerp(
+            lerp(corner(0, 0), corner(1, 0), u),
+            lerp(corner(0, 1), corner(1, 1), u),
+            v,
+        )
+    }
+
+    pub fn noise3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, yi, zi) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let (xf, yf, zf) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            let hash = self.hash(self.hash(self.hash(xi + dx) as i32 + yi + dy) as i32 + zi + dz);
+            gradient3(hash, xf - dx as f32, yf - dy as f32, zf - dz as f32)
+        };
+        lerp(
+            lerp(
+                lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+                lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+                v,
+            ),
+            lerp(
+                lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+                lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+                v,
+            ),
+            w,
+        )
+    }
+
+    // Ruido fractal: suma de octavas que doblan la frecuencia y reducen la amplitud a la mitad,
+    // normalizada para seguir en [-1, 1]
+    pub fn fbm2(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let (mut sum, mut total, mut amplitude, mut frequency) = (0.0, 0.0, 1.0, 1.0);
+        for _ in 0..octaves {
+            sum += self.noise2(x * frequency, y * frequency) * amplitude;
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn gradient2(hash: usize, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+// Las doce direcciones de las aristas de un cubo (más cuatro repetidas para llegar a 16)
+fn gradient3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    match hash & 15 {
+        0 | 12 => x + y,
+        1 | 14 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 13 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
@@ -0,0 +1,332 @@
+use raylib::prelude::Vector3;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::material::Material;
+use crate::primitives::{Mesh, Triangle};
+use crate::textures::TextureManager;
+
+// Propiedades de un material MTL tal como vienen en el archivo
+struct MtlMaterial {
+    diffuse: Vector3,
+    specular: Vector3,
+    emission: Vector3,
+    shininess: f32,
+    refractive_index: f32,
+    opacity: f32,
+    illum: u32,
+    metallic: Option<f32>,
+    texture: Option<String>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        MtlMaterial {
+            diffuse: Vector3::new(0.8, 0.8, 0.8),
+            specular: Vector3::zero(),
+            emission: Vector3::zero(),
+            shininess: 10.0,
+            refractive_index: 1.0,
+            opacity: 1.0,
+            illum: 2,
+            metallic: None,
+            texture: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    // Traduce el modelo de Phong del MTL a los pesos de albedo del trazador
+    // [difuso, especular, reflexión, transparencia]
+    fn to_material(&self) -> Material {
+        let transparency = (1.0 - self.opacity).clamp(0.0, 1.0);
+        let specular_weight = (self.specular.x + self.specular.y + self.specular.z) / 3.0;
+        // illum 3, 5 y 7 son los modos con reflejo trazado
+        let reflectivity = self
+            .metallic
+            .unwrap_or(if matches!(self.illum, 3 | 5 | 7) { specular_weight } else { 0.0 })
+            .clamp(0.0, 1.0);
+        let albedo = [
+            0.9 * (1.0 - transparency) * (1.0 - reflectivity),
+            specular_weight,
+            reflectivity,
+            transparency,
+        ];
+        let refractive_index = if transparency > 0.0 { self.refractive_index } else { 0.0 };
+        let shininess = self.shininess.clamp(1.0, 200.0);
+
+        let emission_strength = self.emission.x.max(self.emission.y).max(self.emission.z);
+        if emission_strength > 0.0 {
+            Material::new_emissive(
+                self.diffuse,
+                shininess,
+                albedo,
+                refractive_index,
+                self.texture.clone(),
+                self.emission / emission_strength,
+                emission_strength,
+            )
+        } else {
+            Material::new(self.diffuse, shininess, albedo, refractive_index, self.texture.clone())
+        }
+    }
+}
+
+// Carga un modelo Wavefront OBJ (con sus MTL) como una única malla. Las texturas `map_Kd`
+// se cargan en el TextureManager con su ruta relativa al directorio de trabajo.
+// Se admiten polígonos (se triangulan en abanico) e índices negativos.
+pub fn load_obj(path: &str, texture_manager: &mut TextureManager) -> Result<Mesh, String> {
+    let contents = std::fs::read_to_string(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+    let mut triangles = Vec::new();
+
+    // El material 0 es el de las caras sin `usemtl`
+    let mut materials = vec![MtlMaterial::default().to_material()];
+    let mut material_indices: HashMap<String, usize> = HashMap::new();
+    let mut current_material = 0;
+    // Órdenes que el trazador no usa (líneas, curvas, mapas...), para avisar una sola vez de cada una
+    let mut skipped: BTreeSet<&str> = BTreeSet::new();
+
+    for (line_index, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let line_error = |message: String| format!("{}: línea {}: {}", path, line_index + 1, message);
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens[0] {
+            "v" => positions.push(parse_vector(&tokens[1..]).map_err(line_error)?),
+            "vn" => normals.push(parse_vector(&tokens[1..]).map_err(line_error)?.normalized()),
+            "vt" => {
+                let u = parse_number(&tokens, 1).map_err(line_error)?;
+                let v = parse_number(&tokens, 2).unwrap_or(0.0);
+                uvs.push((u, v));
+            }
+            "f" => {
+                let corners = tokens[1..]
+                    .iter()
+                    .map(|corner| parse_corner(corner, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(line_error)?;
+                if corners.len() < 3 {
+                    return Err(line_error("una cara necesita al menos 3 vértices".to_string()));
+                }
+
+                for index in 1..corners.len() - 1 {
+                    let corners = [corners[0], corners[index], corners[index + 1]];
+                    triangles.push(Triangle {
+                        positions: corners.map(|(position, _, _)| positions[position]),
+                        normals: corners
+                            .iter()
+                            .all(|(_, _, normal)| normal.is_some())
+                            .then(|| corners.map(|(_, _, normal)| normals[normal.unwrap()])),
+                        uvs: corners
+                            .iter()
+                            .all(|(_, uv, _)| uv.is_some())
+                            .then(|| corners.map(|(_, uv, _)| uvs[uv.unwrap()])),
+                        material: current_material,
+                    });
+                }
+            }
+            "mtllib" => {
+                for library in &tokens[1..] {
+                    let library_path = join_path(directory, library);
+                    for (name, mtl) in load_mtl(&library_path)? {
+                        let material = mtl.to_material();
+                        if let Some(texture) = &material.texture_id {
+                            texture_manager.load_cpu_texture(texture);
+                        }
+                        material_indices.insert(name, materials.len());
+                        materials.push(material);
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = tokens.get(1).copied().unwrap_or("");
+                current_material = *material_indices
+                    .get(name)
+                    .ok_or_else(|| line_error(format!("material desconocido '{}'", name)))?;
+            }
+            // Grupos, objetos y grupos de suavizado no afectan al render
+            "o" | "g" | "s" => {}
+            other => {
+                skipped.insert(other);
+            }
+        }
+    }
+
+    if !skipped.is_empty() {
+        let skipped: Vec<&str> = skipped.into_iter().collect();
+        println!("{}: órdenes ignoradas: {}", path, skipped.join(", "));
+    }
+
+    if triangles.is_empty() {
+        return Err(format!("{}: el modelo no tiene caras", path));
+    }
+    Ok(Mesh::new(triangles, materials))
+}
+
+// Lee los materiales de un archivo MTL, en el orden en que aparecen
+fn load_mtl(path: &str) -> Result<Vec<(String, MtlMaterial)>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+    let mut materials: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for (line_index, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let line_error = |message: String| format!("{}: línea {}: {}", path, line_index + 1, message);
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        if tokens[0] == "newmtl" {
+            let name = tokens.get(1).ok_or_else(|| line_error("falta el nombre".to_string()))?;
+            materials.push((name.to_string(), MtlMaterial::default()));
+            continue;
+        }
+        let Some((_, material)) = materials.last_mut() else {
+            return Err(line_error("propiedad antes de 'newmtl'".to_string()));
+        };
+
+        match tokens[0] {
+            "Kd" => material.diffuse = parse_vector(&tokens[1..]).map_err(line_error)?,
+            "Ks" => material.specular = parse_vector(&tokens[1..]).map_err(line_error)?,
+            "Ke" => material.emission = parse_vector(&tokens[1..]).map_err(line_error)?,
+            "Ns" => material.shininess = parse_number(&tokens, 1).map_err(line_error)?,
+            "Ni" => material.refractive_index = parse_number(&tokens, 1).map_err(line_error)?,
+            "d" => material.opacity = parse_number(&tokens, 1).map_err(line_error)?,
+            "Tr" => material.opacity = 1.0 - parse_number(&tokens, 1).map_err(line_error)?,
+            "Pm" => material.metallic = Some(parse_number(&tokens, 1).map_err(line_error)?),
+            "illum" => material.illum = parse_number(&tokens, 1).map_err(line_error)? as u32,
+            "map_Kd" => {
+                // Las opciones (-s, -o, ...) van antes del nombre: el archivo es el último token
+                let file = tokens.last().filter(|_| tokens.len() > 1).ok_or_else(|| line_error("falta la textura".to_string()))?;
+                let texture = join_path(directory, file);
+                if !Path::new(&texture).is_file() {
+                    return Err(line_error(format!("no existe la textura {}", texture)));
+                }
+                material.texture = Some(texture);
+            }
+            // El resto (Ka, mapas de relieve, etc.) no tiene equivalente en el trazador
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+// Esquina de una cara: `v`, `v/vt`, `v//vn` o `v/vt/vn`, ya convertida a índices desde 0
+fn parse_corner(
+    corner: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = corner.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), position_count)?
+        .ok_or_else(|| format!("vértice sin posición '{}'", corner))?;
+    let uv = resolve_index(parts.next().unwrap_or(""), uv_count)?;
+    let normal = resolve_index(parts.next().unwrap_or(""), normal_count)?;
+    Ok((position, uv, normal))
+}
+
+// Los índices OBJ empiezan en 1; los negativos cuentan desde el final
+fn resolve_index(token: &str, count: usize) -> Result<Option<usize>, String> {
+    if token.is_empty() {
+        return Ok(None);
+    }
+    let index: i64 = token.parse().map_err(|_| format!("índice no válido '{}'", token))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!("índice fuera de rango '{}'", token));
+    }
+    Ok(Some(resolved as usize))
+}
+
+fn parse_number(tokens: &[&str], index: usize) -> Result<f32, String> {
+    let token = tokens.get(index).ok_or_else(|| "faltan valores".to_string())?;
+    token.parse::<f32>().map_err(|_| format!("número no válido '{}'", token))
+}
+
+fn parse_vector(tokens: &[&str]) -> Result<Vector3, String> {
+    Ok(Vector3::new(parse_number(tokens, 0)?, parse_number(tokens, 1)?, parse_number(tokens, 2)?))
+}
+
+// Une una ruta relativa al directorio del archivo que la nombra, resolviendo `.` y `..`,
+// para que la misma imagen tenga siempre la misma clave en el TextureManager
+// (p. ej. `assets/models/../wood.png` queda como `assets/wood.png`)
+pub fn join_path(directory: &Path, file: &str) -> String {
+    let joined = directory.join(file.replace('\\', "/"));
+    let mut parts: Vec<String> = Vec::new();
+    for component in joined.to_string_lossy().split('/') {
+        match component {
+            "" | "." if !parts.is_empty() => {}
+            ".." if parts.last().is_some_and(|last| !matches!(last.as_str(), ".." | "." | "")) => {
+                parts.pop();
+            }
+            _ => parts.push(component.to_string()),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_positive_negative_and_missing_indices() {
+        assert_eq!(resolve_index("1", 3), Ok(Some(0)));
+        assert_eq!(resolve_index("3", 3), Ok(Some(2)));
+        assert_eq!(resolve_index("-1", 3), Ok(Some(2)));
+        assert_eq!(resolve_index("-3", 3), Ok(Some(0)));
+        assert_eq!(resolve_index("", 3), Ok(None));
+        for token in ["0", "4", "-4", "x", "1.5"] {
+            assert!(resolve_index(token, 3).is_err(), "'{}' debería fallar", token);
+        }
+        assert!(resolve_index("1", 0).is_err());
+    }
+
+    #[test]
+    fn parses_face_corners() {
+        assert_eq!(parse_corner("2", 3, 0, 0), Ok((1, None, None)));
+        assert_eq!(parse_corner("2/1", 3, 2, 0), Ok((1, Some(0), None)));
+        assert_eq!(parse_corner("-1//-2", 3, 0, 2), Ok((2, None, Some(0))));
+        assert_eq!(parse_corner("1/2/3", 3, 2, 3), Ok((0, Some(1), Some(2))));
+        assert!(parse_corner("/1/1", 3, 1, 1).is_err());
+        assert!(parse_corner("1/3", 3, 2, 0).is_err());
+    }
+
+    #[test]
+    fn joins_paths_resolving_dots() {
+        assert_eq!(join_path(Path::new("assets/models"), "../wood.png"), "assets/wood.png");
+        assert_eq!(join_path(Path::new("assets/models"), "./textures/./bark.png"), "assets/models/textures/bark.png");
+        assert_eq!(join_path(Path::new("assets/models"), "..\\..\\sky.png"), "sky.png");
+        assert_eq!(join_path(Path::new(""), "../compartido/roca.png"), "../compartido/roca.png");
+        assert_eq!(join_path(Path::new("../modelos"), "../../a.png"), "../../a.png");
+        assert_eq!(join_path(Path::new("/srv/escena"), "../b.png"), "/srv/b.png");
+        assert_eq!(join_path(Path::new("assets"), "/tmp/c.png"), "/tmp/c.png");
+    }
+
+    #[test]
+    fn mtl_weights_follow_opacity_and_illumination() {
+        let glass = MtlMaterial { opacity: 0.25, refractive_index: 1.5, ..MtlMaterial::default() }.to_material();
+        assert_eq!(glass.albedo[3], 0.75);
+        assert_eq!(glass.refractive_index, 1.5);
+
+        let mirror = MtlMaterial { specular: Vector3::new(0.6, 0.6, 0.6), illum: 3, ..MtlMaterial::default() };
+        assert!((mirror.to_material().albedo[2] - 0.6).abs() < 1e-6);
+        let metal = MtlMaterial { metallic: Some(2.0), ..MtlMaterial::default() }.to_material();
+        assert_eq!(metal.albedo[2], 1.0);
+
+        let lamp = MtlMaterial { emission: Vector3::new(2.0, 1.0, 0.0), ..MtlMaterial::default() }.to_material();
+        assert_eq!(lamp.emission_strength, 2.0);
+        assert_eq!(lamp.emission, Vector3::new(1.0, 0.5, 0.0));
+    }
+}
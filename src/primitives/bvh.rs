@@ -0,0 +1,281 @@
+use raylib::prelude::Vector3;
+
+// Máximo de elementos por hoja; por debajo de esto no compensa seguir dividiendo
+const MAX_LEAF_SIZE: usize = 4;
+
+// Caja alineada a los ejes
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points(points: &[Vector3]) -> Self {
+        points.iter().fold(Aabb::empty(), |aabb, point| aabb.grow(*point))
+    }
+
+    pub fn grow(self, point: Vector3) -> Self {
+        Aabb {
+            min: Vector3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z)),
+            max: Vector3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z)),
+        }
+    }
+
+    pub fn union(self, other: Aabb) -> Self {
+        self.grow(other.min).grow(other.max)
+    }
+
+    pub fn expanded(self, margin: f32) -> Self {
+        let margin = Vector3::new(margin, margin, margin);
+        Aabb {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    pub fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extent(&self) -> Vector3 {
+        self.max - self.min
+    }
+
+    // Distancia de entrada del rayo en la caja (None si no la toca antes de `max_distance`)
+    pub fn ray_entry(&self, ray_origin: Vector3, inverse_direction: Vector3, max_distance: f32) -> Option<f32> {
+        let t1 = (self.min - ray_origin) * inverse_direction;
+        let t2 = (self.max - ray_origin) * inverse_direction;
+        let near = t1.x.min(t2.x).max(t1.y.min(t2.y)).max(t1.z.min(t2.z)).max(0.0);
+        let far = t1.x.max(t2.x).min(t1.y.max(t2.y)).min(t1.z.max(t2.z)).min(max_distance);
+        (near <= far).then_some(near)
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        let dx = (self.min.x - point.x).max(0.0).max(point.x - self.max.x);
+        let dy = (self.min.y - point.y).max(0.0).max(point.y - self.max.y);
+        let dz = (self.min.z - point.z).max(0.0).max(point.z - self.max.z);
+        dx * dx + dy * dy + dz * dz < radius * radius
+    }
+}
+
+// Nodo del árbol: si `count` > 0 es una hoja con los elementos [first, first + count),
+// si no, sus hijos están en `first` y `first + 1`
+#[derive(Clone, Copy, Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    first: usize,
+    count: usize,
+}
+
+// Jerarquía de cajas envolventes sobre una lista de elementos (p. ej. los triángulos de una malla)
+#[derive(Clone, Debug)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+}
+
+impl Bvh {
+    // Construye el árbol y devuelve, además, el orden en el que hay que guardar los elementos
+    // para que cada hoja apunte a un rango contiguo
+    pub fn build(element_bounds: &[Aabb]) -> (Self, Vec<usize>) {
+        let mut order: Vec<usize> = (0..element_bounds.len()).collect();
+        let mut nodes = vec![BvhNode {
+            bounds: Aabb::empty(),
+            first: 0,
+            count: order.len(),
+        }];
+        if !order.is_empty() {
+            Self::split(&mut nodes, 0, &mut order, element_bounds);
+        }
+        (Bvh { nodes }, order)
+    }
+
+    fn split(nodes: &mut Vec<BvhNode>, index: usize, order: &mut [usize], element_bounds: &[Aabb]) {
+        let BvhNode { first, count, .. } = nodes[index];
+        let elements = &mut order[first..first + count];
+        nodes[index].bounds = elements
+            .iter()
+            .fold(Aabb::empty(), |aabb, element| aabb.union(element_bounds[*element]));
+        if count <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        // División por la mediana de los centros en el eje más largo
+        let centers = elements
+            .iter()
+            .fold(Aabb::empty(), |aabb, element| aabb.grow(element_bounds[*element].center()));
+        let extent = centers.extent();
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let key = |element: &usize| {
+            let center = element_bounds[*element].center();
+            [center.x, center.y, center.z][axis]
+        };
+        let middle = count / 2;
+        elements.select_nth_unstable_by(middle, |a, b| key(a).total_cmp(&key(b)));
+
+        let left = nodes.len();
+        nodes.push(BvhNode { bounds: Aabb::empty(), first, count: middle });
+        nodes.push(BvhNode { bounds: Aabb::empty(), first: first + middle, count: count - middle });
+        nodes[index].first = left;
+        nodes[index].count = 0;
+
+        Self::split(nodes, left, order, element_bounds);
+        Self::split(nodes, left + 1, order, element_bounds);
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes[0].bounds
+    }
+
+    // Un árbol sin elementos es una sola hoja vacía; no hay que recorrerlo
+    fn is_empty(&self) -> bool {
+        self.nodes.len() == 1 && self.nodes[0].count == 0
+    }
+
+    // Recorre el árbol de delante hacia atrás. `hit(i, max)` prueba el elemento i y devuelve
+    // su distancia si lo toca antes de `max`; se devuelve el elemento más cercano
+    pub fn closest_hit<T>(
+        &self,
+        ray_origin: Vector3,
+        ray_direction: Vector3,
+        mut hit: impl FnMut(usize, f32) -> Option<(f32, T)>,
+    ) -> Option<(f32, T)> {
+        let inverse_direction = Vector3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
+        let mut closest: Option<(f32, T)> = None;
+        let mut stack = if self.is_empty() { Vec::new() } else { vec![0] };
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let max_distance = closest.as_ref().map_or(f32::INFINITY, |(t, _)| *t);
+            if node.bounds.ray_entry(ray_origin, inverse_direction, max_distance).is_none() {
+                continue;
+            }
+
+            if node.count > 0 {
+                for element in node.first..node.first + node.count {
+                    let max_distance = closest.as_ref().map_or(f32::INFINITY, |(t, _)| *t);
+                    if let Some(candidate) = hit(element, max_distance) {
+                        closest = Some(candidate);
+                    }
+                }
+                continue;
+            }
+
+            // Se apila primero el hijo lejano para visitar antes el cercano
+            let (left, right) = (node.first, node.first + 1);
+            let left_entry = self.nodes[left].bounds.ray_entry(ray_origin, inverse_direction, max_distance);
+            let right_entry = self.nodes[right].bounds.ray_entry(ray_origin, inverse_direction, max_distance);
+            match (left_entry, right_entry) {
+                (Some(l), Some(r)) if l <= r => stack.extend([right, left]),
+                (Some(_), Some(_)) => stack.extend([left, right]),
+                (Some(_), None) => stack.push(left),
+                (None, Some(_)) => stack.push(right),
+                (None, None) => {}
+            }
+        }
+
+        closest
+    }
+
+    // true si algún elemento cuya caja toca la esfera cumple `test`
+    pub fn any_in_sphere(&self, point: Vector3, radius: f32, mut test: impl FnMut(usize) -> bool) -> bool {
+        let mut stack = if self.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.overlaps_sphere(point, radius) {
+                continue;
+            }
+            if node.count > 0 {
+                if (node.first..node.first + node.count).any(&mut test) {
+                    return true;
+                }
+            } else {
+                stack.extend([node.first, node.first + 1]);
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::Rng;
+
+    // Cajas pequeñas repartidas al azar por un cubo de 20 de lado
+    fn scattered_boxes(count: usize) -> Vec<Aabb> {
+        let mut rng = Rng::new(5);
+        (0..count)
+            .map(|_| {
+                let center = Vector3::new(rng.range(-10.0, 10.0), rng.range(-10.0, 10.0), rng.range(-10.0, 10.0));
+                Aabb::from_points(&[center]).expanded(rng.range(0.1, 0.6))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn build_orders_every_element_once() {
+        let boxes = scattered_boxes(100);
+        let (bvh, mut order) = Bvh::build(&boxes);
+        order.sort();
+        assert_eq!(order, (0..100).collect::<Vec<_>>());
+        let all = boxes.iter().fold(Aabb::empty(), |aabb, element| aabb.union(*element));
+        assert_eq!((bvh.bounds().min, bvh.bounds().max), (all.min, all.max));
+
+        let (empty, order) = Bvh::build(&[]);
+        assert!(order.is_empty());
+        assert!(empty.closest_hit(Vector3::zero(), Vector3::new(1.0, 0.0, 0.0), |_, _| Some((1.0, ()))).is_none());
+        assert!(!empty.any_in_sphere(Vector3::zero(), 100.0, |_| true));
+    }
+
+    #[test]
+    fn closest_hit_matches_brute_force() {
+        let boxes = scattered_boxes(300);
+        let (bvh, order) = Bvh::build(&boxes);
+        let mut rng = Rng::new(11);
+        for _ in 0..200 {
+            let origin = Vector3::new(rng.range(-15.0, 15.0), rng.range(-15.0, 15.0), rng.range(-15.0, 15.0));
+            let direction = (Vector3::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), rng.range(-1.0, 1.0)) - origin * 0.05).normalized();
+            let inverse = Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+            let expected = boxes
+                .iter()
+                .enumerate()
+                .filter_map(|(element, aabb)| aabb.ray_entry(origin, inverse, f32::INFINITY).map(|t| (t, element)))
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+
+            // Los elementos se guardan en el orden de la BVH: `order` dice cuál es cada uno
+            let found = bvh.closest_hit(origin, direction, |slot, max_distance| {
+                boxes[order[slot]]
+                    .ray_entry(origin, inverse, max_distance)
+                    .map(|t| (t, order[slot]))
+            });
+            assert_eq!(found.map(|(t, _)| t), expected.map(|(t, _)| t));
+        }
+    }
+
+    #[test]
+    fn any_in_sphere_finds_only_nearby_elements() {
+        let boxes = scattered_boxes(200);
+        let (bvh, order) = Bvh::build(&boxes);
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            let point = Vector3::new(rng.range(-12.0, 12.0), rng.range(-12.0, 12.0), rng.range(-12.0, 12.0));
+            let radius = rng.range(0.1, 2.0);
+            let expected = boxes.iter().any(|aabb| aabb.overlaps_sphere(point, radius));
+            assert_eq!(bvh.any_in_sphere(point, radius, |slot| boxes[order[slot]].overlaps_sphere(point, radius)), expected);
+        }
+    }
+}
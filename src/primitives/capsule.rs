@@ -0,0 +1,135 @@
+use raylib::prelude::Vector3;
+
+use super::sphere::ray_sphere;
+use super::{HIT_EPSILON, angle_to_u, closest_point_on_segment, tangent_basis};
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Cápsula: cilindro de extremos redondeados entre `a` y `b` (objetos con bordes suaves)
+#[derive(Clone, Debug)]
+pub struct Capsule {
+    pub a: Vector3,
+    pub b: Vector3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Capsule {
+    pub fn new(a: Vector3, b: Vector3, radius: f32, material: Material) -> Self {
+        Capsule { a, b, radius, material }
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        (point - closest_point_on_segment(self.a, self.b, point)).length() < self.radius + radius
+    }
+}
+
+impl RayIntersect for Capsule {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let axis = self.b - self.a;
+        let length = axis.length();
+        let axis_n = if length > 1e-6 { axis / length } else { Vector3::new(0.0, 1.0, 0.0) };
+        let oc = *ray_origin - self.a;
+
+        let d_axis = ray_direction.dot(axis_n);
+        let o_axis = oc.dot(axis_n);
+        let d_perp = *ray_direction - axis_n * d_axis;
+        let o_perp = oc - axis_n * o_axis;
+
+        let mut best: Option<f32> = None;
+        let mut consider = |t: f32| {
+            if t > HIT_EPSILON && best.is_none_or(|best_t| t < best_t) {
+                best = Some(t);
+            }
+        };
+
+        // Cuerpo cilíndrico (solo entre los dos extremos)
+        let a = d_perp.dot(d_perp);
+        if a > 1e-8 {
+            let half_b = o_perp.dot(d_perp);
+            let c = o_perp.dot(o_perp) - self.radius * self.radius;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                for t in [(-half_b - root) / a, (-half_b + root) / a] {
+                    if (0.0..=length).contains(&(o_axis + t * d_axis)) {
+                        consider(t);
+                    }
+                }
+            }
+        }
+
+        // Semiesferas de los extremos: solo cuenta la mitad que queda fuera del cuerpo
+        for (cap_center, outward) in [(self.a, -1.0), (self.b, 1.0)] {
+            for t in sphere_roots(*ray_origin, *ray_direction, cap_center, self.radius) {
+                let point = *ray_origin + *ray_direction * t;
+                if (point - cap_center).dot(axis_n) * outward >= 0.0 {
+                    consider(t);
+                }
+            }
+        }
+
+        let Some(t) = best else {
+            return Intersect::empty();
+        };
+
+        let point = *ray_origin + *ray_direction * t;
+        let normal = (point - closest_point_on_segment(self.a, self.b, point)).normalized();
+
+        // u alrededor del eje; v a lo largo de toda la cápsula, semiesferas incluidas
+        let (tangent, bitangent) = tangent_basis(axis_n);
+        let height = (point - self.a).dot(axis_n);
+        let u = angle_to_u(normal, tangent, bitangent);
+        let v = 1.0 - ((height + self.radius) / (length + 2.0 * self.radius)).clamp(0.0, 1.0);
+
+        Intersect::new(point, normal, t, self.material.clone(), u, v)
+    }
+}
+
+// Las dos raíces válidas de una esfera (la de entrada y la de salida)
+fn sphere_roots(ray_origin: Vector3, ray_direction: Vector3, center: Vector3, radius: f32) -> Vec<f32> {
+    let Some(first) = ray_sphere(ray_origin, ray_direction, center, radius) else {
+        return Vec::new();
+    };
+    let oc = ray_origin - center;
+    let a = ray_direction.dot(ray_direction);
+    let half_b = oc.dot(ray_direction);
+    let far = -2.0 * half_b / a - first;
+    if far > first { vec![first, far] } else { vec![first] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_hit, plain};
+
+    // De y = 0 a y = 2 con radio 1: llega de y = -1 a y = 3
+    fn pill() -> Capsule {
+        Capsule::new(Vector3::zero(), Vector3::new(0.0, 2.0, 0.0), 1.0, plain(0.9, 0.9, 0.9))
+    }
+
+    #[test]
+    fn hits_body_and_rounded_ends() {
+        let pill = pill();
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        assert_hit(&pill.ray_intersect(&Vector3::new(-5.0, 1.0, 0.0), &x), 4.0, -x);
+        assert_hit(&pill.ray_intersect(&Vector3::new(0.0, 10.0, 0.0), &-y), 7.0, y);
+        assert_hit(&pill.ray_intersect(&Vector3::new(0.0, -10.0, 0.0), &y), 9.0, -y);
+        // Cerca del borde se toca la semiesfera, más abajo de donde estaría la tapa de un cilindro
+        let rim = 0.19f32.sqrt();
+        assert_hit(&pill.ray_intersect(&Vector3::new(0.9, 10.0, 0.0), &-y), 8.0 - rim, Vector3::new(0.9, rim, 0.0));
+        assert_hit(&pill.ray_intersect(&Vector3::new(-5.0, 2.9, 0.0), &x), 5.0 - rim, Vector3::new(-rim, 0.9, 0.0));
+        assert!(!pill.ray_intersect(&Vector3::new(-5.0, 3.1, 0.0), &x).is_intersecting);
+    }
+
+    #[test]
+    fn hits_from_inside() {
+        let pill = pill();
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        assert_hit(&pill.ray_intersect(&Vector3::new(0.0, 1.0, 0.0), &x), 1.0, x);
+        assert_hit(&pill.ray_intersect(&Vector3::new(0.0, 1.0, 0.0), &y), 2.0, y);
+        assert_hit(&pill.ray_intersect(&Vector3::new(0.0, 2.5, 0.0), &-y), 3.5, -y);
+    }
+}
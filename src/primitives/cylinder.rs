@@ -0,0 +1,149 @@
+use raylib::prelude::Vector3;
+
+use super::{HIT_EPSILON, angle_to_u, tangent_basis};
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Cilindro con tapas entre `base` y `top` (p. ej. el palo de una antorcha)
+#[derive(Clone, Debug)]
+pub struct Cylinder {
+    pub base: Vector3,
+    pub top: Vector3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Cylinder {
+    pub fn new(base: Vector3, top: Vector3, radius: f32, material: Material) -> Self {
+        Cylinder { base, top, radius, material }
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        let axis = self.top - self.base;
+        let length = axis.length();
+        let axis = axis / length;
+        let offset = point - self.base;
+        let height = offset.dot(axis);
+        let radial = (offset - axis * height).length();
+
+        let outside_height = (height - height.clamp(0.0, length)).abs();
+        let outside_radial = (radial - self.radius).max(0.0);
+        outside_height * outside_height + outside_radial * outside_radial < radius * radius
+    }
+}
+
+impl RayIntersect for Cylinder {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let axis = self.top - self.base;
+        let length = axis.length();
+        let axis_n = axis / length;
+        let oc = *ray_origin - self.base;
+
+        // Se separa el rayo en su parte a lo largo del eje y la perpendicular
+        let d_axis = ray_direction.dot(axis_n);
+        let o_axis = oc.dot(axis_n);
+        let d_perp = *ray_direction - axis_n * d_axis;
+        let o_perp = oc - axis_n * o_axis;
+
+        // (t, normal, es tapa)
+        let mut best: Option<(f32, Vector3, bool)> = None;
+        let mut consider = |t: f32, normal: Vector3, cap: bool| {
+            if t > HIT_EPSILON && best.is_none_or(|(best_t, _, _)| t < best_t) {
+                best = Some((t, normal, cap));
+            }
+        };
+
+        // Pared lateral
+        let a = d_perp.dot(d_perp);
+        if a > 1e-8 {
+            let half_b = o_perp.dot(d_perp);
+            let c = o_perp.dot(o_perp) - self.radius * self.radius;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                for t in [(-half_b - root) / a, (-half_b + root) / a] {
+                    let height = o_axis + t * d_axis;
+                    if (0.0..=length).contains(&height) {
+                        consider(t, (o_perp + d_perp * t) / self.radius, false);
+                    }
+                }
+            }
+        }
+
+        // Tapas
+        if d_axis.abs() > 1e-8 {
+            for (cap_height, normal) in [(0.0, -axis_n), (length, axis_n)] {
+                let t = (cap_height - o_axis) / d_axis;
+                let radial = o_perp + d_perp * t;
+                if radial.dot(radial) <= self.radius * self.radius {
+                    consider(t, normal, true);
+                }
+            }
+        }
+
+        let Some((t, normal, cap)) = best else {
+            return Intersect::empty();
+        };
+
+        let point = *ray_origin + *ray_direction * t;
+        let offset = point - self.base;
+        let height = offset.dot(axis_n);
+        let radial = offset - axis_n * height;
+        let (tangent, bitangent) = tangent_basis(axis_n);
+
+        let (u, v) = if cap {
+            (
+                (radial.dot(tangent) / self.radius + 1.0) * 0.5,
+                (radial.dot(bitangent) / self.radius + 1.0) * 0.5,
+            )
+        } else {
+            (angle_to_u(radial, tangent, bitangent), 1.0 - height / length)
+        };
+
+        Intersect::new(point, normal, t, self.material.clone(), u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_hit, plain};
+
+    // De y = 0 a y = 4, radio 1
+    fn post() -> Cylinder {
+        Cylinder::new(Vector3::zero(), Vector3::new(0.0, 4.0, 0.0), 1.0, plain(0.5, 0.3, 0.1))
+    }
+
+    #[test]
+    fn hits_side_and_caps() {
+        let post = post();
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        assert_hit(&post.ray_intersect(&Vector3::new(-5.0, 2.0, 0.0), &x), 4.0, -x);
+        assert_hit(&post.ray_intersect(&Vector3::new(0.5, 10.0, 0.0), &-y), 6.0, y);
+        assert_hit(&post.ray_intersect(&Vector3::new(0.0, -3.0, 0.5), &y), 3.0, -y);
+        // Por encima de la tapa y por fuera del radio
+        assert!(!post.ray_intersect(&Vector3::new(-5.0, 4.5, 0.0), &x).is_intersecting);
+        assert!(!post.ray_intersect(&Vector3::new(1.5, 10.0, 0.0), &-y).is_intersecting);
+    }
+
+    #[test]
+    fn hits_from_inside() {
+        let post = post();
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        assert_hit(&post.ray_intersect(&Vector3::new(0.0, 2.0, 0.0), &x), 1.0, x);
+        assert_hit(&post.ray_intersect(&Vector3::new(0.0, 2.0, 0.0), &y), 2.0, y);
+        // En diagonal hacia la tapa de abajo
+        let direction = Vector3::new(0.6, -0.8, 0.0);
+        assert_hit(&post.ray_intersect(&Vector3::new(0.0, 1.0, 0.0), &direction), 1.25, -y);
+    }
+
+    #[test]
+    fn tilted_axis() {
+        let beam = Cylinder::new(Vector3::zero(), Vector3::new(4.0, 0.0, 0.0), 0.5, plain(0.5, 0.3, 0.1));
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        assert_hit(&beam.ray_intersect(&Vector3::new(2.0, 3.0, 0.0), &down), 2.5, -down);
+        assert_hit(&beam.ray_intersect(&Vector3::new(6.0, 0.0, 0.0), &Vector3::new(-1.0, 0.0, 0.0)), 2.0, Vector3::new(1.0, 0.0, 0.0));
+    }
+}
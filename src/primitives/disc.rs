@@ -0,0 +1,84 @@
+use raylib::prelude::Vector3;
+
+use super::{HIT_EPSILON, tangent_basis};
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Disco plano de dos caras (p. ej. un sol en el cielo o un cartel redondo)
+#[derive(Clone, Debug)]
+pub struct Disc {
+    pub center: Vector3,
+    pub normal: Vector3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Disc {
+    pub fn new(center: Vector3, normal: Vector3, radius: f32, material: Material) -> Self {
+        Disc {
+            center,
+            normal: normal.normalized(),
+            radius,
+            material,
+        }
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        let offset = point - self.center;
+        let height = offset.dot(self.normal);
+        let radial = (offset - self.normal * height).length();
+        let outside = (radial - self.radius).max(0.0);
+        height * height + outside * outside < radius * radius
+    }
+}
+
+impl RayIntersect for Disc {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let denominator = ray_direction.dot(self.normal);
+        if denominator.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+
+        let t = (self.center - *ray_origin).dot(self.normal) / denominator;
+        if t <= HIT_EPSILON {
+            return Intersect::empty();
+        }
+
+        let point = *ray_origin + *ray_direction * t;
+        let local = point - self.center;
+        if local.dot(local) > self.radius * self.radius {
+            return Intersect::empty();
+        }
+
+        // Sin grosor: la normal mira siempre hacia el rayo
+        let normal = if denominator > 0.0 { -self.normal } else { self.normal };
+        let (tangent, bitangent) = tangent_basis(self.normal);
+        let u = (local.dot(tangent) / self.radius + 1.0) * 0.5;
+        let v = (local.dot(bitangent) / self.radius + 1.0) * 0.5;
+
+        Intersect::new(point, normal, t, self.material.clone(), u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_hit, plain};
+
+    #[test]
+    fn normal_faces_the_ray_on_both_sides() {
+        let disc = Disc::new(Vector3::new(0.0, 0.0, -4.0), Vector3::new(0.0, 0.0, 3.0), 1.0, plain(1.0, 1.0, 0.0));
+        let forward = Vector3::new(0.0, 0.0, -1.0);
+        assert_hit(&disc.ray_intersect(&Vector3::zero(), &forward), 4.0, Vector3::new(0.0, 0.0, 1.0));
+        assert_hit(&disc.ray_intersect(&Vector3::new(0.5, 0.0, -6.0), &-forward), 2.0, forward);
+    }
+
+    #[test]
+    fn misses_outside_the_radius_and_edge_on() {
+        let disc = Disc::new(Vector3::zero(), Vector3::new(0.0, 1.0, 0.0), 1.0, plain(1.0, 1.0, 0.0));
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        assert!(disc.ray_intersect(&Vector3::new(0.99, 1.0, 0.0), &down).is_intersecting);
+        assert!(!disc.ray_intersect(&Vector3::new(0.8, 1.0, 0.8), &down).is_intersecting);
+        assert!(!disc.ray_intersect(&Vector3::new(-3.0, 0.0, 0.0), &Vector3::new(1.0, 0.0, 0.0)).is_intersecting);
+    }
+}
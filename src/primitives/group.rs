@@ -0,0 +1,71 @@
+use raylib::prelude::Vector3;
+
+use super::Primitive;
+use super::bvh::{Aabb, Bvh};
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Conjunto de objetos tratado como uno solo (p. ej. el prototipo de un árbol), con su propia BVH
+#[derive(Clone, Debug)]
+pub struct Group {
+    objects: Vec<Primitive>,
+    bvh: Bvh,
+    // Objetos sin caja (planos infinitos): se prueban siempre
+    unbounded: Vec<Primitive>,
+}
+
+impl Group {
+    pub fn new(objects: Vec<Primitive>) -> Self {
+        let (bounded, unbounded): (Vec<Primitive>, Vec<Primitive>) =
+            objects.into_iter().partition(|object| object.aabb().is_some());
+        let bounds: Vec<Aabb> = bounded.iter().filter_map(Primitive::aabb).collect();
+        let (bvh, order) = Bvh::build(&bounds);
+
+        // Mismo orden que las hojas de la BVH
+        let mut slots: Vec<Option<Primitive>> = bounded.into_iter().map(Some).collect();
+        let objects = order
+            .into_iter()
+            .map(|index| slots[index].take().expect("Objeto repetido en la BVH"))
+            .collect();
+
+        Group { objects, bvh, unbounded }
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &Primitive> {
+        self.objects.iter().chain(&self.unbounded)
+    }
+
+    pub fn aabb(&self) -> Option<Aabb> {
+        (self.unbounded.is_empty() && !self.objects.is_empty()).then(|| self.bvh.bounds())
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        self.unbounded.iter().any(|object| object.overlaps_sphere(point, radius))
+            || (!self.objects.is_empty()
+                && self
+                    .bvh
+                    .any_in_sphere(point, radius, |index| self.objects[index].overlaps_sphere(point, radius)))
+    }
+}
+
+impl RayIntersect for Group {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let mut closest = Intersect::empty();
+        for object in &self.unbounded {
+            let intersect = object.ray_intersect(ray_origin, ray_direction);
+            if intersect.is_intersecting && (!closest.is_intersecting || intersect.distance < closest.distance) {
+                closest = intersect;
+            }
+        }
+        if self.objects.is_empty() {
+            return closest;
+        }
+
+        let max_distance = if closest.is_intersecting { closest.distance } else { f32::INFINITY };
+        let hit = self.bvh.closest_hit(*ray_origin, *ray_direction, |index, limit| {
+            let intersect = self.objects[index].ray_intersect(ray_origin, ray_direction);
+            (intersect.is_intersecting && intersect.distance < limit.min(max_distance))
+                .then_some((intersect.distance, intersect))
+        });
+        hit.map_or(closest, |(_, intersect)| intersect)
+    }
+}
@@ -0,0 +1,245 @@
+use raylib::prelude::Vector3;
+
+use super::HIT_EPSILON;
+use super::bvh::{Aabb, Bvh};
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Triángulo con normales y UVs por vértice (None si el modelo no las trae)
+#[derive(Clone, Debug)]
+pub struct Triangle {
+    pub positions: [Vector3; 3],
+    pub normals: Option<[Vector3; 3]>,
+    pub uvs: Option<[(f32, f32); 3]>,
+    // Índice en `Mesh::materials`
+    pub material: usize,
+}
+
+impl Triangle {
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(&self.positions)
+    }
+
+    fn geometric_normal(&self) -> Vector3 {
+        (self.positions[1] - self.positions[0])
+            .cross(self.positions[2] - self.positions[0])
+            .normalized()
+    }
+
+    // Möller–Trumbore: distancia y coordenadas baricéntricas (b1, b2) del impacto
+    fn intersect(&self, ray_origin: Vector3, ray_direction: Vector3) -> Option<(f32, f32, f32)> {
+        let edge1 = self.positions[1] - self.positions[0];
+        let edge2 = self.positions[2] - self.positions[0];
+        let p = ray_direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < 1e-9 {
+            return None;
+        }
+
+        let inverse_determinant = 1.0 / determinant;
+        let s = ray_origin - self.positions[0];
+        let b1 = s.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let b2 = ray_direction.dot(q) * inverse_determinant;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inverse_determinant;
+        (t > HIT_EPSILON).then_some((t, b1, b2))
+    }
+
+    // Punto del triángulo más cercano a `point` (Ericson, "Real-Time Collision Detection")
+    fn closest_point(&self, point: Vector3) -> Vector3 {
+        let [a, b, c] = self.positions;
+        let ab = b - a;
+        let ac = c - a;
+        let ap = point - a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+
+        let bp = point - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return a + ab * (d1 / (d1 - d3));
+        }
+
+        let cp = point - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0.0 && d5 <= d6 {
+            return c;
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return a + ac * (d2 / (d2 - d6));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+
+        let denominator = 1.0 / (va + vb + vc);
+        a + ab * (vb * denominator) + ac * (vc * denominator)
+    }
+}
+
+// Malla de triángulos con su propia BVH; se trata como un único objeto de la escena
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    triangles: Vec<Triangle>,
+    materials: Vec<Material>,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>, materials: Vec<Material>) -> Self {
+        let bounds: Vec<Aabb> = triangles.iter().map(Triangle::bounds).collect();
+        let (bvh, order) = Bvh::build(&bounds);
+
+        // Se reordenan los triángulos para que cada hoja sea un rango contiguo
+        let mut slots: Vec<Option<Triangle>> = triangles.into_iter().map(Some).collect();
+        let triangles = order
+            .into_iter()
+            .map(|index| slots[index].take().expect("Triángulo repetido en la BVH"))
+            .collect();
+
+        Mesh { triangles, materials, bvh }
+    }
+
+    pub fn brightest_material(&self) -> &Material {
+        self.materials
+            .iter()
+            .max_by(|a, b| a.emission_strength.total_cmp(&b.emission_strength))
+            .expect("Malla sin materiales")
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bvh.bounds()
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        !self.triangles.is_empty()
+            && self.bvh.any_in_sphere(point, radius, |index| {
+                (self.triangles[index].closest_point(point) - point).length() < radius
+            })
+    }
+}
+
+impl RayIntersect for Mesh {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        if self.triangles.is_empty() {
+            return Intersect::empty();
+        }
+
+        let hit = self.bvh.closest_hit(*ray_origin, *ray_direction, |index, max_distance| {
+            self.triangles[index]
+                .intersect(*ray_origin, *ray_direction)
+                .filter(|(t, _, _)| *t < max_distance)
+                .map(|(t, b1, b2)| (t, (index, b1, b2)))
+        });
+        let Some((t, (index, b1, b2))) = hit else {
+            return Intersect::empty();
+        };
+
+        let triangle = &self.triangles[index];
+        let material = &self.materials[triangle.material];
+        let b0 = 1.0 - b1 - b2;
+
+        let mut normal = match triangle.normals {
+            Some([n0, n1, n2]) => (n0 * b0 + n1 * b1 + n2 * b2).normalized(),
+            None => triangle.geometric_normal(),
+        };
+        // Las mallas suelen ser superficies abiertas: si el material es opaco la normal mira
+        // hacia el rayo; en los transparentes se conserva para saber si el rayo entra o sale
+        if material.albedo[3] <= 0.0 && normal.dot(*ray_direction) > 0.0 {
+            normal = -normal;
+        }
+
+        // Las UV de los modelos van con v hacia arriba y se repiten fuera de [0, 1]
+        let (u, v) = match triangle.uvs {
+            Some([(u0, v0), (u1, v1), (u2, v2)]) => (
+                (u0 * b0 + u1 * b1 + u2 * b2).rem_euclid(1.0),
+                (1.0 - (v0 * b0 + v1 * b1 + v2 * b2)).rem_euclid(1.0),
+            ),
+            None => (0.0, 0.0),
+        };
+
+        let point = *ray_origin + *ray_direction * t;
+        Intersect::new(point, normal, t, material.clone(), u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_hit, plain};
+
+    // Cubo cerrado de lado 2 centrado en el origen, dos triángulos por cara
+    fn cube_mesh(material: Material) -> Mesh {
+        let corner = |index: usize| {
+            let sign = |bit: usize| if index & bit != 0 { 1.0 } else { -1.0 };
+            Vector3::new(sign(1), sign(2), sign(4))
+        };
+        let faces = [[0, 1, 3, 2], [4, 6, 7, 5], [0, 4, 5, 1], [2, 3, 7, 6], [0, 2, 6, 4], [1, 5, 7, 3]];
+        let triangles = faces
+            .iter()
+            .flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]])
+            .map(|corners| Triangle { positions: corners.map(corner), normals: None, uvs: None, material: 0 })
+            .collect();
+        Mesh::new(triangles, vec![material])
+    }
+
+    #[test]
+    fn opaque_normals_face_the_ray() {
+        let mesh = cube_mesh(plain(0.8, 0.8, 0.8));
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        assert_hit(&mesh.ray_intersect(&Vector3::new(-5.0, 0.3, 0.2), &x), 4.0, -x);
+        assert_hit(&mesh.ray_intersect(&Vector3::new(5.0, -0.3, 0.2), &-x), 4.0, x);
+        // Desde dentro se toca la cara de enfrente, con la normal girada hacia el rayo
+        assert_hit(&mesh.ray_intersect(&Vector3::new(0.0, 0.5, 0.0), &x), 1.0, -x);
+        assert!(!mesh.ray_intersect(&Vector3::new(-5.0, 1.5, 0.0), &x).is_intersecting);
+    }
+
+    #[test]
+    fn transparent_normals_keep_their_side() {
+        let glass = Material::new(Vector3::new(1.0, 1.0, 1.0), 80.0, [0.2, 0.2, 0.1, 0.5], 1.5, None);
+        let mesh = cube_mesh(glass);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        // El sentido del giro de los triángulos decide la normal: en este cubo miran hacia dentro
+        let outside = mesh.ray_intersect(&Vector3::new(0.2, 5.0, 0.1), &-y);
+        let inside = mesh.ray_intersect(&Vector3::new(0.2, 0.0, 0.1), &y);
+        assert_hit(&outside, 4.0, -y);
+        assert_hit(&inside, 1.0, -y);
+    }
+
+    #[test]
+    fn interpolates_vertex_normals_and_uvs() {
+        let triangle = Triangle {
+            positions: [Vector3::zero(), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)],
+            normals: Some([Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 1.0, 0.0).normalized(), Vector3::new(0.0, 1.0, 0.0)]),
+            uvs: Some([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]),
+            material: 0,
+        };
+        let mesh = Mesh::new(vec![triangle], vec![plain(1.0, 1.0, 1.0)]);
+        let hit = mesh.ray_intersect(&Vector3::new(0.5, 2.0, 0.25), &Vector3::new(0.0, -1.0, 0.0));
+        assert!(hit.is_intersecting);
+        assert!(hit.normal.x > 0.0 && hit.normal.y > 0.0 && (hit.normal.length() - 1.0).abs() < 1e-5);
+        // v va hacia abajo en la escena, al revés que en el modelo
+        assert!((hit.u - 0.5).abs() < 1e-5 && (hit.v - 0.75).abs() < 1e-5);
+    }
+}
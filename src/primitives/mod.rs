@@ -0,0 +1,257 @@
+use raylib::prelude::Vector3;
+use std::sync::Arc;
+
+use crate::cube::{Cube, Vec3};
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+pub mod sphere;
+pub use sphere::*;
+pub mod plane;
+pub use plane::*;
+pub mod disc;
+pub use disc::*;
+pub mod cylinder;
+pub use cylinder::*;
+pub mod capsule;
+pub use capsule::*;
+pub mod mesh;
+pub use mesh::*;
+pub mod bvh;
+pub use bvh::Aabb;
+pub mod group;
+pub use group::*;
+pub mod transform;
+pub use transform::*;
+pub mod voxels;
+pub use voxels::*;
+
+// Distancia mínima de impacto, para no chocar con la superficie de la que sale el rayo
+pub const HIT_EPSILON: f32 = 1e-4;
+
+// Cualquier objeto de la escena. Un enum mantiene la lista homogénea, sin asignaciones
+// por objeto, y se reparte entre hilos sin problemas
+#[derive(Clone, Debug)]
+pub enum Primitive {
+    Cube(Cube),
+    Sphere(Sphere),
+    Plane(Plane),
+    Disc(Disc),
+    Cylinder(Cylinder),
+    Capsule(Capsule),
+    // Compartida: varias copias de la escena (o del mismo modelo) no duplican los triángulos
+    Mesh(Arc<Mesh>),
+    // Varios objetos con su propia BVH, normalmente el prototipo de una instancia
+    Group(Arc<Group>),
+    // Objeto (o grupo) compartido colocado con una transformación
+    Instance(Box<Instance>),
+    // Mundo de bloques por trozos; compartido para que clonar la escena no copie millones de bloques
+    Voxels(Arc<VoxelWorld>),
+}
+
+impl Primitive {
+    // Envuelve el objeto con una transformación (traslación, giro, escala no uniforme)
+    pub fn transformed(self, transform: Transform) -> Primitive {
+        Instance::new(Arc::new(self), transform).into()
+    }
+
+    // Caja envolvente en coordenadas del objeto; None para objetos infinitos
+    pub fn aabb(&self) -> Option<Aabb> {
+        match self {
+            Primitive::Cube(cube) => Some(Aabb {
+                min: (cube.center - cube.half).to_vector3(),
+                max: (cube.center + cube.half).to_vector3(),
+            }),
+            Primitive::Sphere(sphere) => Some(Aabb::from_points(&[sphere.center]).expanded(sphere.radius)),
+            Primitive::Plane(_) => None,
+            Primitive::Disc(disc) => Some(Aabb::from_points(&[disc.center]).expanded(disc.radius)),
+            Primitive::Cylinder(cylinder) => {
+                Some(Aabb::from_points(&[cylinder.base, cylinder.top]).expanded(cylinder.radius))
+            }
+            Primitive::Capsule(capsule) => Some(Aabb::from_points(&[capsule.a, capsule.b]).expanded(capsule.radius)),
+            Primitive::Mesh(mesh) => Some(mesh.bounds()),
+            Primitive::Group(group) => group.aabb(),
+            Primitive::Instance(instance) => instance.aabb(),
+            Primitive::Voxels(voxels) => voxels.aabb(),
+        }
+    }
+
+    // Partes emisivas del objeto como (centro, radio envolvente, material) en coordenadas del
+    // mundo. Grupos e instancias se recorren para situar cada emisor donde está de verdad
+    pub fn emitters(&self) -> Vec<(Vector3, f32, &Material)> {
+        let mut emitters = Vec::new();
+        self.collect_emitters(&Transform::identity(), &mut emitters);
+        emitters
+    }
+
+    fn collect_emitters<'a>(&'a self, transform: &Transform, emitters: &mut Vec<(Vector3, f32, &'a Material)>) {
+        let (material, center, radius) = match self {
+            Primitive::Group(group) => {
+                for object in group.objects() {
+                    object.collect_emitters(transform, emitters);
+                }
+                return;
+            }
+            Primitive::Instance(instance) => {
+                instance
+                    .prototype
+                    .collect_emitters(&instance.transform.then(*transform), emitters);
+                return;
+            }
+            Primitive::Voxels(voxels) => {
+                for (center, radius, material) in voxels.emitters() {
+                    emitters.push((transform.transform_point(center), radius * transform.max_scale(), material));
+                }
+                return;
+            }
+            // Un emisor infinito no tiene posición: no se trata como fuente puntual
+            Primitive::Plane(_) => return,
+            Primitive::Cube(cube) => (&cube.material, cube.center.to_vector3(), cube.half.length()),
+            Primitive::Sphere(sphere) => (&sphere.material, sphere.center, sphere.radius),
+            Primitive::Disc(disc) => (&disc.material, disc.center, disc.radius),
+            Primitive::Cylinder(cylinder) => {
+                let half_height = (cylinder.top - cylinder.base).length() * 0.5;
+                (
+                    &cylinder.material,
+                    (cylinder.base + cylinder.top) * 0.5,
+                    (half_height * half_height + cylinder.radius * cylinder.radius).sqrt(),
+                )
+            }
+            Primitive::Capsule(capsule) => (
+                &capsule.material,
+                (capsule.a + capsule.b) * 0.5,
+                (capsule.b - capsule.a).length() * 0.5 + capsule.radius,
+            ),
+            // En las mallas cuenta el material más emisivo, situado en el centro de la malla
+            Primitive::Mesh(mesh) => {
+                let bounds = mesh.bounds();
+                (mesh.brightest_material(), bounds.center(), bounds.extent().length() * 0.5)
+            }
+        };
+
+        if material.emission_strength > 0.0 {
+            emitters.push((transform.transform_point(center), radius * transform.max_scale(), material));
+        }
+    }
+
+    // Comprueba si una esfera (p. ej. la cámara) toca el objeto
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        match self {
+            Primitive::Cube(cube) => cube.overlaps_sphere(Vec3::from_vector3(point), radius),
+            Primitive::Sphere(sphere) => sphere.overlaps_sphere(point, radius),
+            Primitive::Plane(plane) => plane.overlaps_sphere(point, radius),
+            Primitive::Disc(disc) => disc.overlaps_sphere(point, radius),
+            Primitive::Cylinder(cylinder) => cylinder.overlaps_sphere(point, radius),
+            Primitive::Capsule(capsule) => capsule.overlaps_sphere(point, radius),
+            Primitive::Mesh(mesh) => mesh.overlaps_sphere(point, radius),
+            Primitive::Group(group) => group.overlaps_sphere(point, radius),
+            Primitive::Instance(instance) => instance.overlaps_sphere(point, radius),
+            Primitive::Voxels(voxels) => voxels.overlaps_sphere(point, radius),
+        }
+    }
+}
+
+impl RayIntersect for Primitive {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        match self {
+            Primitive::Cube(cube) => cube.ray_intersect(ray_origin, ray_direction),
+            Primitive::Sphere(sphere) => sphere.ray_intersect(ray_origin, ray_direction),
+            Primitive::Plane(plane) => plane.ray_intersect(ray_origin, ray_direction),
+            Primitive::Disc(disc) => disc.ray_intersect(ray_origin, ray_direction),
+            Primitive::Cylinder(cylinder) => cylinder.ray_intersect(ray_origin, ray_direction),
+            Primitive::Capsule(capsule) => capsule.ray_intersect(ray_origin, ray_direction),
+            Primitive::Mesh(mesh) => mesh.ray_intersect(ray_origin, ray_direction),
+            Primitive::Group(group) => group.ray_intersect(ray_origin, ray_direction),
+            Primitive::Instance(instance) => instance.ray_intersect(ray_origin, ray_direction),
+            Primitive::Voxels(voxels) => voxels.ray_intersect(ray_origin, ray_direction),
+        }
+    }
+}
+
+impl From<Cube> for Primitive {
+    fn from(cube: Cube) -> Self {
+        Primitive::Cube(cube)
+    }
+}
+
+impl From<Sphere> for Primitive {
+    fn from(sphere: Sphere) -> Self {
+        Primitive::Sphere(sphere)
+    }
+}
+
+impl From<Plane> for Primitive {
+    fn from(plane: Plane) -> Self {
+        Primitive::Plane(plane)
+    }
+}
+
+impl From<Disc> for Primitive {
+    fn from(disc: Disc) -> Self {
+        Primitive::Disc(disc)
+    }
+}
+
+impl From<Cylinder> for Primitive {
+    fn from(cylinder: Cylinder) -> Self {
+        Primitive::Cylinder(cylinder)
+    }
+}
+
+impl From<Capsule> for Primitive {
+    fn from(capsule: Capsule) -> Self {
+        Primitive::Capsule(capsule)
+    }
+}
+
+impl From<Mesh> for Primitive {
+    fn from(mesh: Mesh) -> Self {
+        Primitive::Mesh(Arc::new(mesh))
+    }
+}
+
+impl From<Group> for Primitive {
+    fn from(group: Group) -> Self {
+        Primitive::Group(Arc::new(group))
+    }
+}
+
+impl From<VoxelWorld> for Primitive {
+    fn from(voxels: VoxelWorld) -> Self {
+        Primitive::Voxels(Arc::new(voxels))
+    }
+}
+
+impl From<Instance> for Primitive {
+    fn from(instance: Instance) -> Self {
+        Primitive::Instance(Box::new(instance))
+    }
+}
+
+// Dos ejes perpendiculares a `normal` (y entre sí) para mapear UVs en superficies planas
+pub fn tangent_basis(normal: Vector3) -> (Vector3, Vector3) {
+    let helper = if normal.y.abs() < 0.99 {
+        Vector3::new(0.0, 1.0, 0.0)
+    } else {
+        Vector3::new(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(normal).normalized();
+    let bitangent = normal.cross(tangent);
+    (tangent, bitangent)
+}
+
+// Punto del segmento [a, b] más cercano a `point`
+pub fn closest_point_on_segment(a: Vector3, b: Vector3, point: Vector3) -> Vector3 {
+    let ab = b - a;
+    let length_squared = ab.dot(ab);
+    if length_squared <= 1e-12 {
+        return a;
+    }
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+// Ángulo alrededor de un eje convertido a coordenada u en [0, 1]
+fn angle_to_u(offset: Vector3, tangent: Vector3, bitangent: Vector3) -> f32 {
+    offset.dot(bitangent).atan2(offset.dot(tangent)) / (2.0 * std::f32::consts::PI) + 0.5
+}
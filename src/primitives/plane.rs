@@ -0,0 +1,81 @@
+use raylib::prelude::Vector3;
+
+use super::{HIT_EPSILON, tangent_basis};
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Plano infinito (p. ej. una superficie de agua). La normal indica el lado "de fuera",
+// así que un rayo que lo cruza desde abajo se trata como salida al refractar
+#[derive(Clone, Debug)]
+pub struct Plane {
+    pub point: Vector3,
+    pub normal: Vector3,
+    pub material: Material,
+    // Tamaño en unidades de escena de una repetición de la textura
+    pub uv_scale: f32,
+}
+
+impl Plane {
+    pub fn new(point: Vector3, normal: Vector3, uv_scale: f32, material: Material) -> Self {
+        Plane {
+            point,
+            normal: normal.normalized(),
+            material,
+            uv_scale: uv_scale.max(1e-3),
+        }
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        (point - self.point).dot(self.normal).abs() < radius
+    }
+}
+
+impl RayIntersect for Plane {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let denominator = ray_direction.dot(self.normal);
+        if denominator.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+
+        let t = (self.point - *ray_origin).dot(self.normal) / denominator;
+        if t <= HIT_EPSILON {
+            return Intersect::empty();
+        }
+
+        let point = *ray_origin + *ray_direction * t;
+        let (tangent, bitangent) = tangent_basis(self.normal);
+        let local = point - self.point;
+        let u = (local.dot(tangent) / self.uv_scale).rem_euclid(1.0);
+        let v = (local.dot(bitangent) / self.uv_scale).rem_euclid(1.0);
+
+        Intersect::new(point, self.normal, t, self.material.clone(), u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_hit, plain};
+
+    #[test]
+    fn hits_from_both_sides_with_the_same_normal() {
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let plane = Plane::new(Vector3::new(0.0, -1.0, 0.0), Vector3::new(0.0, 2.0, 0.0), 2.0, plain(0.0, 0.0, 1.0));
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        assert_hit(&plane.ray_intersect(&Vector3::new(3.0, 2.0, 0.0), &down), 3.0, up);
+        // Desde abajo la normal no se gira: así se sabe que el rayo sale del agua
+        assert_hit(&plane.ray_intersect(&Vector3::new(0.0, -4.0, 0.0), &up), 3.0, up);
+        assert!(!plane.ray_intersect(&Vector3::new(0.0, 2.0, 0.0), &Vector3::new(1.0, 0.0, 0.0)).is_intersecting);
+        assert!(!plane.ray_intersect(&Vector3::new(0.0, 2.0, 0.0), &up).is_intersecting);
+    }
+
+    #[test]
+    fn uv_repeats_every_uv_scale() {
+        let plane = Plane::new(Vector3::zero(), Vector3::new(0.0, 1.0, 0.0), 2.0, plain(0.0, 0.0, 1.0));
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        let a = plane.ray_intersect(&Vector3::new(0.5, 1.0, 0.25), &down);
+        let b = plane.ray_intersect(&Vector3::new(4.5, 1.0, -3.75), &down);
+        assert!((a.u - b.u).abs() < 1e-4 && (a.v - b.v).abs() < 1e-4);
+        assert!((0.0..1.0).contains(&a.u) && (0.0..1.0).contains(&a.v));
+    }
+}
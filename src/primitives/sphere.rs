@@ -0,0 +1,85 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use super::HIT_EPSILON;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+#[derive(Clone, Debug)]
+pub struct Sphere {
+    pub center: Vector3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Sphere {
+    pub fn new(center: Vector3, radius: f32, material: Material) -> Self {
+        Sphere { center, radius, material }
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        (point - self.center).length() < self.radius + radius
+    }
+}
+
+// Raíz más cercana (mayor que HIT_EPSILON) de un rayo contra una esfera
+pub fn ray_sphere(ray_origin: Vector3, ray_direction: Vector3, center: Vector3, radius: f32) -> Option<f32> {
+    let oc = ray_origin - center;
+    let a = ray_direction.dot(ray_direction);
+    let half_b = oc.dot(ray_direction);
+    let c = oc.dot(oc) - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    [(-half_b - root) / a, (-half_b + root) / a]
+        .into_iter()
+        .find(|t| *t > HIT_EPSILON)
+}
+
+impl RayIntersect for Sphere {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let Some(t) = ray_sphere(*ray_origin, *ray_direction, self.center, self.radius) else {
+            return Intersect::empty();
+        };
+
+        let point = *ray_origin + *ray_direction * t;
+        let normal = (point - self.center) / self.radius;
+
+        // Mapeo esférico (longitud, latitud), con v hacia abajo como en los cubos
+        let u = normal.z.atan2(normal.x) / (2.0 * PI) + 0.5;
+        let v = 0.5 - normal.y.clamp(-1.0, 1.0).asin() / PI;
+
+        Intersect::new(point, normal, t, self.material.clone(), u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_hit, plain};
+
+    #[test]
+    fn hits_from_outside_and_inside() {
+        let sphere = Sphere::new(Vector3::new(0.0, 0.0, -5.0), 2.0, plain(1.0, 0.0, 0.0));
+        let forward = Vector3::new(0.0, 0.0, -1.0);
+        assert_hit(&sphere.ray_intersect(&Vector3::zero(), &forward), 3.0, Vector3::new(0.0, 0.0, 1.0));
+        // Desde dentro se sale por la cara de enfrente; la normal sigue mirando hacia fuera
+        assert_hit(&sphere.ray_intersect(&Vector3::new(0.0, 0.0, -5.0), &forward), 2.0, forward);
+        // Rasante por arriba y del todo por fuera
+        assert!(!sphere.ray_intersect(&Vector3::new(0.0, 2.1, 0.0), &forward).is_intersecting);
+        // Detrás del rayo
+        assert!(!sphere.ray_intersect(&Vector3::new(0.0, 0.0, -10.0), &forward).is_intersecting);
+    }
+
+    #[test]
+    fn uv_follows_longitude_and_latitude() {
+        let sphere = Sphere::new(Vector3::zero(), 1.0, plain(1.0, 1.0, 1.0));
+        let top = sphere.ray_intersect(&Vector3::new(0.0, 5.0, 0.0), &Vector3::new(0.0, -1.0, 0.0));
+        assert!(top.v.abs() < 1e-4);
+        let side = sphere.ray_intersect(&Vector3::new(5.0, 0.0, 0.0), &Vector3::new(-1.0, 0.0, 0.0));
+        assert!((side.u - 0.5).abs() < 1e-4 && (side.v - 0.5).abs() < 1e-4);
+    }
+}
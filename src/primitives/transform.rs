@@ -0,0 +1,278 @@
+use raylib::prelude::Vector3;
+use std::sync::Arc;
+
+use super::Primitive;
+use super::bvh::Aabb;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Transformación afín: parte lineal (rotación, escala, cizalla) por columnas más traslación
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    x_axis: Vector3,
+    y_axis: Vector3,
+    z_axis: Vector3,
+    translation: Vector3,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform {
+            x_axis: Vector3::new(1.0, 0.0, 0.0),
+            y_axis: Vector3::new(0.0, 1.0, 0.0),
+            z_axis: Vector3::new(0.0, 0.0, 1.0),
+            translation: Vector3::zero(),
+        }
+    }
+
+    pub fn translation(offset: Vector3) -> Self {
+        Transform {
+            translation: offset,
+            ..Self::identity()
+        }
+    }
+
+    // Escala por eje (no uniforme si los tres valores difieren)
+    pub fn scale(factors: Vector3) -> Self {
+        Transform {
+            x_axis: Vector3::new(factors.x, 0.0, 0.0),
+            y_axis: Vector3::new(0.0, factors.y, 0.0),
+            z_axis: Vector3::new(0.0, 0.0, factors.z),
+            translation: Vector3::zero(),
+        }
+    }
+
+    // Giro de `angle` radianes alrededor de `axis` (regla de la mano derecha)
+    pub fn rotation(axis: Vector3, angle: f32) -> Self {
+        let axis = axis.normalized();
+        let (sin, cos) = angle.sin_cos();
+        // Fórmula de Rodrigues aplicada a cada eje de la base
+        let rotate = |v: Vector3| v * cos + axis.cross(v) * sin + axis * (axis.dot(v) * (1.0 - cos));
+        Transform {
+            x_axis: rotate(Vector3::new(1.0, 0.0, 0.0)),
+            y_axis: rotate(Vector3::new(0.0, 1.0, 0.0)),
+            z_axis: rotate(Vector3::new(0.0, 0.0, 1.0)),
+            translation: Vector3::zero(),
+        }
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        Self::rotation(Vector3::new(0.0, 1.0, 0.0), angle)
+    }
+
+    // Matriz 4x4 por columnas (el formato de glTF)
+    pub fn from_columns(matrix: [[f32; 4]; 4]) -> Self {
+        let column = |index: usize| Vector3::new(matrix[index][0], matrix[index][1], matrix[index][2]);
+        Transform {
+            x_axis: column(0),
+            y_axis: column(1),
+            z_axis: column(2),
+            translation: column(3),
+        }
+    }
+
+    // Encadena: primero `self` y luego `outer`. Permite escribir
+    // `Transform::scale(s).then(Transform::rotation_y(a)).then(Transform::translation(p))`
+    pub fn then(&self, outer: Transform) -> Self {
+        Transform {
+            x_axis: outer.transform_vector(self.x_axis),
+            y_axis: outer.transform_vector(self.y_axis),
+            z_axis: outer.transform_vector(self.z_axis),
+            translation: outer.transform_point(self.translation),
+        }
+    }
+
+    fn determinant(&self) -> f32 {
+        self.x_axis.dot(self.y_axis.cross(self.z_axis))
+    }
+
+    // None si la transformación no es invertible (alguna escala es cero)
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        // Las filas de la inversa son los productos vectoriales de las columnas entre el determinante
+        let row0 = self.y_axis.cross(self.z_axis) / determinant;
+        let row1 = self.z_axis.cross(self.x_axis) / determinant;
+        let row2 = self.x_axis.cross(self.y_axis) / determinant;
+        let linear = Transform {
+            x_axis: Vector3::new(row0.x, row1.x, row2.x),
+            y_axis: Vector3::new(row0.y, row1.y, row2.y),
+            z_axis: Vector3::new(row0.z, row1.z, row2.z),
+            translation: Vector3::zero(),
+        };
+        Some(Transform {
+            translation: -linear.transform_vector(self.translation),
+            ..linear
+        })
+    }
+
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        self.x_axis * vector.x + self.y_axis * vector.y + self.z_axis * vector.z
+    }
+
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        self.transform_vector(point) + self.translation
+    }
+
+    // Las normales se transforman con la inversa traspuesta; la matriz de cofactores es
+    // proporcional a ella y basta con normalizar el resultado
+    pub fn transform_normal(&self, normal: Vector3) -> Vector3 {
+        let transformed = self.y_axis.cross(self.z_axis) * normal.x
+            + self.z_axis.cross(self.x_axis) * normal.y
+            + self.x_axis.cross(self.y_axis) * normal.z;
+        // Si la transformación refleja (determinante negativo) los cofactores invierten la normal
+        (if self.determinant() < 0.0 { -transformed } else { transformed }).normalized()
+    }
+
+    // Mayor factor de escala que puede aplicar a una longitud (cota por la suma de columnas)
+    pub fn max_scale(&self) -> f32 {
+        self.x_axis.length().max(self.y_axis.length()).max(self.z_axis.length())
+    }
+
+    // Caja que envuelve a `aabb` una vez transformada
+    pub fn transform_aabb(&self, aabb: &Aabb) -> Aabb {
+        let corners = (0..8).map(|corner| {
+            Vector3::new(
+                if corner & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if corner & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if corner & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            )
+        });
+        corners.fold(Aabb::empty(), |bounds, corner| bounds.grow(self.transform_point(corner)))
+    }
+}
+
+// Un objeto colocado con una transformación. El prototipo se comparte: muchas instancias
+// (p. ej. un árbol entero, como `Group`) no duplican su geometría
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub prototype: Arc<Primitive>,
+    pub transform: Transform,
+    inverse: Transform,
+}
+
+impl Instance {
+    pub fn new(prototype: Arc<Primitive>, transform: Transform) -> Self {
+        let inverse = transform
+            .inverse()
+            .expect("Transformación no invertible (¿escala cero?)");
+        Instance {
+            prototype,
+            transform,
+            inverse,
+        }
+    }
+
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.prototype
+            .aabb()
+            .map(|aabb| self.transform.transform_aabb(&aabb))
+    }
+
+    // La esfera pasa a espacio local como una esfera algo mayor (cota conservadora con escala no uniforme)
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        self.prototype
+            .overlaps_sphere(self.inverse.transform_point(point), radius * self.inverse.max_scale())
+    }
+}
+
+impl RayIntersect for Instance {
+    // El rayo se lleva al espacio del prototipo sin normalizar la dirección: así el parámetro t
+    // del impacto es el mismo en los dos espacios
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let local_origin = self.inverse.transform_point(*ray_origin);
+        let local_direction = self.inverse.transform_vector(*ray_direction);
+        let mut intersect = self.prototype.ray_intersect(&local_origin, &local_direction);
+        if intersect.is_intersecting {
+            intersect.point = *ray_origin + *ray_direction * intersect.distance;
+            intersect.normal = self.transform.transform_normal(intersect.normal);
+        }
+        intersect
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::Sphere;
+    use crate::test_support::{assert_hit, plain};
+
+    fn assert_close(actual: Vector3, expected: Vector3) {
+        assert!((actual - expected).length() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn inverse_undoes_scale_rotation_reflection_and_translation() {
+        let transform = Transform::scale(Vector3::new(2.0, 0.5, -3.0))
+            .then(Transform::rotation(Vector3::new(1.0, 1.0, 0.0), 0.7))
+            .then(Transform::translation(Vector3::new(4.0, -1.0, 2.0)));
+        let inverse = transform.inverse().unwrap();
+        for point in [Vector3::zero(), Vector3::new(1.0, 2.0, 3.0), Vector3::new(-5.0, 0.5, 8.0)] {
+            assert_close(inverse.transform_point(transform.transform_point(point)), point);
+            assert_close(transform.transform_point(inverse.transform_point(point)), point);
+        }
+        assert!(Transform::scale(Vector3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn normals_use_the_inverse_transpose() {
+        // El plano x + y = 1 estirado al doble en X pasa a ser x / 2 + y = 1: su normal es (1, 2, 0)
+        let stretch = Transform::scale(Vector3::new(2.0, 1.0, 1.0));
+        let normal = Vector3::new(1.0, 1.0, 0.0).normalized();
+        assert_close(stretch.transform_normal(normal), Vector3::new(1.0, 2.0, 0.0).normalized());
+        // La traslación no afecta a las normales
+        let moved = stretch.then(Transform::translation(Vector3::new(3.0, 3.0, 3.0)));
+        assert_close(moved.transform_normal(normal), Vector3::new(1.0, 2.0, 0.0).normalized());
+    }
+
+    #[test]
+    fn reflected_normals_keep_pointing_outwards() {
+        let mirror = Transform::scale(Vector3::new(-1.0, 1.0, 1.0));
+        // El punto (1, 0, 0) de una esfera unidad pasa a (-1, 0, 0), donde la normal de fuera es -X
+        assert_close(mirror.transform_normal(Vector3::new(1.0, 0.0, 0.0)), Vector3::new(-1.0, 0.0, 0.0));
+        assert_close(
+            mirror.transform_normal(Vector3::new(1.0, 1.0, 0.0).normalized()),
+            Vector3::new(-1.0, 1.0, 0.0).normalized(),
+        );
+        // Reflejo con escala no uniforme a la vez
+        let squashed_mirror = Transform::scale(Vector3::new(-2.0, 1.0, 1.0));
+        assert_close(
+            squashed_mirror.transform_normal(Vector3::new(1.0, 1.0, 0.0).normalized()),
+            Vector3::new(-1.0, 2.0, 0.0).normalized(),
+        );
+    }
+
+    #[test]
+    fn instance_of_a_stretched_sphere() {
+        // Elipsoide (x / 2)² + y² + z² = 1 centrado en (0, 0, -5)
+        let sphere = Arc::new(Primitive::from(Sphere::new(Vector3::zero(), 1.0, plain(1.0, 1.0, 1.0))));
+        let transform = Transform::scale(Vector3::new(2.0, 1.0, 1.0)).then(Transform::translation(Vector3::new(0.0, 0.0, -5.0)));
+        let instance = Instance::new(sphere, transform);
+
+        assert_hit(&instance.ray_intersect(&Vector3::zero(), &Vector3::new(0.0, 0.0, -1.0)), 4.0, Vector3::new(0.0, 0.0, 1.0));
+        let hit = instance.ray_intersect(&Vector3::new(10.0, 0.0, -5.0), &Vector3::new(-1.0, 0.0, 0.0));
+        assert_hit(&hit, 8.0, Vector3::new(1.0, 0.0, 0.0));
+        assert_close(hit.point, Vector3::new(2.0, 0.0, -5.0));
+
+        // En x = √2 la superficie está a y = √½ y la normal es proporcional al gradiente (x / 4, y)
+        let x = 2f32.sqrt();
+        let hit = instance.ray_intersect(&Vector3::new(x, 5.0, -5.0), &Vector3::new(0.0, -1.0, 0.0));
+        assert_hit(&hit, 5.0 - 0.5f32.sqrt(), Vector3::new(1.0, 2.0, 0.0).normalized());
+
+        let bounds = instance.aabb().unwrap();
+        assert_close(bounds.min, Vector3::new(-2.0, -1.0, -6.0));
+        assert_close(bounds.max, Vector3::new(2.0, 1.0, -4.0));
+    }
+
+    #[test]
+    fn mirrored_instance_keeps_outward_normals() {
+        let sphere = Arc::new(Primitive::from(Sphere::new(Vector3::new(1.0, 0.0, 0.0), 1.0, plain(1.0, 1.0, 1.0))));
+        // La esfera reflejada queda centrada en (-1, 0, 0)
+        let instance = Instance::new(sphere, Transform::scale(Vector3::new(-1.0, 1.0, 1.0)));
+        let right = Vector3::new(1.0, 0.0, 0.0);
+        assert_hit(&instance.ray_intersect(&Vector3::new(-10.0, 0.0, 0.0), &right), 8.0, -right);
+        assert_hit(&instance.ray_intersect(&Vector3::new(10.0, 0.0, 0.0), &-right), 10.0, right);
+    }
+}
@@ -0,0 +1,585 @@
+use raylib::prelude::Vector3;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use super::bvh::Aabb;
+use crate::cube::{Vec3, face_uv, intersect_box};
+use crate::faces::{exposed_faces, hides_face, is_enclosed};
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::structures::BlockPos;
+
+// Lado de los trozos en bloques
+pub const CHUNK_SIZE: i32 = 16;
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+// Materiales distintos que caben en la paleta de un trozo con un byte por celda; si hacen falta más
+// las celdas pasan a dos bytes, que alcanzan para un material distinto en cada una
+const MAX_NARROW_MATERIALS: usize = 256;
+
+// Índices de las celdas en la paleta del trozo
+#[derive(Clone, Debug)]
+enum Cells {
+    Narrow(Box<[u8; CHUNK_VOLUME]>),
+    Wide(Box<[u16; CHUNK_VOLUME]>),
+}
+
+impl Cells {
+    #[inline]
+    fn get(&self, cell: usize) -> usize {
+        match self {
+            Cells::Narrow(cells) => cells[cell] as usize,
+            Cells::Wide(cells) => cells[cell] as usize,
+        }
+    }
+
+    fn set(&mut self, cell: usize, entry: usize) {
+        if let Cells::Narrow(cells) = self
+            && entry >= MAX_NARROW_MATERIALS
+        {
+            let mut wide = Box::new([0u16; CHUNK_VOLUME]);
+            for (wide, &narrow) in wide.iter_mut().zip(cells.iter()) {
+                *wide = narrow as u16;
+            }
+            *self = Cells::Wide(wide);
+        }
+        match self {
+            Cells::Narrow(cells) => cells[cell] = entry as u8,
+            Cells::Wide(cells) => cells[cell] = entry as u16,
+        }
+    }
+}
+
+// Trozo de 16³ bloques: un bit por celda dice si está ocupada y cada celda ocupada guarda el índice
+// de su material en la paleta del trozo, que a su vez apunta a la tabla de materiales del mundo
+#[derive(Clone, Debug)]
+struct Chunk {
+    occupancy: [u64; CHUNK_VOLUME / 64],
+    cells: Cells,
+    palette: Vec<usize>,
+    // Celdas que usan cada entrada de la paleta; las que se quedan a cero se reutilizan
+    usage: Vec<u16>,
+    count: usize,
+}
+
+impl Chunk {
+    fn new() -> Self {
+        Chunk {
+            occupancy: [0; CHUNK_VOLUME / 64],
+            cells: Cells::Narrow(Box::new([0; CHUNK_VOLUME])),
+            palette: Vec::new(),
+            usage: Vec::new(),
+            count: 0,
+        }
+    }
+
+    #[inline]
+    fn is_occupied(&self, cell: usize) -> bool {
+        self.occupancy[cell / 64] & (1 << (cell % 64)) != 0
+    }
+
+    // Id global del material de la celda, si está ocupada
+    #[inline]
+    fn material_id(&self, cell: usize) -> Option<usize> {
+        self.is_occupied(cell).then(|| self.palette[self.cells.get(cell)])
+    }
+
+    fn set(&mut self, cell: usize, material_id: Option<usize>) {
+        if self.is_occupied(cell) {
+            self.usage[self.cells.get(cell)] -= 1;
+            self.occupancy[cell / 64] &= !(1 << (cell % 64));
+            self.count -= 1;
+        }
+        let Some(material_id) = material_id else {
+            return;
+        };
+
+        // Como las entradas sin uso se reutilizan, nunca hay más que celdas en el trozo
+        let entry = match self.palette.iter().position(|&id| id == material_id) {
+            Some(entry) => entry,
+            None => match self.usage.iter().position(|&usage| usage == 0) {
+                Some(entry) => {
+                    self.palette[entry] = material_id;
+                    entry
+                }
+                None => {
+                    self.palette.push(material_id);
+                    self.usage.push(0);
+                    self.palette.len() - 1
+                }
+            },
+        };
+        self.usage[entry] += 1;
+        self.cells.set(cell, entry);
+        self.occupancy[cell / 64] |= 1 << (cell % 64);
+        self.count += 1;
+    }
+}
+
+// Mundo de bloques disperso: solo existen los trozos con algún bloque, en un mapa por coordenada de
+// trozo. El rayo avanza celda a celda (DDA) y salta de una vez los trozos que no existen, así que su
+// coste depende de lo que atraviesa y no de cuántos bloques haya
+#[derive(Clone, Debug, Default)]
+pub struct VoxelWorld {
+    // En Arc: copiar el mundo (el hilo de render trabaja con una copia) solo duplica los trozos que se editan
+    chunks: HashMap<BlockPos, Arc<Chunk>>,
+    materials: Vec<Material>,
+    // Id de cada material, para no comparar con toda la tabla en cada `set`
+    material_ids: HashMap<MaterialKey, usize>,
+    // Bloques emisivos, encerrados o no; se mantiene al poner y quitar bloques
+    emissive: BTreeSet<BlockPos>,
+    count: usize,
+    // Bloques extremos de los trozos que han tenido algo; el rayo no busca fuera de esta caja
+    bounds: Option<(BlockPos, BlockPos)>,
+}
+
+impl VoxelWorld {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.count
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn get(&self, position: BlockPos) -> Option<&Material> {
+        let (chunk, cell) = split(position);
+        let id = self.chunks.get(&chunk)?.material_id(cell)?;
+        Some(&self.materials[id])
+    }
+
+    // Pone (o quita, con None) el bloque de esa posición. Devuelve el material que había
+    pub fn set(&mut self, position: BlockPos, material: Option<&Material>) -> Option<Material> {
+        let previous = self.get(position).cloned();
+        let (chunk_position, cell) = split(position);
+        let material_id = material.map(|material| self.material_id(material));
+
+        if material_id.is_some() {
+            self.grow_bounds(chunk_position);
+        } else if !self.chunks.contains_key(&chunk_position) {
+            return previous;
+        }
+        if material.is_some_and(|material| material.emission_strength > 0.0) {
+            self.emissive.insert(position);
+        } else {
+            self.emissive.remove(&position);
+        }
+        let chunk = Arc::make_mut(self.chunks.entry(chunk_position).or_insert_with(|| Arc::new(Chunk::new())));
+        let before = chunk.count;
+        chunk.set(cell, material_id);
+        self.count = self.count + chunk.count - before;
+        if chunk.count == 0 {
+            self.chunks.remove(&chunk_position);
+        }
+        previous
+    }
+
+    // Bloques del mundo con su material, trozo a trozo
+    pub fn blocks(&self) -> impl Iterator<Item = (BlockPos, &Material)> {
+        self.chunks.iter().flat_map(move |(&(cx, cy, cz), chunk)| {
+            (0..CHUNK_VOLUME).filter_map(move |cell| {
+                let id = chunk.material_id(cell)?;
+                let (x, y, z) = cell_offset(cell);
+                let position = (cx * CHUNK_SIZE + x, cy * CHUNK_SIZE + y, cz * CHUNK_SIZE + z);
+                Some((position, &self.materials[id]))
+            })
+        })
+    }
+
+    fn material_id(&mut self, material: &Material) -> usize {
+        *self.material_ids.entry(MaterialKey::new(material)).or_insert_with(|| {
+            self.materials.push(material.clone());
+            self.materials.len() - 1
+        })
+    }
+
+    fn grow_bounds(&mut self, (cx, cy, cz): BlockPos) {
+        let low = (cx * CHUNK_SIZE, cy * CHUNK_SIZE, cz * CHUNK_SIZE);
+        let high = (low.0 + CHUNK_SIZE - 1, low.1 + CHUNK_SIZE - 1, low.2 + CHUNK_SIZE - 1);
+        self.bounds = Some(match self.bounds {
+            Some((min, max)) => (
+                (min.0.min(low.0), min.1.min(low.1), min.2.min(low.2)),
+                (max.0.max(high.0), max.1.max(high.1), max.2.max(high.2)),
+            ),
+            None => (low, high),
+        });
+    }
+
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.bounds.map(|(min, max)| Aabb {
+            min: Vector3::new(min.0 as f32 - 0.5, min.1 as f32 - 0.5, min.2 as f32 - 0.5),
+            max: Vector3::new(max.0 as f32 + 0.5, max.1 as f32 + 0.5, max.2 as f32 + 0.5),
+        })
+    }
+
+    // Posiciones de los bloques emisivos, estén encerrados o no
+    pub fn emissive_blocks(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.emissive.iter().copied()
+    }
+
+    // Bloques emisivos que se ven (no encerrados), como (centro, radio envolvente, material)
+    pub fn emitters(&self) -> Vec<(Vector3, f32, &Material)> {
+        self.emissive
+            .iter()
+            .filter(|&&position| !is_enclosed(position, |position| self.get(position)))
+            .filter_map(|&(x, y, z)| {
+                let material = self.get((x, y, z))?;
+                Some((Vector3::new(x as f32, y as f32, z as f32), 0.75f32.sqrt(), material))
+            })
+            .collect()
+    }
+
+    pub fn overlaps_sphere(&self, point: Vector3, radius: f32) -> bool {
+        let low = |value: f32| (value - radius).round() as i32;
+        let high = |value: f32| (value + radius).round() as i32;
+        for y in low(point.y)..=high(point.y) {
+            for z in low(point.z)..=high(point.z) {
+                for x in low(point.x)..=high(point.x) {
+                    if self.get((x, y, z)).is_none() {
+                        continue;
+                    }
+                    let dx = ((point.x - x as f32).abs() - 0.5).max(0.0);
+                    let dy = ((point.y - y as f32).abs() - 0.5).max(0.0);
+                    let dz = ((point.z - z as f32).abs() - 0.5).max(0.0);
+                    if dx * dx + dy * dy + dz * dz < radius * radius {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    // Primer bloque que toca el rayo: distancia, normal, UV y material
+    fn cast(&self, ro: Vec3, rd: Vec3) -> Option<(f32, Vec3, f32, f32, &Material)> {
+        let (min, max) = self.bounds?;
+        let bounds = self.aabb()?;
+        let inverse = Vec3::new(inverse(rd.x), inverse(rd.y), inverse(rd.z));
+        let (enter, exit) = slab(bounds.min, bounds.max, ro, inverse)?;
+        let step = (direction(inverse.x), direction(inverse.y), direction(inverse.z));
+        let delta = Vec3::new(inverse.x.abs(), inverse.y.abs(), inverse.z.abs());
+
+        // Las celdas van de p - 0.5 a p + 0.5: desplazando medio bloque la celda es el suelo del punto
+        let grid_origin = ro + Vec3::new(0.5, 0.5, 0.5);
+        let mut t = enter.max(0.0);
+        let material = |id: Option<usize>| id.map(|id| &self.materials[id]);
+        'restart: while t <= exit {
+            let point = grid_origin + rd * t;
+            let mut cell = (
+                (point.x.floor() as i32).clamp(min.0, max.0),
+                (point.y.floor() as i32).clamp(min.1, max.1),
+                (point.z.floor() as i32).clamp(min.2, max.2),
+            );
+            // Distancia a la que el rayo cruza el siguiente plano de la rejilla en cada eje
+            let boundary = |cell: i32, step: i32, origin: f32, inverse: f32| {
+                if step > 0 {
+                    (cell as f32 + 1.0 - origin) * inverse
+                } else if step < 0 {
+                    (cell as f32 - origin) * inverse
+                } else {
+                    f32::INFINITY
+                }
+            };
+            let mut next = Vec3::new(
+                boundary(cell.0, step.0, grid_origin.x, inverse.x),
+                boundary(cell.1, step.1, grid_origin.y, inverse.y),
+                boundary(cell.2, step.2, grid_origin.z, inverse.z),
+            );
+
+            let mut current: Option<(BlockPos, &Chunk)> = None;
+            // Celda de la que viene el rayo, su material y el eje por el que la dejó. Los impactos se
+            // buscan al cruzar de una celda a otra: así una cara tapada se atraviesa sin más
+            let mut previous: Option<(BlockPos, Option<usize>, usize)> = None;
+            // Al salir del mundo o entrar en un trozo vacío, lo siguiente es aire: si el rayo venía por
+            // dentro de un bloque (agua, vidrio), su cara de salida es el impacto
+            let leave = |previous: Option<(BlockPos, Option<usize>, usize)>, t: f32| {
+                let (from, from_id, axis) = previous?;
+                Some(face_hit(ro, rd, t, from, axis, material(from_id)?))
+            };
+            loop {
+                if cell.0 < min.0 || cell.1 < min.1 || cell.2 < min.2 || cell.0 > max.0 || cell.1 > max.1 || cell.2 > max.2 {
+                    return leave(previous, t);
+                }
+                let (chunk_position, index) = split(cell);
+                let chunk = match current {
+                    Some((position, chunk)) if position == chunk_position => chunk,
+                    _ => match self.chunks.get(&chunk_position) {
+                        Some(chunk) => {
+                            current = Some((chunk_position, chunk));
+                            chunk
+                        }
+                        // Trozo vacío: se sigue desde donde el rayo sale de él
+                        None => {
+                            if let Some(hit) = leave(previous, t) {
+                                return Some(hit);
+                            }
+                            let (cx, cy, cz) = chunk_position;
+                            let low = Vec3::new(
+                                (cx * CHUNK_SIZE) as f32 - 0.5,
+                                (cy * CHUNK_SIZE) as f32 - 0.5,
+                                (cz * CHUNK_SIZE) as f32 - 0.5,
+                            );
+                            let high = low + Vec3::new(CHUNK_SIZE as f32, CHUNK_SIZE as f32, CHUNK_SIZE as f32);
+                            let chunk_exit = slab(low.to_vector3(), high.to_vector3(), ro, inverse).map_or(t, |(_, exit)| exit);
+                            t = chunk_exit.max(t) + 1e-4;
+                            continue 'restart;
+                        }
+                    },
+                };
+
+                let id = chunk.material_id(index);
+                match previous {
+                    // Primera celda: si el rayo llega desde fuera (del mundo o de un trozo vacío) se prueba
+                    // la caja entera, que da la cara de entrada; si empieza dentro todavía no hay impacto
+                    None => {
+                        if let Some(block) = material(id)
+                            && t > 0.0
+                        {
+                            let faces = exposed_faces(block, cell, |position| self.get(position));
+                            let center = Vec3::new(cell.0 as f32, cell.1 as f32, cell.2 as f32);
+                            if let Some((distance, normal, u, v)) = intersect_box(center, Vec3::new(0.5, 0.5, 0.5), faces, ro, rd) {
+                                return Some((distance, normal, u, v, block));
+                            }
+                        }
+                    }
+                    Some((from, from_id, axis)) => {
+                        // La cara de entrada de esta celda, si la anterior no la tapa; si no, la de salida de
+                        // la anterior (el rayo venía por dentro de un bloque transparente)
+                        if let Some(block) = material(id)
+                            && !hides_face(material(from_id), block)
+                        {
+                            return Some(face_hit(ro, rd, t, cell, axis, block));
+                        }
+                        if let Some(block) = material(from_id)
+                            && !hides_face(material(id), block)
+                        {
+                            return Some(face_hit(ro, rd, t, from, axis, block));
+                        }
+                    }
+                }
+
+                // Avanza por el eje cuyo plano se cruza antes
+                let from = cell;
+                let axis = if next.x <= next.y && next.x <= next.z {
+                    t = next.x;
+                    cell.0 += step.0;
+                    next.x += delta.x;
+                    0
+                } else if next.y <= next.z {
+                    t = next.y;
+                    cell.1 += step.1;
+                    next.y += delta.y;
+                    1
+                } else {
+                    t = next.z;
+                    cell.2 += step.2;
+                    next.z += delta.z;
+                    2
+                };
+                previous = Some((from, id, axis));
+                if t > exit {
+                    return leave(previous, t);
+                }
+            }
+        }
+        None
+    }
+
+}
+
+impl RayIntersect for VoxelWorld {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let ro = Vec3::from_vector3(*ray_origin);
+        let rd = Vec3::from_vector3(*ray_direction);
+        match self.cast(ro, rd) {
+            Some((distance, normal, u, v, material)) => Intersect::new(
+                (ro + rd * distance).to_vector3(),
+                normal.to_vector3(),
+                distance,
+                material.clone(),
+                u,
+                v,
+            ),
+            None => Intersect::empty(),
+        }
+    }
+}
+
+// Material como clave de un mapa: sus números por bits y la ruta de la textura
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct MaterialKey {
+    values: [u32; 13],
+    texture: Option<String>,
+}
+
+impl MaterialKey {
+    fn new(material: &Material) -> Self {
+        let Material { diffuse, albedo, specular, refractive_index, texture_id, emission, emission_strength } = material;
+        let values = [
+            diffuse.x,
+            diffuse.y,
+            diffuse.z,
+            albedo[0],
+            albedo[1],
+            albedo[2],
+            albedo[3],
+            *specular,
+            *refractive_index,
+            emission.x,
+            emission.y,
+            emission.z,
+            *emission_strength,
+        ];
+        MaterialKey { values: values.map(f32::to_bits), texture: texture_id.clone() }
+    }
+}
+
+// Trozo de un bloque y su celda dentro de él (x + z·16 + y·256)
+#[inline]
+fn split((x, y, z): BlockPos) -> (BlockPos, usize) {
+    let chunk = (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE), z.div_euclid(CHUNK_SIZE));
+    let (lx, ly, lz) = (x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE), z.rem_euclid(CHUNK_SIZE));
+    (chunk, (lx + lz * CHUNK_SIZE + ly * CHUNK_SIZE * CHUNK_SIZE) as usize)
+}
+
+fn cell_offset(cell: usize) -> BlockPos {
+    let cell = cell as i32;
+    (cell % CHUNK_SIZE, cell / (CHUNK_SIZE * CHUNK_SIZE), (cell / CHUNK_SIZE) % CHUNK_SIZE)
+}
+
+// Impacto a distancia `t` en la cara del bloque `position` perpendicular a `axis` que cruza el rayo
+fn face_hit(ro: Vec3, rd: Vec3, t: f32, position: BlockPos, axis: usize, material: &Material) -> (f32, Vec3, f32, f32, &Material) {
+    let center = Vec3::new(position.0 as f32, position.1 as f32, position.2 as f32);
+    let (normal, u, v) = face_uv(ro + rd * t - center, Vec3::new(0.5, 0.5, 0.5), axis);
+    (t, normal, u, v, material)
+}
+
+#[inline]
+fn inverse(value: f32) -> f32 {
+    if value.abs() > 1e-8 { 1.0 / value } else { f32::INFINITY }
+}
+
+// Sentido en que avanza el rayo por un eje (0 si va paralelo a él)
+#[inline]
+fn direction(inverse: f32) -> i32 {
+    if inverse.is_infinite() { 0 } else { inverse.signum() as i32 }
+}
+
+// Tramo del rayo dentro de la caja (entrada, salida); None si no la toca por delante
+fn slab(min: Vector3, max: Vector3, ro: Vec3, inverse: Vec3) -> Option<(f32, f32)> {
+    let t1 = (Vec3::from_vector3(min) - ro) * inverse;
+    let t2 = (Vec3::from_vector3(max) - ro) * inverse;
+    let enter = t1.x.min(t2.x).max(t1.y.min(t2.y)).max(t1.z.min(t2.z));
+    let exit = t1.x.max(t2.x).min(t1.y.max(t2.y)).min(t1.z.max(t2.z));
+    (enter <= exit && exit >= 0.0).then_some((enter, exit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_material(number: usize) -> Material {
+        Material::new(Vector3::new(number as f32, 0.0, 0.0), 0.0, [1.0, 0.0, 0.0, 0.0], 1.0, None)
+    }
+
+    fn water() -> Material {
+        Material::new(Vector3::new(0.2, 0.4, 0.9), 80.0, [0.2, 0.2, 0.1, 0.7], 1.33, None)
+    }
+
+    // Agua de 4×4 en y = 14 y y = 15, la capa de arriba del trozo
+    fn pond() -> VoxelWorld {
+        let mut world = VoxelWorld::new();
+        for x in 0..4 {
+            for z in 0..4 {
+                world.set((x, 14, z), Some(&water()));
+                world.set((x, 15, z), Some(&water()));
+            }
+        }
+        world
+    }
+
+    fn assert_surface_hit(world: &VoxelWorld, origin: Vector3, direction: Vector3) {
+        let direction = direction.normalized();
+        let hit = world.ray_intersect(&origin, &direction);
+        assert!(hit.is_intersecting, "el rayo no encuentra la superficie");
+        // La superficie del agua está en y = 15.5
+        assert!((hit.point.y - 15.5).abs() < 1e-3, "impacto en {:?}", hit.point);
+        assert_eq!(hit.normal, Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(hit.material, water());
+    }
+
+    #[test]
+    fn water_surface_at_the_top_of_the_world() {
+        let world = pond();
+        // Desde el fondo: atraviesa la cara entre los dos bloques de agua sin pararse
+        assert_surface_hit(&world, Vector3::new(1.0, 14.2, 1.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_surface_hit(&world, Vector3::new(1.0, 15.2, 1.0), Vector3::new(0.3, 1.0, 0.2));
+    }
+
+    #[test]
+    fn water_surface_below_an_empty_chunk() {
+        let mut world = pond();
+        // Un bloque dos trozos más arriba: el de en medio no existe y el rayo lo salta
+        world.set((30, 40, 30), Some(&numbered_material(0)));
+        assert_surface_hit(&world, Vector3::new(1.0, 14.2, 1.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_surface_hit(&world, Vector3::new(2.5, 15.1, 2.5), Vector3::new(-0.2, 1.0, 0.1));
+    }
+
+    #[test]
+    fn chunk_with_more_materials_than_a_byte() {
+        let mut world = VoxelWorld::new();
+        let positions: Vec<BlockPos> = (0..CHUNK_VOLUME).map(cell_offset).take(300).collect();
+        for (number, &position) in positions.iter().enumerate() {
+            world.set(position, Some(&numbered_material(number)));
+        }
+        assert_eq!(world.chunk_count(), 1);
+        assert_eq!(world.block_count(), 300);
+        for (number, &position) in positions.iter().enumerate() {
+            assert_eq!(world.get(position), Some(&numbered_material(number)));
+        }
+
+        // Quitar y volver a poner reutiliza las entradas libres de la paleta
+        world.set(positions[0], None);
+        world.set(positions[1], Some(&numbered_material(1000)));
+        assert_eq!(world.get(positions[0]), None);
+        assert_eq!(world.get(positions[1]), Some(&numbered_material(1000)));
+        assert_eq!(world.get(positions[299]), Some(&numbered_material(299)));
+        assert_eq!(world.block_count(), 299);
+    }
+
+    #[test]
+    fn emitters_follow_set_and_clear() {
+        let lamp = Material::new_emissive(
+            Vector3::new(1.0, 0.9, 0.7),
+            0.0,
+            [1.0, 0.0, 0.0, 0.0],
+            1.0,
+            None,
+            Vector3::new(1.0, 0.9, 0.7),
+            4.0,
+        );
+        let stone = numbered_material(0);
+        let mut world = VoxelWorld::default();
+        world.set((0, 0, 0), Some(&lamp));
+        world.set((5, 0, 0), Some(&lamp));
+        assert_eq!(world.emitters().len(), 2);
+        assert_eq!(world.materials.len(), 1);
+
+        // Encerrar una lámpara la oculta, aunque siga siendo emisiva
+        for (dx, dy, dz) in [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)] {
+            world.set((dx, dy, dz), Some(&stone));
+        }
+        assert_eq!(world.emitters().len(), 1);
+        assert_eq!(world.emissive_blocks().count(), 2);
+
+        // Quitarla o taparla con otro material la saca de la lista
+        world.set((5, 0, 0), None);
+        world.set((0, 0, 0), Some(&stone));
+        assert!(world.emitters().is_empty());
+        assert_eq!(world.emissive_blocks().count(), 0);
+        assert_eq!(world.materials.len(), 2);
+    }
+}
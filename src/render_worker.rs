@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{Scope, ScopedJoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::editor::EditableWorld;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::temporal::{MAX_HISTORY, TemporalAccumulator};
+use crate::textures::TextureManager;
+use crate::tiles::CancellationToken;
+use crate::{QualitySettings, render};
+
+// Datos de la escena que el hilo de render solo necesita leer. Los bloques se pueden editar desde la
+// ventana: al empezar cada frame el hilo toma una copia bajo el cerrojo y lo suelta enseguida
+pub struct RenderScene<'a> {
+    pub world: &'a RwLock<EditableWorld>,
+    pub light: &'a Light,
+    pub texture_manager: &'a TextureManager,
+}
+
+// Instantánea de lo que hay que renderizar, enviada desde el hilo de la ventana
+#[derive(Clone)]
+pub struct RenderRequest {
+    pub camera: Camera,
+    pub quality: QualitySettings,
+    pub width: u32,
+    pub height: u32,
+    pub temporal: bool,
+    // Cambia con cada edición de bloques: el historial temporal ya no vale
+    pub scene_version: u32,
+}
+
+impl RenderRequest {
+    // Dos peticiones con la misma vista pueden reutilizar (y acumular) el mismo historial
+    pub fn same_view(&self, other: &RenderRequest) -> bool {
+        self.camera.eye == other.camera.eye
+            && self.camera.center == other.camera.center
+            && self.camera.up == other.camera.up
+            && self.camera.projection == other.camera.projection
+            && self.camera.fov == other.camera.fov
+            && self.camera.aperture == other.camera.aperture
+            && self.camera.focus_distance == other.camera.focus_distance
+            && self.camera.stereo == other.camera.stereo
+            && self.quality == other.quality
+            && self.width == other.width
+            && self.height == other.height
+            && self.temporal == other.temporal
+            && self.scene_version == other.scene_version
+    }
+}
+
+// Frame completo publicado por el hilo de render
+pub struct FinishedFrame {
+    pub framebuffer: Framebuffer,
+    pub render_time: Duration,
+    // Petición que lo generó
+    pub request: RenderRequest,
+    // El historial temporal de esta vista ya está lleno: otro frame igual apenas cambiaría la imagen
+    pub converged: bool,
+}
+
+// Hilo de render: recibe peticiones por canal y publica los frames terminados
+pub struct RenderWorker<'scope> {
+    requests: Option<Sender<RenderRequest>>,
+    frames: Receiver<FinishedFrame>,
+    progress: Arc<Mutex<Framebuffer>>,
+    active_cancel: Arc<Mutex<CancellationToken>>,
+    frame_started: Arc<Mutex<Option<Instant>>>,
+    // Peticiones enviadas que el hilo todavía no ha terminado (o descartado por viejas)
+    pending: Arc<AtomicUsize>,
+    handle: ScopedJoinHandle<'scope, ()>,
+}
+
+impl<'scope> RenderWorker<'scope> {
+    pub fn spawn<'env>(scope: &'scope Scope<'scope, 'env>, scene: RenderScene<'env>) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel::<RenderRequest>();
+        let (frame_sender, frame_receiver) = mpsc::channel::<FinishedFrame>();
+        let progress = Arc::new(Mutex::new(Framebuffer::new(1, 1)));
+        let active_cancel = Arc::new(Mutex::new(CancellationToken::new()));
+        let frame_started = Arc::new(Mutex::new(None));
+        let pending = Arc::new(AtomicUsize::new(0));
+
+        let worker_progress = Arc::clone(&progress);
+        let worker_cancel = Arc::clone(&active_cancel);
+        let worker_started = Arc::clone(&frame_started);
+        let worker_pending = Arc::clone(&pending);
+
+        let handle = scope.spawn(move || {
+            let mut temporal = TemporalAccumulator::new();
+            let mut scene_version = None;
+            // Frames seguidos terminados con la misma vista, que se han ido acumulando
+            let mut accumulated: Option<(RenderRequest, usize)> = None;
+
+            while let Ok(mut request) = request_receiver.recv() {
+                // Solo interesa la petición más reciente; las anteriores ya quedaron viejas
+                let mut received = 1;
+                while let Ok(newer) = request_receiver.try_recv() {
+                    request = newer;
+                    received += 1;
+                }
+
+                let cancel = CancellationToken::new();
+                *worker_cancel.lock().unwrap() = cancel.clone();
+                worker_progress.lock().unwrap().resize(request.width, request.height);
+                temporal.enabled = request.temporal;
+                if scene_version.replace(request.scene_version) != Some(request.scene_version) {
+                    temporal.reset();
+                }
+
+                let start = Instant::now();
+                *worker_started.lock().unwrap() = Some(start);
+
+                let (objects, emissive_sources) = scene.world.read().unwrap().snapshot();
+                let completed = render(
+                    &worker_progress,
+                    &objects,
+                    &emissive_sources,
+                    &request.camera,
+                    scene.light,
+                    scene.texture_manager,
+                    &request.quality,
+                    &mut temporal,
+                    &cancel,
+                );
+                drop((objects, emissive_sources));
+
+                *worker_started.lock().unwrap() = None;
+
+                if completed {
+                    let frames = match &accumulated {
+                        Some((previous, frames)) if previous.same_view(&request) => frames + 1,
+                        _ => 1,
+                    };
+                    accumulated = Some((request.clone(), frames));
+                    let frame = FinishedFrame {
+                        framebuffer: worker_progress.lock().unwrap().clone(),
+                        render_time: start.elapsed(),
+                        converged: request.temporal && frames > MAX_HISTORY as usize,
+                        request,
+                    };
+                    if frame_sender.send(frame).is_err() {
+                        break;
+                    }
+                }
+
+                worker_pending.fetch_sub(received, Ordering::AcqRel);
+            }
+        });
+
+        RenderWorker {
+            requests: Some(request_sender),
+            frames: frame_receiver,
+            progress,
+            active_cancel,
+            frame_started,
+            pending,
+            handle,
+        }
+    }
+
+    pub fn submit(&self, request: RenderRequest) {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        if let Some(requests) = &self.requests {
+            let _ = requests.send(request);
+        }
+    }
+
+    // Aborta el frame en curso (la vista cambió y ya no sirve)
+    pub fn cancel_current(&self) {
+        self.active_cancel.lock().unwrap().cancel();
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.pending.load(Ordering::Acquire) > 0
+    }
+
+    // Tiempo que lleva calculándose el frame actual
+    pub fn busy_for(&self) -> Option<Duration> {
+        self.frame_started.lock().unwrap().map(|start| start.elapsed())
+    }
+
+    // Devuelve el frame terminado más reciente, descartando los intermedios
+    pub fn take_latest_frame(&self) -> Option<FinishedFrame> {
+        self.frames.try_iter().last()
+    }
+
+    // Copia el buffer en progreso (el último frame con los tiles nuevos ya escritos encima)
+    pub fn copy_preview_into(&self, target: &mut Framebuffer) {
+        target.copy_from(&self.progress.lock().unwrap());
+    }
+
+    pub fn shutdown(mut self) {
+        self.cancel_current();
+        self.requests = None;
+        self.handle.join().expect("El hilo de render terminó con error");
+    }
+}
@@ -0,0 +1,519 @@
+use raylib::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::EmissiveSource;
+use crate::camera::Camera;
+use crate::cube::{Cube, Vec3};
+use crate::faces::{Occupancy, grid_position};
+use crate::light::Light;
+use crate::material::Material;
+use crate::gltf_loader::load_gltf;
+use crate::mesh_export::{export_gltf, export_obj};
+use crate::obj_loader::load_obj;
+use crate::primitives::{Group, Instance, Primitive, Transform};
+use crate::schematic::{BlockMaterials, load_schematic};
+use crate::structures::{BlockPos, Placement, cave_prefab, farm_prefab, house_prefab, portal_prefab, snowman_prefab, torch, tree_prefab};
+use crate::terrain::{TerrainMaterials, TerrainSettings, generate_terrain};
+use crate::textures::TextureManager;
+use crate::vegetation::{VegetationMaterials, scatter_vegetation};
+use crate::vox::{load_vox, save_vox};
+
+// Texturas que usan los materiales del diorama (el cielo incluido)
+pub const TEXTURE_PATHS: [&str; 15] = [
+    "assets/wood.png",
+    "assets/rock.png",
+    "assets/log.png",
+    "assets/log2.png",
+    "assets/leaf.png",
+    "assets/grass.png",
+    "assets/soil.png",
+    "assets/obs.png",
+    "assets/lava.png",
+    "assets/bamboo.png",
+    "assets/diamond.png",
+    "assets/bush.png",
+    "assets/face.png",
+    "assets/gravel.png",
+    crate::SKY_TEXTURE_PATH,
+];
+
+// Modelos de malla que se colocan en el diorama
+const BARREL_MODEL_PATH: &str = "assets/models/barril.obj";
+
+// Todo lo que el trazador necesita de la escena
+pub struct Scene {
+    pub objects: Vec<Primitive>,
+    pub emissive_sources: Vec<EmissiveSource>,
+    pub light: Light,
+    // Cámaras que trae la escena importada (--gltf-camera elige una)
+    pub cameras: Vec<Camera>,
+}
+
+impl Scene {
+    // Añade un modelo glTF al diorama: sus mallas, luces y cámaras. Una luz direccional
+    // sustituye a la luz principal
+    pub fn import_gltf(&mut self, path: &str, offset: Vector3, scale: f32, texture_manager: &mut TextureManager) -> Result<(), String> {
+        let import = load_gltf(path, offset, scale, texture_manager)?;
+        println!(
+            "{}: {} mallas, {} luces, {} cámaras",
+            path,
+            import.objects.len(),
+            import.emissive_sources.len() + usize::from(import.sun.is_some()),
+            import.cameras.len()
+        );
+
+        // Las mallas emisivas también iluminan su entorno
+        self.emissive_sources.extend(emissive_sources_from(&import.objects));
+        self.emissive_sources.extend(import.emissive_sources);
+        self.objects.extend(import.objects);
+        if let Some(sun) = import.sun {
+            self.light = sun;
+        }
+        self.cameras.extend(import.cameras);
+        Ok(())
+    }
+
+    // Añade un modelo MagicaVoxel al diorama, desplazado `offset` bloques
+    pub fn import_vox(&mut self, path: &str, offset: BlockPos) -> Result<(), String> {
+        let cubes = load_vox(path, offset)?;
+        println!("{}: {} bloques", path, cubes.len());
+        let model = Primitive::from(Group::new(cubes.into_iter().map(Primitive::from).collect()));
+        self.emissive_sources.extend(emissive_sources_from(std::slice::from_ref(&model)));
+        self.objects.push(model);
+        Ok(())
+    }
+
+    // Añade una estructura de Minecraft (`.schem` o `.nbt`) al diorama, desplazada `offset` bloques
+    pub fn import_schematic(&mut self, path: &str, offset: BlockPos, materials: &BlockMaterials) -> Result<(), String> {
+        let cubes = load_schematic(path, offset, materials)?;
+        println!("{}: {} bloques", path, cubes.len());
+        let model = Primitive::from(Group::new(cubes.into_iter().map(Primitive::from).collect()));
+        self.emissive_sources.extend(emissive_sources_from(std::slice::from_ref(&model)));
+        self.objects.push(model);
+        Ok(())
+    }
+
+    // Cubos de la escena, también los que van dentro de grupos como el terreno
+    pub fn cubes(&self) -> Vec<&Cube> {
+        let mut cubes = Vec::new();
+        collect_cubes(&self.objects, &mut cubes);
+        cubes
+    }
+
+    // Preparación para el render: quita los cubos rodeados de bloques opacos por sus seis caras (y sus
+    // fuentes emisivas), que ningún rayo puede ver, y guarda en el resto qué caras quedan expuestas.
+    // Los grupos se reconstruyen con su BVH. Devuelve cuántos cubos se quitaron
+    pub fn cull_hidden_cubes(&mut self) -> usize {
+        let cubes = self.cubes();
+        let occupancy = Occupancy::new(&cubes);
+        let mut enclosed_emitters = Vec::new();
+        let visibility: Vec<Option<u8>> = cubes
+            .iter()
+            .map(|cube| {
+                if !occupancy.is_enclosed(cube) {
+                    return Some(occupancy.exposed_faces(cube));
+                }
+                if cube.material.emission_strength > 0.0 {
+                    enclosed_emitters.push(Primitive::from((*cube).clone()));
+                }
+                None
+            })
+            .collect();
+        let removed = visibility.iter().filter(|faces| faces.is_none()).count();
+
+        let objects = std::mem::take(&mut self.objects);
+        self.objects = apply_visibility(objects, &mut visibility.into_iter());
+        // Se quita la fuente que generó cada cubo quitado, y solo esa: una luz de la escena que
+        // coincida en posición con un bloque encerrado se conserva
+        for source in emissive_sources_from(&enclosed_emitters) {
+            if let Some(index) = self.emissive_sources.iter().position(|known| *known == source) {
+                self.emissive_sources.remove(index);
+            }
+        }
+        removed
+    }
+
+    // Exporta a `.vox` los bloques de la escena: los cubos unitarios alineados con la rejilla.
+    // Devuelve cuántos se escribieron
+    pub fn export_vox(&self, path: &str, texture_manager: &TextureManager) -> Result<usize, String> {
+        let voxels: Vec<(BlockPos, &Material)> = self
+            .cubes()
+            .into_iter()
+            .filter_map(|cube| grid_position(cube).map(|position| (position, &cube.material)))
+            .collect();
+        save_vox(path, &voxels, texture_manager)
+    }
+
+    // Exporta los cubos como malla OBJ + MTL o glTF (según la extensión) sin las caras ocultas.
+    // El resto de primitivas (esferas, mallas importadas) no se exporta. Devuelve cuántas caras se escribieron
+    pub fn export_mesh(&self, path: &str) -> Result<usize, String> {
+        let cubes = self.cubes();
+        match Path::new(path).extension().and_then(|extension| extension.to_str()).map(str::to_lowercase).as_deref() {
+            Some("obj") => export_obj(path, &cubes),
+            Some("gltf") => export_gltf(path, &cubes),
+            _ => Err(format!("Formato de exportación desconocido (se espera .obj o .gltf): {}", path)),
+        }
+    }
+}
+
+// Recorre los objetos en el mismo orden que `collect_cubes`, aplicando a cada cubo sus caras
+// expuestas o quitándolo si no tiene
+fn apply_visibility(objects: impl IntoIterator<Item = Primitive>, visibility: &mut impl Iterator<Item = Option<u8>>) -> Vec<Primitive> {
+    objects
+        .into_iter()
+        .filter_map(|object| match object {
+            Primitive::Cube(mut cube) => {
+                cube.faces = visibility.next().flatten()?;
+                Some(Primitive::Cube(cube))
+            }
+            Primitive::Group(group) => {
+                let objects = apply_visibility(group.objects().cloned(), visibility);
+                (!objects.is_empty()).then(|| Primitive::from(Group::new(objects)))
+            }
+            object => Some(object),
+        })
+        .collect()
+}
+
+fn collect_cubes<'a>(objects: impl IntoIterator<Item = &'a Primitive>, cubes: &mut Vec<&'a Cube>) {
+    for object in objects {
+        match object {
+            Primitive::Cube(cube) => cubes.push(cube),
+            Primitive::Group(group) => collect_cubes(group.objects(), cubes),
+            _ => {}
+        }
+    }
+}
+
+// Cámara orbital con la que arranca el programa
+pub fn default_camera() -> Camera {
+    Camera::new(
+        Vector3::new(0.0, 3.0, 12.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    )
+}
+
+// Monta el diorama completo: materiales, estructuras, fuentes emisivas y luz principal.
+// Las texturas de los modelos de malla se cargan en `texture_manager` al leerlos.
+// Con `terrain` se genera además un paisaje procedural alrededor; falla si no cabe en memoria
+pub fn build_diorama(texture_manager: &mut TextureManager, terrain: Option<&TerrainSettings>) -> Result<Scene, String> {
+    // Definición de materiales principales usados en las estructuras
+    let log_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0), 
+        8.0,                         
+        [0.9, 0.1, 0.0, 0.0],       
+        0.0,
+        Some("assets/log.png".to_string()),
+    );
+    
+    let face_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0),
+        8.0,
+        [1.35, 0.12, 0.0, 0.0],
+        0.0,
+        Some("assets/face.png".to_string()),
+    );
+
+    let bamboo_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0),
+        8.0,                         
+        [0.9, 0.1, 0.0, 0.0], 
+        0.0,      
+        Some("assets/bamboo.png".to_string()),
+    );
+
+    let log2_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0), 
+        8.0,
+        [0.9, 0.1, 0.0, 0.0],
+        0.0,
+        Some("assets/log2.png".to_string()),
+    );
+
+    let soil_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0), 
+        3.0,                          
+        [0.95, 0.05, 0.0, 0.0],      
+        0.0,
+        Some("assets/soil.png".to_string()),
+    );
+
+
+    let leaf_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0), 
+        12.0,                        
+        [0.85, 0.15, 0.0, 0.0],      
+        0.0,
+        Some("assets/leaf.png".to_string()),
+    );
+
+    let bush_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0), 
+        12.0,                        
+        [0.85, 0.15, 0.0, 0.0],      
+        0.0,
+        Some("assets/bush.png".to_string()),
+    );
+
+    let lava_material = Material::new_emissive(
+        Vector3::new(1.5, 1.3, 1.0),  
+        5.0,                          
+        [1.0, 0.0, 0.0, 0.0],         
+        0.0,
+        Some("assets/lava.png".to_string()),
+        Vector3::new(0.8, 0.3, 0.05), 
+        2.0,                          
+    );
+
+    // Llama de las antorchas: sin textura, casi todo emisión
+    let torch_flame_material = Material::new_emissive(
+        Vector3::new(1.0, 0.75, 0.35),
+        5.0,
+        [1.0, 0.0, 0.0, 0.0],
+        0.0,
+        None,
+        Vector3::new(1.0, 0.55, 0.15),
+        2.5,
+    );
+
+    let grass_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0), 
+        10.0,                        
+        [0.9, 0.1, 0.0, 0.0],        
+        0.0,
+        Some("assets/grass.png".to_string()),
+    );
+
+    let diamond_material = Material::new(
+        Vector3::new(0.65, 0.92, 1.0),  
+        10.0,                          
+        [1.1, 0.55, 0.0, 0.0],        
+        0.0,                           
+        Some("assets/diamond.png".to_string()),
+    );
+
+    let rock_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0), 
+        6.0,                          
+        [0.95, 0.05, 0.0, 0.0],      
+        0.0,
+        Some("assets/rock.png".to_string()),
+    );
+
+    let _metal_material = Material::new(
+        Vector3::new(0.7, 0.7, 0.7), 
+        100.0,
+        [0.2, 0.6, 0.8, 0.0],       
+        0.0,
+        None,
+    );
+
+    let obs_material = Material::new(
+        Vector3::new(0.1, 0.1, 0.15), 
+        120.0,                        
+        [0.3, 0.4, 0.0, 0.0],         
+        0.0,
+        Some("assets/obs.png".to_string()),
+    );
+
+
+    let ice_material = Material::new(
+        Vector3::new(0.6, 0.8, 1.0),  
+        50.0,                         
+        [0.85, 0.1, 0.25, 0.0],     
+        0.0,                          
+        None,
+    );
+
+    let snow_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0),  
+        12.0,                         
+        [0.85, 0.1, 0.05, 0.0],       
+        0.0,                          
+        Some("assets/snow.png".to_string()),
+    );
+
+    let portal_material = Material::new(
+        Vector3::new(0.4, 0.1, 0.8), 
+        80.0,                        
+        [0.25, 0.2, 0.15, 0.55],     
+        1.45,
+        None,                         
+    );
+
+    let water_material = Material::new(
+        Vector3::new(0.18, 0.34, 0.48),  
+        70.0,                            
+        [0.35, 0.18, 0.12, 0.55],        
+        1.33,                            
+        None,
+    );
+
+    let gravel_material = Material::new(
+        Vector3::new(1.0, 1.0, 1.0),
+        4.0,
+        [0.95, 0.05, 0.0, 0.0],
+        0.0,
+        Some("assets/gravel.png".to_string()),
+    );
+
+    let sand_material = Material::new(
+        Vector3::new(0.93, 0.84, 0.6),
+        6.0,
+        [0.95, 0.05, 0.0, 0.0],
+        0.0,
+        None,
+    );
+
+    // Colección de cubos que componen el mundo voxel
+    let mut objects = Vec::new();
+
+
+    // Cada estructura es un prefab en coordenadas locales que se coloca en el diorama
+    cave_prefab(
+        grass_material.clone(),
+        soil_material.clone(),
+        rock_material.clone(),
+        ice_material.clone(),
+        lava_material.clone(),
+        diamond_material.clone(),
+        snow_material.clone(),
+    )
+    .place(&Placement::at(0, -1, 0), &mut objects);
+    house_prefab(rock_material.clone(), log_material.clone()).place(&Placement::at(0, -1, 4), &mut objects);
+    portal_prefab(obs_material.clone(), snow_material.clone(), portal_material.clone())
+        .place(&Placement::at(0, 11, 0), &mut objects);
+    // El mismo árbol se reutiliza junto a la casa, girado, reflejado y con la copa de arbusto
+    let tree = tree_prefab(leaf_material.clone(), log2_material.clone(), 4);
+    tree.place(&Placement::at(-3, -1, -4), &mut objects);
+    tree.place(
+        &Placement::at(-4, -1, 3).rotated(1).mirrored_x().with_material("hojas", bush_material.clone()),
+        &mut objects,
+    );
+    farm_prefab(bamboo_material.clone(), soil_material.clone(), water_material.clone(), bush_material.clone())
+        .place(&Placement::at(4, -1, -1), &mut objects);
+    snowman_prefab(snow_material.clone(), face_material.clone()).place(&Placement::at(-1, -1, -1), &mut objects);
+
+    // Las estructuras de bloques se suman al resto de primitivas de la escena
+    let mut objects: Vec<Primitive> = objects.into_iter().map(Primitive::from).collect();
+    // Las dos antorchas de la entrada comparten la misma geometría
+    let torch = Arc::new(torch(log_material.clone(), torch_flame_material.clone()));
+    for x in [-1.6, 1.6] {
+        objects.push(Instance::new(torch.clone(), Transform::translation(Vector3::new(x, -1.5, 5.9))).into());
+    }
+
+    // El terreno son miles de bloques: va en un grupo con su propia BVH
+    if let Some(settings) = terrain {
+        let mut blocks = Vec::new();
+        let materials = TerrainMaterials {
+            grass: grass_material.clone(),
+            snow: snow_material.clone(),
+            gravel: gravel_material.clone(),
+            sand: sand_material.clone(),
+            soil: soil_material.clone(),
+            rock: rock_material.clone(),
+            water: water_material.clone(),
+        };
+        let terrain = generate_terrain(settings, materials)?;
+        terrain.blocks.place(&Placement::default(), &mut blocks);
+        println!(
+            "Terreno {}x{} (semilla {}): {} bloques visibles",
+            settings.size,
+            settings.size,
+            settings.seed,
+            blocks.len()
+        );
+
+        if let Some(spacing) = settings.vegetation {
+            let vegetation_materials = VegetationMaterials {
+                oak_log: log2_material.clone(),
+                oak_leaves: leaf_material.clone(),
+                birch_log: Material::new(Vector3::new(0.88, 0.86, 0.78), 6.0, [0.95, 0.05, 0.0, 0.0], 0.0, None),
+                birch_leaves: Material::new(Vector3::new(0.5, 0.72, 0.3), 12.0, [0.85, 0.15, 0.0, 0.0], 0.0, None),
+                spruce_log: log_material.clone(),
+                spruce_leaves: Material::new(Vector3::new(0.13, 0.32, 0.18), 12.0, [0.85, 0.15, 0.0, 0.0], 0.0, None),
+                bamboo: bamboo_material.clone(),
+                bamboo_leaves: Material::new(Vector3::new(0.45, 0.7, 0.25), 12.0, [0.85, 0.15, 0.0, 0.0], 0.0, None),
+                bush: bush_material.clone(),
+            };
+            let before = blocks.len();
+            let plants = scatter_vegetation(&terrain, spacing, settings.seed, &vegetation_materials, &mut blocks);
+            println!("Vegetación: {} plantas, {} bloques", plants, blocks.len() - before);
+        }
+        objects.push(Group::new(blocks.into_iter().map(Primitive::from).collect()).into());
+    }
+
+    let barrel = load_obj(BARREL_MODEL_PATH, texture_manager).unwrap_or_else(|error| panic!("{}", error));
+    let barrel_placement = Transform::rotation_y(0.6).then(Transform::translation(Vector3::new(-3.2, -1.5, 6.1)));
+    objects.push(Primitive::from(barrel).transformed(barrel_placement));
+
+    let emissive_sources = emissive_sources_from(&objects);
+
+    let light = Light::new(
+        Vec3::new(8.0, 22.0, 20.0),  
+        Color::new(255, 255, 255, 255),
+        3.0,  
+    );
+
+    Ok(Scene {
+        objects,
+        emissive_sources,
+        light,
+        cameras: Vec::new(),
+    })
+}
+
+// Extraemos los objetos emisivos para acelerar el cálculo de luz secundaria
+pub fn emissive_sources_from(objects: &[Primitive]) -> Vec<EmissiveSource> {
+    objects
+        .iter()
+        .flat_map(Primitive::emitters)
+        .map(|(position, extent, material)| EmissiveSource {
+            position,
+            color: material.emission,
+            strength: material.emission_strength * 1.1,
+            radius: (extent * 9.0).max(5.0),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stone() -> Material {
+        Material::new(Vector3::new(0.5, 0.5, 0.5), 0.0, [1.0, 0.0, 0.0, 0.0], 1.0, None)
+    }
+
+    fn lamp() -> Material {
+        let color = Vector3::new(1.0, 0.8, 0.5);
+        Material::new_emissive(color, 0.0, [1.0, 0.0, 0.0, 0.0], 1.0, None, color, 3.0)
+    }
+
+    fn block(x: i32, y: i32, z: i32, material: Material) -> Primitive {
+        Primitive::from(Cube::new(Vec3::new(x as f32, y as f32, z as f32), Vec3::new(0.5, 0.5, 0.5), material))
+    }
+
+    #[test]
+    fn culling_removes_only_the_sources_of_culled_cubes() {
+        let mut objects = vec![block(0, 0, 0, lamp())];
+        for (x, y, z) in [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)] {
+            objects.push(block(x, y, z, stone()));
+        }
+        // Una luz importada en el mismo sitio que la lámpara encerrada, pero distinta de su fuente
+        let imported = EmissiveSource { position: Vector3::zero(), color: Vector3::one(), strength: 2.0, radius: 8.0 };
+        let mut emissive_sources = emissive_sources_from(&objects);
+        emissive_sources.push(imported.clone());
+        let mut scene = Scene {
+            objects,
+            emissive_sources,
+            light: Light::new(Vec3::new(0.0, 10.0, 0.0), Color::WHITE, 1.0),
+            cameras: Vec::new(),
+        };
+
+        assert_eq!(scene.cull_hidden_cubes(), 1);
+        assert!(scene.emissive_sources == vec![imported]);
+    }
+}
@@ -0,0 +1,466 @@
+use raylib::prelude::Vector3;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::cube::{Cube, Vec3};
+use crate::material::Material;
+use crate::nbt::{Tag, read_nbt};
+use crate::structures::BlockPos;
+use crate::textures::TextureManager;
+
+// Tabla de materiales por defecto para los bloques de Minecraft
+pub const DEFAULT_BLOCK_MATERIALS_PATH: &str = "assets/blocks.txt";
+
+// Bloques que no ocupan nada
+const AIR_BLOCKS: [&str; 4] = ["minecraft:air", "minecraft:cave_air", "minecraft:void_air", "minecraft:structure_void"];
+
+// Regla de la tabla: patrón de id (con `*` como comodín), propiedades de estado que deben
+// coincidir y material resultante (None: el bloque se omite)
+struct BlockRule {
+    pattern: String,
+    state: Vec<(String, String)>,
+    material: Option<Material>,
+}
+
+// Traducción de estados de bloque de Minecraft a materiales, leída de un archivo de texto:
+//   patrón[propiedad=valor,...]   textura.png | #rrggbb | -   [vidrio ior] [emisivo fuerza] [metal]
+// Gana la primera regla que coincide; los bloques sin regla usan un material de relleno
+pub struct BlockMaterials {
+    rules: Vec<BlockRule>,
+    placeholder: Material,
+}
+
+impl BlockMaterials {
+    // Las texturas de la tabla se cargan en `texture_manager` (rutas relativas al archivo)
+    pub fn load(path: &str, texture_manager: &mut TextureManager) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+        let mut rules = Vec::new();
+        for (line_index, line) in contents.lines().enumerate() {
+            // `#` también abre los colores: solo es comentario al principio de la línea
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = parse_rule(line, directory, texture_manager)
+                .map_err(|error| format!("{}: línea {}: {}", path, line_index + 1, error))?;
+            rules.push(rule);
+        }
+
+        Ok(BlockMaterials {
+            rules,
+            // Magenta como la textura que falta en el juego, para que se vea en seguida
+            placeholder: Material::new(Vector3::new(1.0, 0.0, 1.0), 10.0, [0.9, 0.1, 0.0, 0.0], 0.0, None),
+        })
+    }
+
+    // Material del estado (`minecraft:oak_log[axis=y]`); None si el bloque no se dibuja.
+    // El segundo valor indica si se ha usado el material de relleno
+    fn material_for(&self, block_state: &str) -> (Option<&Material>, bool) {
+        let (id, state) = split_block_state(block_state);
+        if AIR_BLOCKS.contains(&id.as_str()) {
+            return (None, false);
+        }
+        let rule = self.rules.iter().find(|rule| {
+            glob_match(&rule.pattern, &id) && rule.state.iter().all(|(key, value)| state.get(key) == Some(value))
+        });
+        match rule {
+            Some(rule) => (rule.material.as_ref(), false),
+            None => (Some(&self.placeholder), true),
+        }
+    }
+}
+
+// Carga una estructura de Minecraft: esquemas de Sponge (`.schem`, versiones 1 a 3) o estructuras
+// del juego (`.nbt`). Los ejes coinciden con los de la escena; se desplaza `offset` bloques
+pub fn load_schematic(path: &str, offset: BlockPos, materials: &BlockMaterials) -> Result<Vec<Cube>, String> {
+    let bytes = std::fs::read(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+    let root = read_nbt(&bytes).map_err(|error| format!("{}: {}", path, error))?;
+
+    let blocks = if root.get("blocks").is_some() && root.get("palette").or(root.get("palettes")).is_some() {
+        structure_blocks(&root)
+    } else {
+        sponge_blocks(root.get("Schematic").unwrap_or(&root))
+    }
+    .map_err(|error| format!("{}: {}", path, error))?;
+
+    let mut unknown: BTreeMap<String, usize> = BTreeMap::new();
+    let mut cubes = Vec::new();
+    for ((x, y, z), block_state) in blocks {
+        let (material, placeholder) = materials.material_for(&block_state);
+        if placeholder {
+            *unknown.entry(split_block_state(&block_state).0).or_default() += 1;
+        }
+        if let Some(material) = material {
+            cubes.push(Cube::new(
+                Vec3::new((x + offset.0) as f32, (y + offset.1) as f32, (z + offset.2) as f32),
+                Vec3::new(0.5, 0.5, 0.5),
+                material.clone(),
+            ));
+        }
+    }
+
+    if !unknown.is_empty() {
+        let names: Vec<String> = unknown.iter().map(|(id, count)| format!("{} ({})", id, count)).collect();
+        println!("{}: bloques sin material en la tabla: {}", path, names.join(", "));
+    }
+    Ok(cubes)
+}
+
+// Esquema de Sponge: paleta estado → índice y los índices de todos los bloques en varints,
+// recorridos en orden x, z, y. En la versión 3 van dentro del compuesto `Blocks`
+fn sponge_blocks(schematic: &Tag) -> Result<Vec<(BlockPos, String)>, String> {
+    let dimension = |name: &str| {
+        schematic
+            .get(name)
+            .and_then(Tag::as_i32)
+            .map(|value| value as u16 as usize)
+            .ok_or(format!("falta {}", name))
+    };
+    let (width, height, length) = (dimension("Width")?, dimension("Height")?, dimension("Length")?);
+    let container = schematic.get("Blocks").unwrap_or(schematic);
+    let palette = container
+        .get("Palette")
+        .and_then(Tag::as_compound)
+        .ok_or("falta la paleta de bloques")?;
+    let data = container
+        .get("Data")
+        .or(container.get("BlockData"))
+        .and_then(Tag::as_bytes)
+        .ok_or("faltan los datos de bloques")?;
+
+    let mut names: HashMap<i32, &str> = HashMap::new();
+    for (state, index) in palette {
+        names.insert(index.as_i32().ok_or("índice de paleta no válido")?, state);
+    }
+
+    // Las dimensiones son u16: el volumen se calcula sin desbordar y no puede pedir más bloques que
+    // bytes de datos (cada índice ocupa al menos uno)
+    let volume = width
+        .checked_mul(height)
+        .and_then(|area| area.checked_mul(length))
+        .ok_or("el esquema es demasiado grande")?;
+    if volume > data.len() {
+        return Err("los datos de bloques están truncados".to_string());
+    }
+    let mut blocks = Vec::with_capacity(volume);
+    let mut bytes = data.iter().map(|&byte| byte as u8);
+    for index in 0..volume {
+        let palette_index = read_varint(&mut bytes).ok_or("los datos de bloques están truncados")?;
+        let state = names
+            .get(&palette_index)
+            .ok_or(format!("índice de paleta desconocido: {}", palette_index))?;
+        let (x, z, y) = (index % width, (index / width) % length, index / (width * length));
+        blocks.push(((x as i32, y as i32, z as i32), state.to_string()));
+    }
+    Ok(blocks)
+}
+
+// Estructura del juego: lista de bloques con posición e índice en la paleta (la primera, si hay varias)
+fn structure_blocks(root: &Tag) -> Result<Vec<(BlockPos, String)>, String> {
+    let palette = match root.get("palette") {
+        Some(palette) => palette.as_list(),
+        None => root
+            .get("palettes")
+            .and_then(Tag::as_list)
+            .and_then(|palettes| palettes.first())
+            .and_then(Tag::as_list),
+    }
+    .ok_or("falta la paleta de bloques")?;
+
+    let states = palette
+        .iter()
+        .map(|entry| {
+            let name = entry.get("Name").and_then(Tag::as_str).ok_or("bloque de la paleta sin nombre")?;
+            let properties: BTreeMap<&str, &str> = entry
+                .get("Properties")
+                .and_then(Tag::as_compound)
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .filter_map(|(key, value)| Some((key.as_str(), value.as_str()?)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            if properties.is_empty() {
+                return Ok(name.to_string());
+            }
+            let properties: Vec<String> = properties.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+            Ok(format!("{}[{}]", name, properties.join(",")))
+        })
+        .collect::<Result<Vec<String>, &str>>()?;
+
+    let blocks = root.get("blocks").and_then(Tag::as_list).ok_or("falta la lista de bloques")?;
+    blocks
+        .iter()
+        .map(|block| {
+            let position = block.get("pos").and_then(Tag::as_ints).ok_or("bloque sin posición")?;
+            let [x, y, z] = position[..] else {
+                return Err("posición de bloque no válida".to_string());
+            };
+            let state = block.get("state").and_then(Tag::as_i32).ok_or("bloque sin estado")?;
+            let state = usize::try_from(state)
+                .ok()
+                .and_then(|state| states.get(state))
+                .ok_or(format!("estado de bloque desconocido: {}", state))?;
+            Ok(((x, y, z), state.clone()))
+        })
+        .collect()
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<i32> {
+    let mut value = 0i32;
+    for shift in (0..35).step_by(7) {
+        let byte = bytes.next()?;
+        value |= ((byte & 0x7f) as i32) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+// `minecraft:oak_log[axis=y]` → ("minecraft:oak_log", {axis: y}). Los ids sin espacio de nombres
+// se entienden como de `minecraft:`
+fn split_block_state(block_state: &str) -> (String, HashMap<String, String>) {
+    let (id, state) = match block_state.split_once('[') {
+        Some((id, state)) => (id, state.trim_end_matches(']')),
+        None => (block_state, ""),
+    };
+    let id = if id.contains(':') { id.to_string() } else { format!("minecraft:{}", id) };
+    let state = state
+        .split(',')
+        .filter_map(|property| property.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    (id, state)
+}
+
+fn parse_rule(line: &str, directory: &Path, texture_manager: &mut TextureManager) -> Result<BlockRule, String> {
+    let mut tokens = line.split_whitespace();
+    let pattern = tokens.next().ok_or("falta el patrón")?;
+    let appearance = tokens.next().ok_or("falta la textura o el color")?;
+    let (pattern, state) = split_block_state(pattern);
+    let state = state.into_iter().collect();
+
+    if appearance == "-" {
+        return Ok(BlockRule { pattern, state, material: None });
+    }
+
+    let (diffuse, texture) = match appearance.strip_prefix('#') {
+        Some(hex) => (parse_hex_color(hex)?, None),
+        None => {
+            let texture = crate::obj_loader::join_path(directory, appearance);
+            if !Path::new(&texture).exists() {
+                return Err(format!("no existe la textura {}", texture));
+            }
+            texture_manager.load_cpu_texture(&texture);
+            (Vector3::new(1.0, 1.0, 1.0), Some(texture))
+        }
+    };
+
+    let mut material = Material::new(diffuse, 10.0, [0.9, 0.1, 0.0, 0.0], 0.0, texture);
+    while let Some(modifier) = tokens.next() {
+        let mut number = |name: &str| -> Result<f32, String> {
+            let value = tokens.next().ok_or(format!("falta el valor de {}", name))?;
+            value.parse().map_err(|_| format!("valor no válido para {}: {}", name, value))
+        };
+        match modifier {
+            "vidrio" => {
+                material.refractive_index = number("vidrio")?;
+                material.albedo = [0.3, 0.2, 0.1, 0.55];
+                material.specular = 80.0;
+            }
+            "emisivo" => {
+                material.emission_strength = number("emisivo")?;
+                material.emission = diffuse;
+                material.albedo = [1.0, 0.0, 0.0, 0.0];
+            }
+            "metal" => {
+                material.albedo = [0.5, 0.4, 0.6, 0.0];
+                material.specular = 60.0;
+            }
+            _ => return Err(format!("modificador desconocido: {}", modifier)),
+        }
+    }
+    Ok(BlockRule { pattern, state, material: Some(material) })
+}
+
+fn parse_hex_color(hex: &str) -> Result<Vector3, String> {
+    let value = u32::from_str_radix(hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 6)
+        .ok_or(format!("color no válido: #{}", hex))?;
+    let channel = |shift: u32| ((value >> shift) & 0xff) as f32 / 255.0;
+    Ok(Vector3::new(channel(16), channel(8), channel(0)))
+}
+
+// Coincidencia con `*` como comodín de cualquier número de caracteres
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) || text.len() < first.len() + last.len() {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{compound, nbt_bytes};
+
+    fn string(text: &str) -> Tag {
+        Tag::String(text.to_string())
+    }
+
+    // Sponge de 2 × 1 × 2: índices en orden x, z, y; el 200 ocupa dos bytes de varint
+    fn sponge_v2() -> Tag {
+        compound(vec![
+            ("Version", Tag::Int(2)),
+            ("Width", Tag::Short(2)),
+            ("Height", Tag::Short(1)),
+            ("Length", Tag::Short(2)),
+            ("Palette", compound(vec![("minecraft:air", Tag::Int(0)), ("minecraft:stone", Tag::Int(200))])),
+            ("BlockData", Tag::ByteArray(vec![0, -56, 1, 0, -56, 1])),
+        ])
+    }
+
+    #[test]
+    fn sponge_blocks_from_nbt() {
+        let root = read_nbt(&nbt_bytes(&sponge_v2())).unwrap();
+        let blocks = sponge_blocks(&root).unwrap();
+        let stone: Vec<BlockPos> =
+            blocks.iter().filter(|(_, state)| state == "minecraft:stone").map(|&(position, _)| position).collect();
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(stone, vec![(1, 0, 0), (1, 0, 1)]);
+
+        // Versión 3: dentro de `Schematic`, con los datos en el compuesto `Blocks`
+        let v3 = compound(vec![(
+            "Schematic",
+            compound(vec![
+                ("Version", Tag::Int(3)),
+                ("Width", Tag::Short(1)),
+                ("Height", Tag::Short(2)),
+                ("Length", Tag::Short(1)),
+                (
+                    "Blocks",
+                    compound(vec![
+                        ("Palette", compound(vec![("minecraft:oak_log[axis=y]", Tag::Int(0)), ("minecraft:dirt", Tag::Int(1))])),
+                        ("Data", Tag::ByteArray(vec![1, 0])),
+                    ]),
+                ),
+            ]),
+        )]);
+        let root = read_nbt(&nbt_bytes(&v3)).unwrap();
+        let blocks = sponge_blocks(root.get("Schematic").unwrap()).unwrap();
+        assert_eq!(blocks, vec![((0, 0, 0), "minecraft:dirt".to_string()), ((0, 1, 0), "minecraft:oak_log[axis=y]".to_string())]);
+    }
+
+    #[test]
+    fn sponge_blocks_rejects_bad_data() {
+        let with = |changes: Vec<(&str, Tag)>| {
+            let Tag::Compound(mut entries) = sponge_v2() else { unreachable!() };
+            entries.extend(changes.into_iter().map(|(name, value)| (name.to_string(), value)));
+            sponge_blocks(&Tag::Compound(entries))
+        };
+        assert_eq!(
+            with(vec![("BlockData", Tag::ByteArray(vec![0, -56, 1]))]),
+            Err("los datos de bloques están truncados".to_string())
+        );
+        assert_eq!(
+            with(vec![("BlockData", Tag::ByteArray(vec![0, 5, 0, 0]))]),
+            Err("índice de paleta desconocido: 5".to_string())
+        );
+        assert!(with(vec![("Palette", Tag::Int(0))]).is_err());
+        // 65535 de lado en los tres ejes (los Short se leen sin signo): el volumen no cabe en un i32
+        let huge = vec![("Width", Tag::Short(-1)), ("Height", Tag::Short(-1)), ("Length", Tag::Short(-1))];
+        assert_eq!(with(huge), Err("los datos de bloques están truncados".to_string()));
+    }
+
+    #[test]
+    fn structure_blocks_from_nbt() {
+        let log = compound(vec![
+            ("Name", string("minecraft:oak_log")),
+            ("Properties", compound(vec![("axis", string("x")), ("age", string("2"))])),
+        ]);
+        let block = |x: i32, y: i32, z: i32, state: i32| {
+            compound(vec![("pos", Tag::List(vec![Tag::Int(x), Tag::Int(y), Tag::Int(z)])), ("state", Tag::Int(state))])
+        };
+        let root = compound(vec![
+            ("size", Tag::List(vec![Tag::Int(2), Tag::Int(2), Tag::Int(1)])),
+            ("palette", Tag::List(vec![compound(vec![("Name", string("minecraft:stone"))]), log])),
+            ("blocks", Tag::List(vec![block(0, 0, 0, 0), block(1, 1, 0, 1)])),
+        ]);
+        let root = read_nbt(&nbt_bytes(&root)).unwrap();
+        assert_eq!(
+            structure_blocks(&root).unwrap(),
+            vec![((0, 0, 0), "minecraft:stone".to_string()), ((1, 1, 0), "minecraft:oak_log[age=2,axis=x]".to_string())]
+        );
+
+        let Tag::Compound(mut entries) = root else { unreachable!() };
+        entries.insert("blocks".to_string(), Tag::List(vec![block(0, 0, 0, 7)]));
+        assert_eq!(structure_blocks(&Tag::Compound(entries)), Err("estado de bloque desconocido: 7".to_string()));
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("minecraft:stone", "minecraft:stone"));
+        assert!(!glob_match("minecraft:stone", "minecraft:stone_bricks"));
+        assert!(glob_match("minecraft:*_log", "minecraft:oak_log"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("minecraft:*glass*", "minecraft:red_stained_glass_pane"));
+        assert!(!glob_match("minecraft:*_log", "minecraft:oak_planks"));
+        // El principio y el final no pueden solaparse
+        assert!(!glob_match("ab*ba", "aba"));
+        assert!(glob_match("a*b*c", "a_c_b_c"));
+        assert!(!glob_match("a*b*c", "a_c_c"));
+    }
+
+    #[test]
+    fn block_states() {
+        let (id, state) = split_block_state("minecraft:oak_log[axis=y]");
+        assert_eq!(id, "minecraft:oak_log");
+        assert_eq!(state, HashMap::from([("axis".to_string(), "y".to_string())]));
+
+        let (id, state) = split_block_state("lantern[ hanging = true , waterlogged=false]");
+        assert_eq!(id, "minecraft:lantern");
+        assert_eq!(state.get("hanging").map(String::as_str), Some("true"));
+        assert_eq!(state.get("waterlogged").map(String::as_str), Some("false"));
+
+        let (id, state) = split_block_state("mod:block");
+        assert_eq!(id, "mod:block");
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn varints() {
+        let read = |bytes: &[u8]| read_varint(&mut bytes.iter().copied());
+        assert_eq!(read(&[0]), Some(0));
+        assert_eq!(read(&[0x7f]), Some(127));
+        assert_eq!(read(&[0x80, 0x01]), Some(128));
+        assert_eq!(read(&[0xc8, 0x01]), Some(200));
+        assert_eq!(read(&[0xff, 0xff, 0xff, 0xff, 0x07]), Some(i32::MAX));
+        assert_eq!(read(&[0xff, 0xff, 0xff, 0xff, 0x0f]), Some(-1));
+        // Sin el último byte, o con más de cinco
+        assert_eq!(read(&[0x80]), None);
+        assert_eq!(read(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x01]), None);
+
+        // Se leen seguidos del mismo flujo
+        let mut bytes = [0xc8, 0x01, 0x05].into_iter();
+        assert_eq!(read_varint(&mut bytes), Some(200));
+        assert_eq!(read_varint(&mut bytes), Some(5));
+        assert_eq!(read_varint(&mut bytes), None);
+    }
+}
@@ -0,0 +1,119 @@
+use raylib::prelude::Vector3;
+
+// Distancia entre ojos por defecto (unidades de escena; un bloque mide 1)
+pub const DEFAULT_EYE_SEPARATION: f32 = 0.2;
+
+// Cómo se reparten las dos vistas en la imagen final
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StereoLayout {
+    // Ojo izquierdo en la mitad izquierda, derecho en la derecha (visores VR, TV 3D)
+    SideBySide,
+    // Ojo izquierdo arriba, derecho abajo (habitual en vídeos 360° estéreo)
+    OverUnder,
+    // Ambas vistas superpuestas en rojo/cian (gafas anáglifas)
+    Anaglyph,
+}
+
+impl StereoLayout {
+    pub fn from_name(name: &str) -> Option<StereoLayout> {
+        match name {
+            "sbs" | "side-by-side" => Some(StereoLayout::SideBySide),
+            "ou" | "over-under" => Some(StereoLayout::OverUnder),
+            "anaglyph" => Some(StereoLayout::Anaglyph),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StereoLayout::SideBySide => "lado a lado",
+            StereoLayout::OverUnder => "arriba/abajo",
+            StereoLayout::Anaglyph => "anáglifo rojo/cian",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StereoEye {
+    Left,
+    Right,
+}
+
+impl StereoEye {
+    // Lado hacia el que se desplaza el ojo respecto al centro de la cámara
+    pub fn sign(&self) -> f32 {
+        match self {
+            StereoEye::Left => -1.0,
+            StereoEye::Right => 1.0,
+        }
+    }
+}
+
+// Parámetros del modo estéreo de la cámara
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StereoSettings {
+    pub layout: StereoLayout,
+    // Distancia interpupilar
+    pub eye_separation: f32,
+    // Distancia a la que convergen las dos vistas (None = la distancia de enfoque de la cámara)
+    pub convergence: Option<f32>,
+}
+
+impl StereoSettings {
+    pub fn new(layout: StereoLayout) -> Self {
+        StereoSettings {
+            layout,
+            eye_separation: DEFAULT_EYE_SEPARATION,
+            convergence: None,
+        }
+    }
+}
+
+// Región de la imagen que corresponde a un ojo y posición del píxel dentro de ella
+pub struct EyeViewport {
+    pub eye: StereoEye,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Para las disposiciones lado a lado y arriba/abajo, a qué ojo pertenece el píxel (x, y)
+pub fn eye_viewport(layout: StereoLayout, x: u32, y: u32, width: u32, height: u32) -> EyeViewport {
+    match layout {
+        StereoLayout::SideBySide => {
+            let half = (width / 2).max(1);
+            let eye = if x < half { StereoEye::Left } else { StereoEye::Right };
+            EyeViewport {
+                eye,
+                x: if x < half { x } else { x - half },
+                y,
+                width: if x < half { half } else { (width - half).max(1) },
+                height,
+            }
+        }
+        StereoLayout::OverUnder => {
+            let half = (height / 2).max(1);
+            let eye = if y < half { StereoEye::Left } else { StereoEye::Right };
+            EyeViewport {
+                eye,
+                x,
+                y: if y < half { y } else { y - half },
+                width,
+                height: if y < half { half } else { (height - half).max(1) },
+            }
+        }
+        StereoLayout::Anaglyph => EyeViewport {
+            eye: StereoEye::Left,
+            x,
+            y,
+            width,
+            height,
+        },
+    }
+}
+
+// Anáglifo de color: rojo del ojo izquierdo, verde y azul del derecho
+pub fn anaglyph(left: Vector3, right: Vector3) -> Vector3 {
+    Vector3::new(left.x, right.y, right.z)
+}
@@ -1,80 +1,36 @@
-use crate::cube::{Cube, Vec3};
 use crate::material::Material;
-
-pub fn farm(objects: &mut Vec<Cube>, bamboo_material: Material, soil_material: Material, water_material: Material, bush_material: Material, snow_material: Material, face_material: Material) {
-
-    let y = -1.0;
-    for x in [2, 6]{
-        for z in -4..=2 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                bamboo_material.clone(),
-            ));
-        }
-    }
-
-    let y = -1.0;
-    for z in [-4, 2]{
-        for x in 3..=6 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                bamboo_material.clone(),
-            ));
-        }
-    }
-
-    let y = -1.0;
-    for x in [3, 5]{
-        for z in -3..=1 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                soil_material.clone(),
-            ));
-        }
+use crate::structures::prefab::Prefab;
+
+// Huerto vallado con bambú: dos surcos de tierra, una acequia en medio y un arbusto detrás.
+// El origen está en el centro de la acequia
+pub fn farm_prefab(bamboo_material: Material, soil_material: Material, water_material: Material, bush_material: Material) -> Prefab {
+    let mut prefab = Prefab::new();
+    let bamboo = prefab.slot("valla", bamboo_material);
+    let soil = prefab.slot("tierra", soil_material);
+    let water = prefab.slot("agua", water_material);
+    let bush = prefab.slot("arbusto", bush_material);
+
+    for x in [-2, 2] {
+        prefab.fill((x, 0, -3), (x, 0, 3), bamboo);
     }
-
-    let y = -1.0;
-    for x in [4]{
-        for z in -3..=1 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                water_material.clone(),
-            ));
-        }
-    }
-
-    let y = -1.0;
-    for x in [4, 5]{
-        for z in 4..=5 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                bush_material.clone(),
-            ));
-        }
+    for z in [-3, 3] {
+        prefab.fill((-1, 0, z), (2, 0, z), bamboo);
     }
-
-    let x = -1.0;
-    let z = -1.0;
-    for y in -1..1 {
-        objects.push(Cube::new(
-            Vec3::new(x, y as f32, z),
-            Vec3::new(0.5, 0.5, 0.5),
-            snow_material.clone(),
-        ));
-    }
-
-    let x = -1.0;
-    let z = -1.0;
-    for y in [1] {
-        objects.push(Cube::new(
-            Vec3::new(x, y as f32, z),
-            Vec3::new(0.5, 0.5, 0.5),
-            face_material.clone(),
-        ));
+    for x in [-1, 1] {
+        prefab.fill((x, 0, -2), (x, 0, 2), soil);
     }
-}
\ No newline at end of file
+    prefab.fill((0, 0, -2), (0, 0, 2), water);
+    prefab.fill((0, 0, 5), (1, 0, 6), bush);
+    prefab
+}
+
+// Muñeco de nieve de dos bloques con la cara encima
+pub fn snowman_prefab(snow_material: Material, face_material: Material) -> Prefab {
+    let mut prefab = Prefab::new();
+    let snow = prefab.slot("nieve", snow_material);
+    let face = prefab.slot("cara", face_material);
+
+    prefab.fill((0, 0, 0), (0, 1, 0), snow);
+    prefab.block((0, 2, 0), face);
+    prefab
+}
@@ -1,56 +1,18 @@
-use crate::cube::{Cube, Vec3};
 use crate::material::Material;
+use crate::structures::prefab::Prefab;
 
-pub fn house_structure(objects: &mut Vec<Cube>, wood_material: Material) {
-    
-    for y in -1..=0 {
-        for x in -1 ..=1 {
-            for z in 3..=5 {
-                objects.push(Cube::new(
-                    Vec3::new(x as f32, y as f32, z as f32),
-                    Vec3::new(0.5, 0.5, 0.5),
-                    wood_material.clone(),
-                ))
-            }
-        }
-    }
-}
-
-
-pub fn house_roof(objects: &mut Vec<Cube>, wood_material: Material) {
-    let y = 0.0;
-    for x in [-2, 2]{
-        for z in 3..=5 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                wood_material.clone(),
-            ));
-        }
-    }
-}
+// Casa de 3x2x3 bloques con tejado a dos aguas. El origen está en el centro del suelo;
+// el tejado asoma un bloque a cada lado en X
+pub fn house_prefab(wall_material: Material, roof_material: Material) -> Prefab {
+    let mut prefab = Prefab::new();
+    let wall = prefab.slot("paredes", wall_material);
+    let roof = prefab.slot("tejado", roof_material);
 
-pub fn house_roof_peak(objects: &mut Vec<Cube>, wood_material: Material) {
-    let y = 1.0;
-    for x in -1..=1 {
-        for z in 3..=5 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                wood_material.clone(),
-            ));
-        }
+    prefab.fill((-1, 0, -1), (1, 1, 1), wall);
+    for x in [-2, 2] {
+        prefab.fill((x, 1, -1), (x, 1, 1), roof);
     }
+    prefab.fill((-1, 2, -1), (1, 2, 1), roof);
+    prefab.fill((0, 3, -1), (0, 3, 1), roof);
+    prefab
 }
-
-pub fn house_peak(objects: &mut Vec<Cube>, wood_material: Material) {
-    let y = 2.0;
-    let x = 0.0;
-    for z in 3..=5 {
-        objects.push(Cube::new(
-            Vec3::new(x as f32, y as f32, z as f32),
-            Vec3::new(0.5, 0.5, 0.5),
-            wood_material.clone(),
-        ))
-    }
-}
\ No newline at end of file
@@ -9,4 +9,8 @@ pub use cave::*;
 pub mod portal;
 pub use portal::*;
 pub mod farm;
-pub use farm::*;
\ No newline at end of file
+pub use farm::*;
+pub mod props;
+pub use props::*;
+pub mod prefab;
+pub use prefab::*;
@@ -1,94 +1,35 @@
-use crate::cube::{Cube, Vec3};
 use crate::material::Material;
+use crate::structures::prefab::Prefab;
 
-pub fn portal_structure(objects: &mut Vec<Cube>, obs_material: Material, snow_material: Material, portal_material: Material) {
-    let y = 11.0;
-    for x in [-5, 5]{
-        for z in -1..=1 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                obs_material.clone(),
-            ));
-        }
-    }
+// Portal sobre una plataforma nevada. El origen está en el centro del anillo de obsidiana de la base
+pub fn portal_prefab(obs_material: Material, snow_material: Material, portal_material: Material) -> Prefab {
+    let mut prefab = Prefab::new();
+    let obs = prefab.slot("marco", obs_material);
+    let snow = prefab.slot("nieve", snow_material);
+    let portal = prefab.slot("portal", portal_material);
 
-    let y = 11.0;
-    for z in [-5, 5]{
-        for x in -1..=1 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                obs_material.clone(),
-            ));
-        }
+    for x in [-5, 5] {
+        prefab.fill((x, 0, -1), (x, 0, 1), obs);
     }
-
-    let y = 12.0;
-    for z in [-4, 4]{
-        for x in -1..=1 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                obs_material.clone(),
-            ));
-        }
+    for z in [-5, 5] {
+        prefab.fill((-1, 0, z), (1, 0, z), obs);
     }
-
-    let y = 12.0;
-    for x in [-4, 4]{
-        for z in -1..=1 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                obs_material.clone(),
-            ));
-        }
+    for z in [-4, 4] {
+        prefab.fill((-1, 1, z), (1, 1, z), obs);
     }
-
-    let y = 12.0;
-    for x in -3..=3 {
-        for z in -3..=3 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                snow_material.clone(),
-            ));
-        }
+    for x in [-4, 4] {
+        prefab.fill((x, 1, -1), (x, 1, 1), obs);
     }
 
-    let z = 0.0;
-    for x in [-2, 2]{
-        for y in 13..=17 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y as f32, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                obs_material.clone(),
-            ));
-        }
-    }
+    prefab.fill((-3, 1, -3), (3, 1, 3), snow);
 
-    let z = 0.0;
-    for y in [13.0, 17.0]{
-        for x in -2..=2 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y as f32, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                obs_material.clone(),
-            ));
-        }
+    for x in [-2, 2] {
+        prefab.fill((x, 2, 0), (x, 6, 0), obs);
     }
-
-    let z = 0.0;
-    for y in 12..=16 {
-        for x in -1..=1 {
-            objects.push(Cube::new(
-                Vec3::new(x as f32, y as f32, z as f32),
-                Vec3::new(0.5, 0.5, 0.5),
-                portal_material.clone(),
-            ));
-        }
+    for y in [2, 6] {
+        prefab.fill((-2, y, 0), (2, y, 0), obs);
     }
 
-
-}
\ No newline at end of file
+    prefab.fill((-1, 1, 0), (1, 5, 0), portal);
+    prefab
+}
@@ -0,0 +1,175 @@
+use crate::cube::{Cube, Vec3};
+use crate::material::Material;
+
+// Posición de un bloque en coordenadas enteras (x, y, z)
+pub type BlockPos = (i32, i32, i32);
+
+// Estructura reutilizable: bloques en coordenadas locales, cada uno apuntando a una ranura de material.
+// El origen local es el punto de apoyo sobre el que se gira y se refleja al colocarla
+#[derive(Clone, Debug, Default)]
+pub struct Prefab {
+    blocks: Vec<(BlockPos, usize)>,
+    slots: Vec<(&'static str, Material)>,
+}
+
+impl Prefab {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Declara una ranura de material con su material por defecto y devuelve su índice
+    pub fn slot(&mut self, name: &'static str, material: Material) -> usize {
+        self.slots.push((name, material));
+        self.slots.len() - 1
+    }
+
+    pub fn block(&mut self, position: BlockPos, slot: usize) {
+        self.blocks.push((position, slot));
+    }
+
+    // Rellena la caja entre `min` y `max`, ambos incluidos
+    pub fn fill(&mut self, min: BlockPos, max: BlockPos, slot: usize) {
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                for z in min.2..=max.2 {
+                    self.block((x, y, z), slot);
+                }
+            }
+        }
+    }
+
+    // Añade los cubos de la estructura ya transformados por la colocación
+    pub fn place(&self, placement: &Placement, objects: &mut Vec<Cube>) {
+        for (slot, _) in &placement.overrides {
+            if !self.slots.iter().any(|(name, _)| name == slot) {
+                panic!("El prefab no tiene la ranura de material {}", slot);
+            }
+        }
+        let materials: Vec<&Material> = self
+            .slots
+            .iter()
+            .map(|(name, material)| {
+                placement
+                    .overrides
+                    .iter()
+                    .rev()
+                    .find(|(slot, _)| slot == name)
+                    .map_or(material, |(_, material)| material)
+            })
+            .collect();
+
+        objects.extend(self.blocks.iter().map(|&(position, slot)| {
+            let (x, y, z) = placement.apply(position);
+            Cube::new(
+                Vec3::new(x as f32, y as f32, z as f32),
+                Vec3::new(0.5, 0.5, 0.5),
+                materials[slot].clone(),
+            )
+        }));
+    }
+}
+
+// Dónde y cómo se coloca un prefab: primero se refleja, luego se gira sobre Y y por último se desplaza
+#[derive(Clone, Debug, Default)]
+pub struct Placement {
+    pub offset: BlockPos,
+    // Cuartos de vuelta alrededor de Y (un cuarto lleva +X a -Z)
+    pub quarter_turns: u8,
+    // Reflejo en X; combinado con media vuelta equivale a reflejar en Z
+    pub mirror_x: bool,
+    // Materiales que sustituyen a los de las ranuras con ese nombre
+    pub overrides: Vec<(&'static str, Material)>,
+}
+
+impl Placement {
+    pub fn at(x: i32, y: i32, z: i32) -> Self {
+        Placement { offset: (x, y, z), ..Self::default() }
+    }
+
+    pub fn rotated(mut self, quarter_turns: u8) -> Self {
+        self.quarter_turns = (self.quarter_turns + quarter_turns % 4) % 4;
+        self
+    }
+
+    pub fn mirrored_x(mut self) -> Self {
+        self.mirror_x = !self.mirror_x;
+        self
+    }
+
+    pub fn with_material(mut self, slot: &'static str, material: Material) -> Self {
+        self.overrides.push((slot, material));
+        self
+    }
+
+    // Lleva una posición local del prefab a coordenadas del mundo
+    pub fn apply(&self, (x, y, z): BlockPos) -> BlockPos {
+        let x = if self.mirror_x { -x } else { x };
+        let (x, z) = match self.quarter_turns % 4 {
+            0 => (x, z),
+            1 => (z, -x),
+            2 => (-x, -z),
+            _ => (-z, x),
+        };
+        (x + self.offset.0, y + self.offset.1, z + self.offset.2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::plain;
+
+    #[test]
+    fn quarter_turns_rotate_around_y() {
+        let corner = (2, 1, 1);
+        assert_eq!(Placement::default().apply(corner), corner);
+        // Un cuarto de vuelta lleva +X a -Z y +Z a +X
+        assert_eq!(Placement::default().rotated(1).apply((1, 0, 0)), (0, 0, -1));
+        assert_eq!(Placement::default().rotated(1).apply((0, 0, 1)), (1, 0, 0));
+        assert_eq!(Placement::default().rotated(1).apply(corner), (1, 1, -2));
+        assert_eq!(Placement::default().rotated(2).apply(corner), (-2, 1, -1));
+        assert_eq!(Placement::default().rotated(3).apply(corner), (-1, 1, 2));
+        // Los giros se acumulan módulo cuatro
+        assert_eq!(Placement::default().rotated(3).rotated(2).quarter_turns, 1);
+        assert_eq!(Placement::default().rotated(5).apply(corner), (1, 1, -2));
+        assert_eq!(Placement::default().rotated(2).rotated(2).apply(corner), corner);
+    }
+
+    #[test]
+    fn mirroring_happens_before_rotation_and_offset() {
+        let corner = (2, 1, 1);
+        let mirrored = Placement::at(10, 20, 30).mirrored_x();
+        assert_eq!(mirrored.apply(corner), (8, 21, 31));
+        // Reflejar en X y girar media vuelta equivale a reflejar en Z
+        assert_eq!(Placement::default().mirrored_x().rotated(2).apply(corner), (2, 1, -1));
+        // Reflejar y luego girar un cuarto no es lo mismo que girar y luego reflejar
+        assert_eq!(Placement::default().mirrored_x().rotated(1).apply(corner), (1, 1, 2));
+        assert_eq!(Placement::default().mirrored_x().mirrored_x().apply(corner), corner);
+    }
+
+    #[test]
+    fn place_transforms_blocks_and_overrides_slots() {
+        let mut prefab = Prefab::new();
+        let wood = prefab.slot("madera", plain(0.5, 0.3, 0.1));
+        let leaves = prefab.slot("hojas", plain(0.1, 0.6, 0.1));
+        prefab.block((1, 0, 0), wood);
+        prefab.fill((0, 1, 0), (0, 1, 1), leaves);
+
+        let mut objects = Vec::new();
+        prefab.place(&Placement::at(5, 0, 5).rotated(1).with_material("hojas", plain(1.0, 0.0, 0.0)), &mut objects);
+        let placed: Vec<((f32, f32, f32), f32)> = objects
+            .iter()
+            .map(|cube| ((cube.center.x, cube.center.y, cube.center.z), cube.material.diffuse.x))
+            .collect();
+        assert_eq!(placed, vec![((5.0, 0.0, 4.0), 0.5), ((5.0, 1.0, 5.0), 1.0), ((6.0, 1.0, 5.0), 1.0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "no tiene la ranura de material piedra")]
+    fn unknown_override_slot_panics() {
+        let mut prefab = Prefab::new();
+        let wood = prefab.slot("madera", plain(0.5, 0.3, 0.1));
+        prefab.block((0, 0, 0), wood);
+        prefab.place(&Placement::default().with_material("piedra", plain(0.5, 0.5, 0.5)), &mut Vec::new());
+    }
+}
@@ -0,0 +1,15 @@
+use raylib::prelude::Vector3;
+
+use crate::material::Material;
+use crate::primitives::{Capsule, Group, Primitive, Sphere};
+
+// Antorcha con la base en el origen: palo redondeado con una llama emisiva encima.
+// Es un prototipo: se coloca en la escena con instancias
+pub fn torch(stick_material: Material, flame_material: Material) -> Primitive {
+    let stick_top = Vector3::new(0.0, 0.9, 0.0);
+    Group::new(vec![
+        Capsule::new(Vector3::new(0.0, 0.06, 0.0), stick_top, 0.06, stick_material).into(),
+        Sphere::new(stick_top + Vector3::new(0.0, 0.12, 0.0), 0.13, flame_material).into(),
+    ])
+    .into()
+}
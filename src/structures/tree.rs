@@ -1,45 +1,26 @@
-use crate::cube::{Cube, Vec3};
 use crate::material::Material;
+use crate::structures::prefab::Prefab;
+
+// Árbol con el tronco en el origen y la copa sobre los últimos bloques del tronco
+pub fn tree_prefab(leaf_material: Material, log_material: Material, trunk_height: i32) -> Prefab {
+    let mut prefab = Prefab::new();
+    let log = prefab.slot("tronco", log_material);
+    let leaf = prefab.slot("hojas", leaf_material);
 
-pub fn tree_structure(objects: &mut Vec<Cube>, leaf_material: Material, log_material: Material) {
     // Tronco
+    prefab.fill((0, 0, 0), (0, trunk_height - 1, 0), log);
 
-    let x = -3.0;
-    let z = -4.0;
-    for y in -1..3 {
-        objects.push(Cube::new(
-            Vec3::new(x, y as f32, z),
-            Vec3::new(0.5, 0.5, 0.5),
-            log_material.clone(),
-        ));
-    }
+    // Nivel superior
+    prefab.block((0, trunk_height + 2, 0), leaf);
 
-    //hojas -
-    let trunk_x = -3.0;
-    let trunk_z = -4.0;
-    
-    // Nivel superior 
-    objects.push(Cube::new(
-        Vec3::new(trunk_x, 5.0, trunk_z),
-        Vec3::new(0.5, 0.5, 0.5),
-        leaf_material.clone(),
-    ));
-    
-    // Nivel medio (y=4)
+    // Nivel medio
     for (dx, dz) in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)] {
-        objects.push(Cube::new(
-            Vec3::new(trunk_x + dx as f32, 4.0, trunk_z + dz as f32),
-            Vec3::new(0.5, 0.5, 0.5),
-            leaf_material.clone(),
-        ));
+        prefab.block((dx, trunk_height + 1, dz), leaf);
     }
-    
-    // Nivel inferior (y=3) 
+
+    // Nivel inferior
     for (dx, dz) in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, 1)] {
-        objects.push(Cube::new(
-            Vec3::new(trunk_x + dx as f32, 3.0, trunk_z + dz as f32),
-            Vec3::new(0.5, 0.5, 0.5),
-            leaf_material.clone(),
-        ));
+        prefab.block((dx, trunk_height, dz), leaf);
     }
-}
\ No newline at end of file
+    prefab
+}
@@ -0,0 +1,233 @@
+use raylib::prelude::Vector3;
+
+use crate::camera::Camera;
+use crate::tiles::Tile;
+
+// Peso mínimo del frame nuevo: evita que el historial se quede "pegado"
+const MIN_BLEND: f32 = 0.1;
+// Tolerancia relativa (según la distancia a la cámara) para aceptar una posición reproyectada
+const DEPTH_TOLERANCE: f32 = 0.04;
+// Coseno mínimo entre normales para considerar que es la misma superficie
+const NORMAL_TOLERANCE: f32 = 0.9;
+// Longitud máxima del historial por píxel
+pub const MAX_HISTORY: u16 = 16;
+
+// Información del primer impacto de cada píxel, usada para reproyectar
+#[derive(Clone, Copy)]
+pub struct PrimaryHit {
+    pub point: Vector3,
+    pub normal: Vector3,
+}
+
+// Resultado de un píxel antes de convertirlo a Color
+#[derive(Clone, Copy)]
+pub struct PixelSample {
+    pub color: Vector3,
+    pub direction: Vector3,
+    pub hit: Option<PrimaryHit>,
+}
+
+// Tile ya mezclado con el historial, pendiente de guardarse al terminar el frame
+pub struct ResolvedTile {
+    pub tile: Tile,
+    pub samples: Vec<PixelSample>,
+    pub history: Vec<u16>,
+}
+
+// Acumulador temporal: guarda el frame anterior y lo mezcla con el nuevo
+pub struct TemporalAccumulator {
+    pub enabled: bool,
+    width: u32,
+    height: u32,
+    colors: Vec<Vector3>,
+    hits: Vec<Option<PrimaryHit>>,
+    history: Vec<u16>,
+    previous_camera: Option<Camera>,
+    frame_index: u32,
+}
+
+impl TemporalAccumulator {
+    pub fn new() -> Self {
+        TemporalAccumulator {
+            enabled: true,
+            width: 0,
+            height: 0,
+            colors: Vec::new(),
+            hits: Vec::new(),
+            history: Vec::new(),
+            previous_camera: None,
+            frame_index: 0,
+        }
+    }
+
+    // Descarta el historial (cambio de resolución, escena, etc.)
+    pub fn reset(&mut self) {
+        self.colors.clear();
+        self.hits.clear();
+        self.history.clear();
+        self.previous_camera = None;
+    }
+
+    // Desplazamiento sub-píxel del frame actual (secuencia de Halton 2,3)
+    pub fn jitter(&self) -> (f32, f32) {
+        if !self.enabled {
+            return (0.0, 0.0);
+        }
+        let index = self.frame_index % 8 + 1;
+        (halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+    }
+
+    // Número de frame, útil como semilla para el muestreo aleatorio
+    pub fn frame_index(&self) -> u32 {
+        self.frame_index
+    }
+
+    // Prepara el acumulador para un frame nuevo; descarta el historial si cambió la resolución
+    pub fn begin_frame(&mut self, width: u32, height: u32) {
+        if !self.enabled || self.width != width || self.height != height {
+            self.reset();
+            self.width = width;
+            self.height = height;
+        }
+    }
+
+    // Mezcla las muestras de un tile con el historial reproyectado.
+    // Devuelve la longitud de historial de cada píxel del tile.
+    pub fn resolve_tile(
+        &self,
+        tile: &Tile,
+        samples: &mut [PixelSample],
+        camera: &Camera,
+    ) -> Vec<u16> {
+        let mut history = vec![0u16; samples.len()];
+
+        let Some(previous_camera) = &self.previous_camera else {
+            return history;
+        };
+
+        let current_colors: Vec<Vector3> = samples.iter().map(|s| s.color).collect();
+
+        for (index, (sample, count)) in samples.iter_mut().zip(history.iter_mut()).enumerate() {
+            let Some(previous_index) = reproject(sample, previous_camera, self.width, self.height) else {
+                continue;
+            };
+
+            if !same_surface(&sample.hit, &self.hits[previous_index], &camera.eye) {
+                continue;
+            }
+
+            // Recorta el historial al rango de colores vecinos para evitar estelas
+            let (low, high) = neighborhood_bounds(&current_colors, index, tile.width, tile.height);
+            let previous_color = self.colors[previous_index].max(low).min(high);
+
+            let accumulated = (self.history[previous_index] + 1).min(MAX_HISTORY);
+            let blend = (1.0 / (accumulated as f32 + 1.0)).max(MIN_BLEND);
+
+            sample.color = previous_color.lerp(sample.color, blend);
+            *count = accumulated;
+        }
+
+        history
+    }
+
+    // Guarda el frame terminado como historial para el siguiente
+    pub fn end_frame(&mut self, camera: &Camera, tiles: &[ResolvedTile]) {
+        if !self.enabled {
+            return;
+        }
+
+        let total_pixels = (self.width * self.height) as usize;
+        let mut colors = vec![Vector3::zero(); total_pixels];
+        let mut hits = vec![None; total_pixels];
+        let mut history = vec![0u16; total_pixels];
+
+        for resolved in tiles {
+            let tile = &resolved.tile;
+            for (local_index, sample) in resolved.samples.iter().enumerate() {
+                let x = tile.x + local_index as u32 % tile.width;
+                let y = tile.y + local_index as u32 / tile.width;
+                let index = (y * self.width + x) as usize;
+                colors[index] = sample.color;
+                hits[index] = sample.hit;
+                history[index] = resolved.history[local_index];
+            }
+        }
+
+        self.colors = colors;
+        self.hits = hits;
+        self.history = history;
+        self.previous_camera = Some(camera.clone());
+        self.frame_index = self.frame_index.wrapping_add(1);
+    }
+}
+
+impl Default for TemporalAccumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Proyecta el punto (o la dirección, si es cielo) en la vista anterior y devuelve el píxel
+fn reproject(sample: &PixelSample, previous_camera: &Camera, width: u32, height: u32) -> Option<usize> {
+    let aspect_ratio = width as f32 / height as f32;
+    let (screen_x, screen_y) = match &sample.hit {
+        Some(hit) => previous_camera.project_point(hit.point, aspect_ratio),
+        None => previous_camera.project_direction(sample.direction, aspect_ratio),
+    }?;
+
+    let x = ((screen_x + 1.0) * width as f32 * 0.5).round();
+    let y = ((1.0 - screen_y) * height as f32 * 0.5).round();
+
+    if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+        return None;
+    }
+
+    Some(y as usize * width as usize + x as usize)
+}
+
+// Rechazo por desoclusión: compara posición y normal del impacto actual y el anterior
+fn same_surface(current: &Option<PrimaryHit>, previous: &Option<PrimaryHit>, eye: &Vector3) -> bool {
+    match (current, previous) {
+        (None, None) => true,
+        (Some(current), Some(previous)) => {
+            let distance_to_eye = (current.point - *eye).length();
+            let tolerance = DEPTH_TOLERANCE * distance_to_eye + 0.02;
+            (current.point - previous.point).length() <= tolerance
+                && current.normal.dot(previous.normal) >= NORMAL_TOLERANCE
+        }
+        _ => false,
+    }
+}
+
+fn neighborhood_bounds(colors: &[Vector3], index: usize, width: u32, height: u32) -> (Vector3, Vector3) {
+    let x = (index % width as usize) as i64;
+    let y = (index / width as usize) as i64;
+    let mut low = colors[index];
+    let mut high = colors[index];
+
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let nx = x + dx;
+            let ny = y + dy;
+            if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                continue;
+            }
+            let color = colors[ny as usize * width as usize + nx as usize];
+            low = low.min(color);
+            high = high.max(color);
+        }
+    }
+
+    (low, high)
+}
+
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0 / base as f32;
+    while index > 0 {
+        result += fraction * (index % base) as f32;
+        index /= base;
+        fraction /= base as f32;
+    }
+    result
+}
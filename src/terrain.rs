@@ -0,0 +1,403 @@
+use crate::material::Material;
+use crate::noise::Perlin;
+use crate::structures::Prefab;
+
+// Huella del diorama (x, z en -6..=6): el terreno la rodea sin pisarla
+const DIORAMA_HALF_SIZE: i32 = 6;
+// Bloques alrededor del diorama en los que el relieve se funde con su suelo
+const BLEND_DISTANCE: f32 = 8.0;
+// Frecuencias de cada ruido (por bloque)
+const RELIEF_FREQUENCY: f32 = 0.04;
+const BIOME_FREQUENCY: f32 = 0.012;
+const RIVER_FREQUENCY: f32 = 0.015;
+const CAVE_FREQUENCY: f32 = 0.09;
+// Cuánto sube la orilla por unidad de ruido al alejarse del cauce
+const RIVER_BANK_SLOPE: f32 = 40.0;
+// Por encima de este valor de ruido 3D la roca se vacía
+const CAVE_THRESHOLD: f32 = 0.28;
+// Bloques de tierra (o arena/grava) bajo la superficie antes de llegar a la roca
+const SOIL_DEPTH: i32 = 3;
+// Lado máximo del terreno: la rejilla completa de bloques se genera en memoria antes de quedarse con
+// los visibles
+pub const MAX_TERRAIN_SIZE: i32 = 2048;
+
+// Parámetros del terreno procedural que rodea el diorama
+pub struct TerrainSettings {
+    // Lado del cuadrado de terreno en bloques, centrado en el diorama
+    pub size: i32,
+    pub seed: u32,
+    // Altura del césped del diorama: el relieve se ajusta a ella en el borde
+    pub ground_level: i32,
+    // Variación aproximada del relieve por encima y por debajo del suelo
+    pub amplitude: f32,
+    pub water_level: i32,
+    pub snow_line: i32,
+    // Capa de bloques más baja
+    pub bottom: i32,
+    // Separación mínima entre árboles y arbustos; None deja el terreno sin vegetación
+    pub vegetation: Option<f32>,
+}
+
+impl TerrainSettings {
+    pub fn new(size: i32, seed: u32) -> Self {
+        TerrainSettings {
+            size,
+            seed,
+            ground_level: -2,
+            amplitude: 9.0,
+            water_level: -3,
+            snow_line: 5,
+            bottom: -9,
+            vegetation: None,
+        }
+    }
+}
+
+// Materiales de cada tipo de bloque del terreno
+pub struct TerrainMaterials {
+    pub grass: Material,
+    pub snow: Material,
+    pub gravel: Material,
+    pub sand: Material,
+    pub soil: Material,
+    pub rock: Material,
+    pub water: Material,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Block {
+    Grass,
+    Snow,
+    Gravel,
+    Sand,
+    Soil,
+    Rock,
+    Water,
+}
+
+// Lo que se decide por columna: altura del bloque superior y de qué está hecho
+struct Column {
+    height: i32,
+    surface: Block,
+    subsoil: Block,
+}
+
+// Terreno generado: sus bloques visibles y la superficie de cada columna para poder plantar encima
+pub struct Terrain {
+    pub blocks: Prefab,
+    columns: Vec<Option<Column>>,
+    size: i32,
+    water_level: i32,
+}
+
+impl Terrain {
+    // Altura y tipo del bloque superior en (x, z); None fuera del terreno, en el diorama o bajo el agua
+    pub fn surface_at(&self, x: i32, z: i32) -> Option<(i32, Block)> {
+        let half = self.size / 2;
+        let range = -half..self.size - half;
+        if !range.contains(&x) || !range.contains(&z) {
+            return None;
+        }
+        self.columns[((x + half) * self.size + z + half) as usize]
+            .as_ref()
+            .filter(|column| column.height >= self.water_level)
+            .map(|column| (column.height, column.surface))
+    }
+
+    // Extremos del cuadrado de terreno en X y Z
+    pub fn extent(&self) -> (i32, i32) {
+        let half = self.size / 2;
+        (-half, self.size - half)
+    }
+}
+
+// Genera el terreno con el origen en el del diorama. Solo se emiten los bloques que tocan
+// aire o agua: el interior macizo nunca se ve y solo engordaría la escena. Falla si la rejilla no
+// se puede indexar
+pub fn generate_terrain(settings: &TerrainSettings, materials: TerrainMaterials) -> Result<Terrain, String> {
+    let generator = Generator::new(settings);
+    let half = settings.size / 2;
+    let x_range = -half..settings.size - half;
+
+    let columns: Vec<Option<Column>> = x_range
+        .clone()
+        .flat_map(|x| x_range.clone().map(move |z| (x, z)))
+        .map(|(x, z)| generator.column(x, z))
+        .collect();
+    let top = columns
+        .iter()
+        .flatten()
+        .map(|column| column.height)
+        .max()
+        .unwrap_or(settings.ground_level)
+        .max(settings.water_level);
+    let height = top - settings.bottom + 1;
+
+    // Rejilla completa de bloques (x, y, z) con las cuevas ya excavadas
+    let (size, height) = (settings.size as usize, height as usize);
+    let volume = size
+        .checked_mul(size)
+        .and_then(|area| area.checked_mul(height))
+        .ok_or_else(|| format!("El terreno de {}x{} bloques es demasiado grande", settings.size, settings.size))?;
+    let index = |x: i32, y: i32, z: i32| {
+        ((x + half) as usize * size + (z + half) as usize) * height + (y - settings.bottom) as usize
+    };
+    let mut blocks = vec![None; volume];
+    for x in x_range.clone() {
+        for z in x_range.clone() {
+            let Some(column) = &columns[(x + half) as usize * size + (z + half) as usize] else {
+                continue;
+            };
+            for y in settings.bottom..=top {
+                blocks[index(x, y, z)] = generator.block(column, x, y, z);
+            }
+        }
+    }
+
+    // Fuera de la rejilla y en la huella del diorama hay aire; por debajo del fondo, roca
+    let block_at = |x: i32, y: i32, z: i32| {
+        if y < settings.bottom {
+            Some(Block::Rock)
+        } else if !x_range.contains(&x) || !x_range.contains(&z) || y > top {
+            None
+        } else {
+            blocks[index(x, y, z)]
+        }
+    };
+
+    let mut prefab = Prefab::new();
+    let grass = prefab.slot("césped", materials.grass);
+    let snow = prefab.slot("nieve", materials.snow);
+    let gravel = prefab.slot("grava", materials.gravel);
+    let sand = prefab.slot("arena", materials.sand);
+    let soil = prefab.slot("tierra", materials.soil);
+    let rock = prefab.slot("roca", materials.rock);
+    let water = prefab.slot("agua", materials.water);
+
+    for x in x_range.clone() {
+        for z in x_range.clone() {
+            for y in settings.bottom..=top {
+                let Some(block) = block_at(x, y, z) else {
+                    continue;
+                };
+                let visible = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)]
+                    .into_iter()
+                    .map(|(dx, dy, dz)| block_at(x + dx, y + dy, z + dz))
+                    .any(|neighbour| match neighbour {
+                        None => true,
+                        Some(Block::Water) => block != Block::Water,
+                        Some(_) => false,
+                    });
+                if !visible {
+                    continue;
+                }
+                let slot = match block {
+                    Block::Grass => grass,
+                    Block::Snow => snow,
+                    Block::Gravel => gravel,
+                    Block::Sand => sand,
+                    Block::Soil => soil,
+                    Block::Rock => rock,
+                    Block::Water => water,
+                };
+                prefab.block((x, y, z), slot);
+            }
+        }
+    }
+    Ok(Terrain {
+        blocks: prefab,
+        columns,
+        size: settings.size,
+        water_level: settings.water_level,
+    })
+}
+
+// Ruidos independientes, todos derivados de la misma semilla
+struct Generator<'a> {
+    settings: &'a TerrainSettings,
+    relief: Perlin,
+    temperature: Perlin,
+    moisture: Perlin,
+    rivers: Perlin,
+    caves: Perlin,
+}
+
+impl<'a> Generator<'a> {
+    fn new(settings: &'a TerrainSettings) -> Self {
+        let seed = settings.seed;
+        Generator {
+            settings,
+            relief: Perlin::new(seed),
+            temperature: Perlin::new(seed.wrapping_add(1)),
+            moisture: Perlin::new(seed.wrapping_add(2)),
+            rivers: Perlin::new(seed.wrapping_add(3)),
+            caves: Perlin::new(seed.wrapping_add(4)),
+        }
+    }
+
+    // Altura y bioma de la columna (x, z); None dentro de la huella del diorama
+    fn column(&self, x: i32, z: i32) -> Option<Column> {
+        let settings = self.settings;
+        let distance = x.abs().max(z.abs()) - DIORAMA_HALF_SIZE;
+        if distance <= 0 {
+            return None;
+        }
+        let (fx, fz) = (x as f32, z as f32);
+        let ground = settings.ground_level as f32;
+        let water_level = settings.water_level as f32;
+
+        // Relieve fractal algo por encima del suelo para que predomine la tierra sobre los lagos;
+        // los ríos cavan un valle en V alrededor de la línea donde su ruido vale cero
+        let relief = ground
+            + settings.amplitude * (0.35 + 1.8 * self.relief.fbm2(fx * RELIEF_FREQUENCY, fz * RELIEF_FREQUENCY, 5));
+        let river_distance = self.rivers.fbm2(fx * RIVER_FREQUENCY, fz * RIVER_FREQUENCY, 2).abs();
+        let river_bank = water_level - 2.0 + river_distance * RIVER_BANK_SLOPE;
+        let in_river = river_bank < relief;
+
+        // Junto al diorama el terreno se funde suavemente con su suelo
+        let blend = smoothstep(distance as f32 / BLEND_DISTANCE);
+        let natural = relief.min(river_bank);
+        let height = (ground + (natural - ground) * blend).round() as i32;
+        let height = height.max(settings.bottom + 1);
+
+        let cold = self.temperature.fbm2(fx * BIOME_FREQUENCY, fz * BIOME_FREQUENCY, 2) < -0.25;
+        let dry = self.moisture.fbm2(fx * BIOME_FREQUENCY * 2.0, fz * BIOME_FREQUENCY * 2.0, 3) < -0.2;
+        let surface = if height < settings.water_level {
+            if in_river { Block::Gravel } else { Block::Sand }
+        } else if height >= settings.snow_line || cold {
+            Block::Snow
+        } else if height <= settings.water_level + 1 {
+            Block::Sand
+        } else if dry {
+            Block::Gravel
+        } else {
+            Block::Grass
+        };
+        let subsoil = match surface {
+            Block::Sand | Block::Gravel => surface,
+            _ => Block::Soil,
+        };
+        Some(Column { height, surface, subsoil })
+    }
+
+    fn block(&self, column: &Column, x: i32, y: i32, z: i32) -> Option<Block> {
+        let settings = self.settings;
+        if y > column.height {
+            return (y <= settings.water_level).then_some(Block::Water);
+        }
+
+        // Las cuevas dejan intacta la corteza y no pasan bajo el agua para no inundarse
+        let cave = y > settings.bottom
+            && y < column.height - SOIL_DEPTH
+            && column.height > settings.water_level + 1
+            && self.caves.noise3(x as f32 * CAVE_FREQUENCY, y as f32 * CAVE_FREQUENCY * 1.5, z as f32 * CAVE_FREQUENCY)
+                > CAVE_THRESHOLD;
+        if cave {
+            None
+        } else if y == column.height {
+            Some(column.surface)
+        } else if y >= column.height - SOIL_DEPTH {
+            Some(column.subsoil)
+        } else {
+            Some(Block::Rock)
+        }
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::structures::Placement;
+    use crate::test_support::plain;
+
+    // Cada tipo de bloque con un rojo distinto para reconocerlo en los cubos
+    fn materials() -> TerrainMaterials {
+        TerrainMaterials {
+            grass: plain(0.1, 0.0, 0.0),
+            snow: plain(0.2, 0.0, 0.0),
+            gravel: plain(0.3, 0.0, 0.0),
+            sand: plain(0.4, 0.0, 0.0),
+            soil: plain(0.5, 0.0, 0.0),
+            rock: plain(0.6, 0.0, 0.0),
+            water: plain(0.7, 0.0, 0.0),
+        }
+    }
+
+    fn terrain_cubes(terrain: &Terrain) -> Vec<Cube> {
+        let mut cubes = Vec::new();
+        terrain.blocks.place(&Placement::default(), &mut cubes);
+        cubes
+    }
+
+    fn block_list(cubes: &[Cube]) -> Vec<((f32, f32, f32), f32)> {
+        cubes
+            .iter()
+            .map(|cube| ((cube.center.x, cube.center.y, cube.center.z), cube.material.diffuse.x))
+            .collect()
+    }
+
+    #[test]
+    fn leaves_the_diorama_footprint_empty() {
+        let terrain = generate_terrain(&TerrainSettings::new(40, 7), materials()).unwrap();
+        let cubes = terrain_cubes(&terrain);
+        assert!(!cubes.is_empty());
+        for cube in &cubes {
+            let (x, z) = (cube.center.x as i32, cube.center.z as i32);
+            assert!(x.abs() > DIORAMA_HALF_SIZE || z.abs() > DIORAMA_HALF_SIZE, "bloque en ({}, {})", x, z);
+        }
+        for x in -DIORAMA_HALF_SIZE..=DIORAMA_HALF_SIZE {
+            for z in -DIORAMA_HALF_SIZE..=DIORAMA_HALF_SIZE {
+                assert_eq!(terrain.surface_at(x, z), None);
+            }
+        }
+        // El anillo justo fuera de la huella sí tiene terreno
+        let edge = DIORAMA_HALF_SIZE + 1;
+        assert!(cubes.iter().any(|cube| cube.center.x as i32 == edge && cube.center.z == 0.0));
+
+        // Con el lado mínimo todo es huella y no queda ningún bloque
+        let footprint_only = generate_terrain(&TerrainSettings::new(2 * DIORAMA_HALF_SIZE + 1, 7), materials()).unwrap();
+        assert!(terrain_cubes(&footprint_only).is_empty());
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_terrain() {
+        let mut settings = TerrainSettings::new(48, 42);
+        let first = block_list(&terrain_cubes(&generate_terrain(&settings, materials()).unwrap()));
+        let second = block_list(&terrain_cubes(&generate_terrain(&settings, materials()).unwrap()));
+        assert_eq!(first, second);
+
+        settings.seed = 43;
+        let other = block_list(&terrain_cubes(&generate_terrain(&settings, materials()).unwrap()));
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn surface_matches_the_top_block_of_each_column() {
+        let terrain = generate_terrain(&TerrainSettings::new(32, 3), materials()).unwrap();
+        let cubes = terrain_cubes(&terrain);
+        let (min, max) = terrain.extent();
+        assert_eq!((min, max), (-16, 16));
+        assert_eq!(terrain.surface_at(max, 10), None);
+        for x in min..max {
+            for z in min..max {
+                let Some((height, block)) = terrain.surface_at(x, z) else {
+                    continue;
+                };
+                let top = cubes
+                    .iter()
+                    .filter(|cube| cube.center.x as i32 == x && cube.center.z as i32 == z)
+                    .max_by(|a, b| a.center.y.total_cmp(&b.center.y))
+                    .unwrap();
+                assert_eq!(top.center.y as i32, height);
+                assert_ne!(block, Block::Water);
+                assert_ne!(top.material.diffuse.x, 0.7);
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use raylib::prelude::Vector3;
+
+use crate::material::Material;
+use crate::nbt::Tag;
+use crate::ray_intersect::Intersect;
+
+// Material difuso opaco, sin textura ni emisión, para las pruebas
+pub fn plain(r: f32, g: f32, b: f32) -> Material {
+    Material::new(Vector3::new(r, g, b), 10.0, [0.9, 0.1, 0.0, 0.0], 0.0, None)
+}
+
+// Comprueba que el rayo toca a `distance` con esa normal (unitaria)
+pub fn assert_hit(hit: &Intersect, distance: f32, normal: Vector3) {
+    assert!(hit.is_intersecting, "el rayo no toca nada");
+    assert!((hit.distance - distance).abs() < 1e-4, "distancia {} en vez de {}", hit.distance, distance);
+    assert!((hit.normal - normal).length() < 1e-4, "normal {:?} en vez de {:?}", hit.normal, normal);
+}
+
+// Codifica una raíz NBT sin comprimir (nombre vacío), para montar esquemas en memoria
+pub fn nbt_bytes(root: &Tag) -> Vec<u8> {
+    let mut bytes = vec![nbt_kind(root), 0, 0];
+    write_nbt_payload(root, &mut bytes);
+    bytes
+}
+
+fn nbt_kind(tag: &Tag) -> u8 {
+    match tag {
+        Tag::Byte(_) => 1,
+        Tag::Short(_) => 2,
+        Tag::Int(_) => 3,
+        Tag::Long(_) => 4,
+        Tag::Float(_) => 5,
+        Tag::Double(_) => 6,
+        Tag::ByteArray(_) => 7,
+        Tag::String(_) => 8,
+        Tag::List(_) => 9,
+        Tag::Compound(_) => 10,
+        Tag::IntArray(_) => 11,
+        Tag::LongArray(_) => 12,
+    }
+}
+
+fn write_nbt_payload(tag: &Tag, bytes: &mut Vec<u8>) {
+    let string = |text: &str, bytes: &mut Vec<u8>| {
+        bytes.extend((text.len() as u16).to_be_bytes());
+        bytes.extend(text.as_bytes());
+    };
+    match tag {
+        Tag::Byte(value) => bytes.push(*value as u8),
+        Tag::Short(value) => bytes.extend(value.to_be_bytes()),
+        Tag::Int(value) => bytes.extend(value.to_be_bytes()),
+        Tag::Long(value) => bytes.extend(value.to_be_bytes()),
+        Tag::Float(value) => bytes.extend(value.to_be_bytes()),
+        Tag::Double(value) => bytes.extend(value.to_be_bytes()),
+        Tag::ByteArray(values) => {
+            bytes.extend((values.len() as i32).to_be_bytes());
+            bytes.extend(values.iter().map(|&value| value as u8));
+        }
+        Tag::String(value) => string(value, bytes),
+        Tag::List(values) => {
+            bytes.push(values.first().map_or(0, nbt_kind));
+            bytes.extend((values.len() as i32).to_be_bytes());
+            for value in values {
+                write_nbt_payload(value, bytes);
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, value) in entries {
+                bytes.push(nbt_kind(value));
+                string(name, bytes);
+                write_nbt_payload(value, bytes);
+            }
+            bytes.push(0);
+        }
+        Tag::IntArray(values) => {
+            bytes.extend((values.len() as i32).to_be_bytes());
+            bytes.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+        }
+        Tag::LongArray(values) => {
+            bytes.extend((values.len() as i32).to_be_bytes());
+            bytes.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+        }
+    }
+}
+
+// Compuesto NBT a partir de sus entradas
+pub fn compound(entries: Vec<(&str, Tag)>) -> Tag {
+    Tag::Compound(entries.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+}
@@ -30,6 +30,7 @@ impl CpuTexture {
     }
 }
 
+#[derive(Default)]
 pub struct TextureManager {
     cpu_textures: HashMap<String, CpuTexture>,
     textures: HashMap<String, Texture2D>, 
@@ -63,6 +64,32 @@ impl TextureManager {
         self.textures.insert(path.to_string(), texture);
     }
 
+    // Solo la copia en CPU, para renderizar sin ventana (no hay contexto de GPU)
+    pub fn load_cpu_texture(&mut self, path: &str) {
+        if self.cpu_textures.contains_key(path) {
+            return;
+        }
+
+        let image = Image::load_image(path)
+            .unwrap_or_else(|_| panic!("Failed to load image {}", path));
+
+        self.cpu_textures.insert(path.to_string(), CpuTexture::from_image(&image));
+    }
+
+    // Imagen ya codificada (PNG, JPG...) que está en memoria, p. ej. embebida en un .glb.
+    // `file_type` es la extensión con punto (".png") y `key` el nombre con el que la pedirán los materiales
+    pub fn load_cpu_texture_from_memory(&mut self, key: &str, file_type: &str, bytes: &[u8]) -> Result<(), String> {
+        if self.cpu_textures.contains_key(key) {
+            return Ok(());
+        }
+
+        let image = Image::load_image_from_mem(file_type, bytes)
+            .map_err(|error| format!("No se pudo decodificar la imagen {}: {}", key, error))?;
+
+        self.cpu_textures.insert(key.to_string(), CpuTexture::from_image(&image));
+        Ok(())
+    }
+
     #[inline]
     pub fn get_pixel_color(
         &self,
@@ -91,6 +118,14 @@ impl TextureManager {
         }
     }
 
+    // Color medio de la textura, p. ej. para darle un color plano al exportar
+    pub fn average_color(&self, path: &str) -> Option<Vector3> {
+        let cpu_texture = self.cpu_textures.get(path)?;
+        let count = cpu_texture.pixels.len().max(1) as f32;
+        let sum = cpu_texture.pixels.iter().fold(Vec3::default(), |sum, &pixel| sum + pixel);
+        Some((sum / count).to_vector3())
+    }
+
     pub fn get_texture(
         &self,
         path: &str,
@@ -98,12 +133,3 @@ impl TextureManager {
         self.textures.get(path)
     }
 }
-
-impl Default for TextureManager {
-    fn default() -> Self {
-        TextureManager {
-            cpu_textures: HashMap::new(),
-            textures: HashMap::new(),
-        }
-    }
-}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Lado de cada tile en píxeles
+pub const TILE_SIZE: u32 = 32;
+
+// Bandera compartida para abortar un frame en curso (por ejemplo, si la cámara se movió)
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Región rectangular de la imagen que se calcula como una unidad de trabajo
+#[derive(Clone, Copy, Debug)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+}
+
+// Divide la imagen en tiles, ordenados desde el centro hacia afuera para que
+// lo primero que aparezca en pantalla sea la zona que normalmente se está mirando
+pub fn split_into_tiles(width: u32, height: u32) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(TILE_SIZE as usize) {
+        for x in (0..width).step_by(TILE_SIZE as usize) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: TILE_SIZE.min(width - x),
+                height: TILE_SIZE.min(height - y),
+            });
+        }
+    }
+
+    let center_x = width as f32 * 0.5;
+    let center_y = height as f32 * 0.5;
+    let distance_to_center = |tile: &Tile| {
+        let dx = tile.x as f32 + tile.width as f32 * 0.5 - center_x;
+        let dy = tile.y as f32 + tile.height as f32 * 0.5 - center_y;
+        dx * dx + dy * dy
+    };
+    tiles.sort_by(|a, b| {
+        distance_to_center(a)
+            .partial_cmp(&distance_to_center(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    tiles
+}
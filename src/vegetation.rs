@@ -0,0 +1,353 @@
+use raylib::prelude::Vector3;
+use std::collections::BTreeMap;
+use std::f32::consts::{SQRT_2, TAU};
+
+use crate::cube::Cube;
+use crate::material::Material;
+use crate::noise::{Perlin, Rng};
+use crate::structures::{BlockPos, Placement, Prefab};
+use crate::terrain::{Block, Terrain};
+
+// Distancia al centro (en bloques, la mayor de |x| y |z|) por debajo de la cual no se planta:
+// deja libre el diorama y el hueco que ocupan las copas
+const DIORAMA_CLEARANCE: i32 = 10;
+// Intentos alrededor de cada punto activo del muestreo de Poisson antes de descartarlo
+const POISSON_ATTEMPTS: u32 = 30;
+// Frecuencia del ruido que reparte bosques de roble y de abedul
+const FOREST_FREQUENCY: f32 = 0.05;
+// Ranuras de material de los prefabs de vegetación
+const LOG: usize = 0;
+const LEAVES: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Species {
+    Oak,
+    Birch,
+    Spruce,
+    Bamboo,
+}
+
+// Forma de un árbol procedural
+#[derive(Clone, Copy, Debug)]
+pub struct TreeParams {
+    pub species: Species,
+    pub height: i32,
+    pub canopy_radius: f32,
+    // Ramas del roble y el abedul, pisos de ramas del abeto o cañas de la mata de bambú
+    pub branches: u32,
+}
+
+impl TreeParams {
+    // Valores típicos de cada especie, con algo de variación para que no salgan todos iguales
+    pub fn preset(species: Species, rng: &mut Rng) -> Self {
+        let (height, canopy_radius, branches) = match species {
+            Species::Oak => (rng.range_i32(6, 8), rng.range(2.6, 3.4), rng.range_i32(4, 5)),
+            Species::Birch => (rng.range_i32(7, 10), rng.range(1.8, 2.3), rng.range_i32(2, 3)),
+            Species::Spruce => (rng.range_i32(8, 12), rng.range(2.6, 3.2), rng.range_i32(4, 5)),
+            Species::Bamboo => (rng.range_i32(6, 10), 1.0, rng.range_i32(2, 4)),
+        };
+        TreeParams { species, height, canopy_radius, branches: branches as u32 }
+    }
+}
+
+// Tronco y hojas de cada especie y hojas de los arbustos
+pub struct VegetationMaterials {
+    pub oak_log: Material,
+    pub oak_leaves: Material,
+    pub birch_log: Material,
+    pub birch_leaves: Material,
+    pub spruce_log: Material,
+    pub spruce_leaves: Material,
+    pub bamboo: Material,
+    pub bamboo_leaves: Material,
+    pub bush: Material,
+}
+
+impl VegetationMaterials {
+    fn for_species(&self, species: Species) -> (Material, Material) {
+        let (log, leaves) = match species {
+            Species::Oak => (&self.oak_log, &self.oak_leaves),
+            Species::Birch => (&self.birch_log, &self.birch_leaves),
+            Species::Spruce => (&self.spruce_log, &self.spruce_leaves),
+            Species::Bamboo => (&self.bamboo, &self.bamboo_leaves),
+        };
+        (log.clone(), leaves.clone())
+    }
+}
+
+// Genera un árbol con la base del tronco en el origen
+pub fn generate_tree(params: &TreeParams, rng: &mut Rng, log_material: Material, leaf_material: Material) -> Prefab {
+    let mut tree = TreeBuilder::default();
+    let height = params.height as f32;
+    let top = Vector3::new(0.0, height - 1.0, 0.0);
+
+    match params.species {
+        Species::Oak => {
+            tree.branch(Vector3::zero(), top);
+            for i in 0..params.branches {
+                let azimuth = i as f32 / params.branches as f32 * TAU + rng.range(-0.3, 0.3);
+                let start = Vector3::new(0.0, rng.range(height * 0.5, height * 0.8), 0.0);
+                let direction = Vector3::new(azimuth.cos(), rng.range(0.5, 0.9), azimuth.sin()).normalized();
+                tree.grow_branch(start, direction, params.canopy_radius * 0.6, 1, 1.6, rng);
+            }
+            tree.canopy(top + Vector3::new(0.0, 1.0, 0.0), params.canopy_radius * 0.8, params.canopy_radius * 0.6, rng);
+        }
+        Species::Birch => {
+            tree.branch(Vector3::zero(), top);
+            for i in 0..params.branches {
+                let azimuth = i as f32 / params.branches as f32 * TAU + rng.range(-0.5, 0.5);
+                let start = Vector3::new(0.0, rng.range(height * 0.55, height * 0.75), 0.0);
+                let direction = Vector3::new(azimuth.cos(), rng.range(0.6, 1.0), azimuth.sin()).normalized();
+                tree.grow_branch(start, direction, params.canopy_radius * 0.7, 0, 1.3, rng);
+            }
+            tree.canopy(top, params.canopy_radius, params.canopy_radius * 1.4, rng);
+        }
+        Species::Spruce => {
+            // Pisos de ramas cada vez más cortos hacia la punta
+            tree.branch(Vector3::zero(), top);
+            for i in 0..params.branches {
+                let t = i as f32 / params.branches as f32;
+                let y = 2.0 + t * (height - 4.0);
+                tree.canopy(Vector3::new(0.0, y, 0.0), params.canopy_radius * (1.0 - t * 0.75), 0.6, rng);
+            }
+            tree.canopy(top, 1.2, 1.0, rng);
+            tree.leaves((0, params.height, 0));
+        }
+        Species::Bamboo => {
+            // Mata de cañas juntas, cada una con su penacho de hojas
+            for &(x, z) in [(0, 0), (1, 0), (0, 1), (-1, 0), (0, -1)].iter().take(params.branches as usize) {
+                let stalk_height = params.height + rng.range_i32(-1, 1);
+                let stalk_top = Vector3::new(x as f32, stalk_height as f32 - 1.0, z as f32);
+                tree.branch(Vector3::new(x as f32, 0.0, z as f32), stalk_top);
+                tree.canopy(stalk_top, params.canopy_radius + 0.4, params.canopy_radius, rng);
+            }
+        }
+    }
+    tree.into_prefab(log_material, leaf_material)
+}
+
+// Arbusto: un tocón con una mata de hojas alrededor
+pub fn generate_bush(rng: &mut Rng, log_material: Material, leaf_material: Material) -> Prefab {
+    let mut bush = TreeBuilder::default();
+    bush.log((0, 0, 0));
+    bush.canopy(Vector3::new(0.0, 0.0, 0.0), rng.range(1.2, 1.8), 1.3, rng);
+    bush.into_prefab(log_material, leaf_material)
+}
+
+// Muestreo de disco de Poisson (Bridson): puntos al azar en el cuadrado [min, max)² que nunca
+// quedan a menos de `spacing` unos de otros
+pub fn poisson_disc(min: f32, max: f32, spacing: f32, rng: &mut Rng) -> Vec<(f32, f32)> {
+    let cell = spacing / SQRT_2;
+    let cells = ((max - min) / cell).ceil() as usize;
+    let cell_of = |(x, z): (f32, f32)| (((x - min) / cell) as usize, ((z - min) / cell) as usize);
+    let mut grid: Vec<Option<usize>> = vec![None; cells * cells];
+    let mut points = vec![(rng.range(min, max), rng.range(min, max))];
+    let mut active = vec![0];
+    let (x, z) = cell_of(points[0]);
+    grid[x * cells + z] = Some(0);
+
+    while !active.is_empty() {
+        let slot = rng.next_u32() as usize % active.len();
+        let center = points[active[slot]];
+        let mut found = false;
+
+        for _ in 0..POISSON_ATTEMPTS {
+            let angle = rng.range(0.0, TAU);
+            let distance = rng.range(spacing, spacing * 2.0);
+            let candidate = (center.0 + angle.cos() * distance, center.1 + angle.sin() * distance);
+            if !(min..max).contains(&candidate.0) || !(min..max).contains(&candidate.1) {
+                continue;
+            }
+
+            let (cx, cz) = cell_of(candidate);
+            let too_close = (cx.saturating_sub(2)..=(cx + 2).min(cells - 1)).any(|x| {
+                (cz.saturating_sub(2)..=(cz + 2).min(cells - 1)).any(|z| {
+                    grid[x * cells + z].is_some_and(|index| {
+                        let (px, pz) = points[index];
+                        (px - candidate.0).powi(2) + (pz - candidate.1).powi(2) < spacing * spacing
+                    })
+                })
+            });
+            if !too_close {
+                grid[cx * cells + cz] = Some(points.len());
+                active.push(points.len());
+                points.push(candidate);
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            active.swap_remove(slot);
+        }
+    }
+    points
+}
+
+// Planta árboles y arbustos sobre el terreno en puntos de Poisson. La especie depende de la
+// superficie (abetos en la nieve, bambú en la arena, robles o abedules según la zona en el césped)
+// y cada planta sale con su propia forma y giro. Devuelve cuántas se plantaron
+pub fn scatter_vegetation(
+    terrain: &Terrain,
+    spacing: f32,
+    seed: u32,
+    materials: &VegetationMaterials,
+    objects: &mut Vec<Cube>,
+) -> usize {
+    let mut rng = Rng::new(seed.wrapping_add(5));
+    let forests = Perlin::new(seed.wrapping_add(6));
+    let (min, max) = terrain.extent();
+    let mut planted = 0;
+
+    for (x, z) in poisson_disc(min as f32, max as f32, spacing, &mut rng) {
+        let (x, z) = (x.floor() as i32, z.floor() as i32);
+        if x.abs().max(z.abs()) < DIORAMA_CLEARANCE {
+            continue;
+        }
+        let Some((height, surface)) = terrain.surface_at(x, z) else {
+            continue;
+        };
+
+        let roll = rng.next_f32();
+        let birch_forest = forests.noise2(x as f32 * FOREST_FREQUENCY, z as f32 * FOREST_FREQUENCY) > 0.1;
+        let species = match surface {
+            Block::Snow if roll < 0.8 => Some(Species::Spruce),
+            Block::Grass if roll >= 0.25 => Some(if birch_forest { Species::Birch } else { Species::Oak }),
+            Block::Sand if roll < 0.4 => Some(Species::Bamboo),
+            _ => None,
+        };
+        let bush = matches!(surface, Block::Grass | Block::Gravel) && roll < 0.25;
+
+        let prefab = match species {
+            Some(species) => {
+                let (log, leaves) = materials.for_species(species);
+                generate_tree(&TreeParams::preset(species, &mut rng), &mut rng, log, leaves)
+            }
+            None if bush => generate_bush(&mut rng, materials.oak_log.clone(), materials.bush.clone()),
+            None => continue,
+        };
+        let placement = Placement::at(x, height + 1, z).rotated((rng.next_u32() % 4) as u8);
+        prefab.place(&placement, objects);
+        planted += 1;
+    }
+    planted
+}
+
+// Bloques de una planta antes de volcarlos a un prefab; la madera tiene prioridad sobre las hojas
+#[derive(Default)]
+struct TreeBuilder {
+    blocks: BTreeMap<BlockPos, usize>,
+}
+
+impl TreeBuilder {
+    fn log(&mut self, position: BlockPos) {
+        self.blocks.insert(position, LOG);
+    }
+
+    // Las hojas nunca bajan del suelo
+    fn leaves(&mut self, position: BlockPos) {
+        if position.1 >= 0 {
+            self.blocks.entry(position).or_insert(LEAVES);
+        }
+    }
+
+    // Tramo recto de madera entre dos puntos
+    fn branch(&mut self, from: Vector3, to: Vector3) {
+        let steps = (to - from).length().ceil().max(1.0) as i32;
+        for i in 0..=steps {
+            self.log(voxel(from + (to - from) * (i as f32 / steps as f32)));
+        }
+    }
+
+    // Regla F → F[+F][-F] de un sistema L: cada rama acaba en dos ramas más cortas abiertas a los
+    // lados, y las últimas en una bola de hojas
+    fn grow_branch(&mut self, start: Vector3, direction: Vector3, length: f32, depth: u32, leaf_radius: f32, rng: &mut Rng) {
+        let end = start + direction * length;
+        self.branch(start, end);
+        if depth == 0 {
+            self.canopy(end, leaf_radius, leaf_radius * 0.75, rng);
+            return;
+        }
+        for side in [-1.0, 1.0] {
+            let child = turn(direction, side * rng.range(0.4, 0.8), rng.range(0.1, 0.3));
+            self.grow_branch(end, child, length * 0.65, depth - 1, leaf_radius, rng);
+        }
+    }
+
+    // Copa elipsoidal con el borde deshilachado al azar
+    fn canopy(&mut self, center: Vector3, radius: f32, vertical_radius: f32, rng: &mut Rng) {
+        let (cx, cy, cz) = voxel(center);
+        let (reach, vertical_reach) = (radius.ceil() as i32, vertical_radius.ceil() as i32);
+        for dy in -vertical_reach..=vertical_reach {
+            for dx in -reach..=reach {
+                for dz in -reach..=reach {
+                    let distance = (((dx * dx + dz * dz) as f32) / (radius * radius)
+                        + (dy * dy) as f32 / (vertical_radius * vertical_radius))
+                        .sqrt();
+                    if distance < 0.75 || (distance <= 1.0 && rng.next_f32() < 0.6) {
+                        self.leaves((cx + dx, cy + dy, cz + dz));
+                    }
+                }
+            }
+        }
+    }
+
+    fn into_prefab(self, log_material: Material, leaf_material: Material) -> Prefab {
+        let mut prefab = Prefab::new();
+        prefab.slot("tronco", log_material);
+        prefab.slot("hojas", leaf_material);
+        for (position, slot) in self.blocks {
+            prefab.block(position, slot);
+        }
+        prefab
+    }
+}
+
+fn voxel(point: Vector3) -> BlockPos {
+    (point.x.round() as i32, point.y.round() as i32, point.z.round() as i32)
+}
+
+// Gira la dirección alrededor de Y y la levanta un poco
+fn turn(direction: Vector3, yaw: f32, lift: f32) -> Vector3 {
+    let (sin, cos) = yaw.sin_cos();
+    Vector3::new(
+        direction.x * cos + direction.z * sin,
+        direction.y + lift,
+        -direction.x * sin + direction.z * cos,
+    )
+    .normalized()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poisson_points_keep_their_spacing() {
+        for (seed, spacing) in [(1, 3.0), (2, 4.5), (3, 7.0), (4, 1.5)] {
+            let points = poisson_disc(-40.0, 40.0, spacing, &mut Rng::new(seed));
+            for (index, a) in points.iter().enumerate() {
+                assert!((-40.0..40.0).contains(&a.0) && (-40.0..40.0).contains(&a.1), "{:?} fuera", a);
+                for b in &points[index + 1..] {
+                    let distance = ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+                    assert!(distance >= spacing, "{:?} y {:?} a {} < {}", a, b, distance, spacing);
+                }
+            }
+            // El muestreo llena el cuadrado: cada punto reserva menos de un disco de radio `spacing`
+            let area = 80.0 * 80.0;
+            assert!(points.len() as f32 > area / (std::f32::consts::PI * spacing * spacing), "solo {} puntos", points.len());
+        }
+    }
+
+    #[test]
+    fn poisson_points_are_deterministic_per_seed() {
+        let first = poisson_disc(0.0, 30.0, 2.0, &mut Rng::new(9));
+        assert_eq!(first, poisson_disc(0.0, 30.0, 2.0, &mut Rng::new(9)));
+        assert_ne!(first, poisson_disc(0.0, 30.0, 2.0, &mut Rng::new(10)));
+    }
+
+    #[test]
+    fn spacing_wider_than_the_square_leaves_one_point() {
+        assert_eq!(poisson_disc(0.0, 5.0, 20.0, &mut Rng::new(1)).len(), 1);
+    }
+}
@@ -0,0 +1,665 @@
+use raylib::prelude::Vector3;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::cube::{Cube, Vec3};
+use crate::material::Material;
+use crate::structures::BlockPos;
+use crate::textures::TextureManager;
+
+// Versión del formato que se escribe (la que guarda MagicaVoxel 0.99 con grafo de escena)
+const VOX_VERSION: i32 = 200;
+// Lado máximo de un modelo; las escenas más grandes se trocean en varios modelos
+const MAX_MODEL_SIZE: i32 = 256;
+
+// Lee un archivo MagicaVoxel `.vox`: todos sus modelos colocados según el grafo de escena (giros y
+// traslaciones de los nodos), con un material por color de la paleta. MagicaVoxel usa Z hacia
+// arriba: (x, y, z) pasa a (x, z, -y) y se desplaza `offset`
+pub fn load_vox(path: &str, offset: BlockPos) -> Result<Vec<Cube>, String> {
+    let bytes = std::fs::read(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+    let file = parse_vox(&bytes).map_err(|error| format!("{}: {}", path, error))?;
+    vox_cubes(&file, offset).map_err(|error| format!("{}: {}", path, error))
+}
+
+// Escribe los bloques en un `.vox`. Cada material distinto ocupa un color de la paleta (el medio de
+// su textura, o su difuso si no tiene) y su tipo (vidrio, metal o emisivo) va en un MATL.
+// Devuelve cuántos bloques se escribieron
+pub fn save_vox(path: &str, voxels: &[(BlockPos, &Material)], texture_manager: &TextureManager) -> Result<usize, String> {
+    let (bytes, written) = encode_vox(voxels, texture_manager)?;
+    std::fs::write(path, bytes).map_err(|error| format!("No se pudo escribir {}: {}", path, error))?;
+    Ok(written)
+}
+
+// Cubos de los modelos colocados de un `.vox` ya leído
+fn vox_cubes(file: &VoxFile, offset: BlockPos) -> Result<Vec<Cube>, String> {
+    let mut materials: Vec<Option<Material>> = vec![None; 256];
+    let mut cubes = Vec::new();
+    for (model, transform) in file.placed_models() {
+        let model = file
+            .models
+            .get(model)
+            .ok_or_else(|| format!("el grafo de escena usa el modelo {}, que no existe", model))?;
+        for &(position, color_index) in &model.voxels {
+            let (x, y, z) = transform.apply(model.size, position);
+            let material = materials[color_index as usize]
+                .get_or_insert_with(|| material_for(file, color_index))
+                .clone();
+            cubes.push(Cube::new(
+                Vec3::new((x + offset.0) as f32, (z + offset.1) as f32, (-y + offset.2) as f32),
+                Vec3::new(0.5, 0.5, 0.5),
+                material,
+            ));
+        }
+    }
+    Ok(cubes)
+}
+
+// Bytes del `.vox` de `save_vox` y cuántos bloques lleva
+fn encode_vox(voxels: &[(BlockPos, &Material)], texture_manager: &TextureManager) -> Result<(Vec<u8>, usize), String> {
+    if voxels.is_empty() {
+        return Err("No hay bloques que exportar".to_string());
+    }
+
+    // Un bloque por posición (si hay varios gana el último, como al pintar en MagicaVoxel),
+    // ya en coordenadas de MagicaVoxel
+    let mut palette: Vec<PaletteEntry> = Vec::new();
+    let mut palette_index: HashMap<PaletteEntry, u8> = HashMap::new();
+    let mut colored: BTreeMap<BlockPos, u8> = BTreeMap::new();
+    for &((x, y, z), material) in voxels {
+        let entry = PaletteEntry::from_material(material, texture_manager);
+        let index = match palette_index.get(&entry) {
+            Some(&index) => index,
+            None => {
+                if palette.len() == 255 {
+                    return Err("La escena tiene más de 255 materiales distintos".to_string());
+                }
+                palette.push(entry.clone());
+                palette_index.insert(entry, palette.len() as u8);
+                palette.len() as u8
+            }
+        };
+        colored.insert((x, -z, y), index);
+    }
+
+    // Trozos de MAX_MODEL_SIZE de lado: cada uno es un modelo con su nodo de traslación
+    let origin = colored.keys().fold((i32::MAX, i32::MAX, i32::MAX), |min, &(x, y, z)| {
+        (min.0.min(x), min.1.min(y), min.2.min(z))
+    });
+    let mut tiles: BTreeMap<BlockPos, Vec<(BlockPos, u8)>> = BTreeMap::new();
+    for (&position, &index) in &colored {
+        let tile = (
+            (position.0 - origin.0) / MAX_MODEL_SIZE,
+            (position.1 - origin.1) / MAX_MODEL_SIZE,
+            (position.2 - origin.2) / MAX_MODEL_SIZE,
+        );
+        tiles.entry(tile).or_default().push((position, index));
+    }
+    let models: Vec<(BlockPos, Model)> = tiles
+        .into_values()
+        .map(|voxels| {
+            let (mut min, mut max) = ((i32::MAX, i32::MAX, i32::MAX), (i32::MIN, i32::MIN, i32::MIN));
+            for &((x, y, z), _) in &voxels {
+                min = (min.0.min(x), min.1.min(y), min.2.min(z));
+                max = (max.0.max(x), max.1.max(y), max.2.max(z));
+            }
+            let size = (max.0 - min.0 + 1, max.1 - min.1 + 1, max.2 - min.2 + 1);
+            let voxels = voxels
+                .into_iter()
+                .map(|((x, y, z), index)| ((x - min.0, y - min.1, z - min.2), index))
+                .collect();
+            (min, Model { size, voxels })
+        })
+        .collect();
+
+    let mut children = Vec::new();
+    for (_, model) in &models {
+        children.extend(chunk(b"SIZE", &ints(&[model.size.0, model.size.1, model.size.2]), &[]));
+        let mut xyzi = ints(&[model.voxels.len() as i32]);
+        for &((x, y, z), index) in &model.voxels {
+            xyzi.extend([x as u8, y as u8, z as u8, index]);
+        }
+        children.extend(chunk(b"XYZI", &xyzi, &[]));
+    }
+
+    // Grafo: transformación raíz → grupo → (transformación → forma) por modelo
+    let transform_ids: Vec<i32> = (0..models.len() as i32).map(|model| 2 + model * 2).collect();
+    let mut root = ints(&[0]);
+    root.extend(dict(&[]));
+    root.extend(ints(&[1, -1, -1, 1]));
+    root.extend(dict(&[]));
+    children.extend(chunk(b"nTRN", &root, &[]));
+
+    let mut group = ints(&[1]);
+    group.extend(dict(&[]));
+    group.extend(ints(&[models.len() as i32]));
+    group.extend(ints(&transform_ids));
+    children.extend(chunk(b"nGRP", &group, &[]));
+
+    for (model, (min, Model { size, .. })) in models.iter().enumerate() {
+        // La lectura resta la mitad del tamaño; la traslación la compensa para conservar posiciones
+        let translation = format!("{} {} {}", min.0 + size.0 / 2, min.1 + size.1 / 2, min.2 + size.2 / 2);
+        let node = transform_ids[model];
+
+        let mut transform = ints(&[node]);
+        transform.extend(dict(&[]));
+        transform.extend(ints(&[node + 1, -1, -1, 1]));
+        transform.extend(dict(&[("_t", translation.as_str())]));
+        children.extend(chunk(b"nTRN", &transform, &[]));
+
+        let mut shape = ints(&[node + 1]);
+        shape.extend(dict(&[]));
+        shape.extend(ints(&[1, model as i32]));
+        shape.extend(dict(&[]));
+        children.extend(chunk(b"nSHP", &shape, &[]));
+    }
+
+    let mut rgba = Vec::with_capacity(256 * 4);
+    for index in 0..256 {
+        match palette.get(index) {
+            Some(entry) => rgba.extend([entry.color[0], entry.color[1], entry.color[2], 255]),
+            None => rgba.extend([0, 0, 0, 255]),
+        }
+    }
+    children.extend(chunk(b"RGBA", &rgba, &[]));
+
+    for (index, entry) in palette.iter().enumerate() {
+        if entry.properties.is_empty() {
+            continue;
+        }
+        let properties: Vec<(&str, &str)> =
+            entry.properties.iter().map(|(key, value)| (*key, value.as_str())).collect();
+        let mut matl = ints(&[index as i32 + 1]);
+        matl.extend(dict(&properties));
+        children.extend(chunk(b"MATL", &matl, &[]));
+    }
+
+    let mut bytes = b"VOX ".to_vec();
+    bytes.extend(ints(&[VOX_VERSION]));
+    bytes.extend(chunk(b"MAIN", &[], &children));
+    Ok((bytes, colored.len()))
+}
+
+// Color de la paleta y propiedades MATL de un material al exportarlo
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PaletteEntry {
+    color: [u8; 3],
+    properties: Vec<(&'static str, String)>,
+}
+
+impl PaletteEntry {
+    fn from_material(material: &Material, texture_manager: &TextureManager) -> Self {
+        let color = material
+            .texture_id
+            .as_deref()
+            .and_then(|texture| texture_manager.average_color(texture))
+            .unwrap_or(material.diffuse);
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        let properties = if material.emission_strength > 0.0 {
+            // `_emit` guarda hasta 1 y `_flux` lo que sobra, para que la lectura recupere la fuerza
+            let strength = material.emission_strength;
+            vec![
+                ("_type", "_emit".to_string()),
+                ("_emit", format!("{}", strength.min(1.0))),
+                ("_flux", format!("{}", (strength - 1.0).max(0.0))),
+            ]
+        } else if material.albedo[3] > 0.0 {
+            vec![
+                ("_type", "_glass".to_string()),
+                ("_trans", format!("{}", material.albedo[3])),
+                ("_ri", format!("{}", material.refractive_index)),
+                ("_ior", format!("{}", material.refractive_index - 1.0)),
+            ]
+        } else if material.albedo[2] > 0.0 {
+            vec![("_type", "_metal".to_string()), ("_metal", format!("{}", material.albedo[2]))]
+        } else {
+            Vec::new()
+        };
+
+        PaletteEntry {
+            color: [channel(color.x), channel(color.y), channel(color.z)],
+            properties,
+        }
+    }
+}
+
+// Material generado para un color de la paleta y su MATL, si lo tiene
+fn material_for(file: &VoxFile, color_index: u8) -> Material {
+    let [r, g, b, _] = file.palette[color_index as usize];
+    let color = Vector3::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let properties = file.materials.get(&color_index);
+    let property = |key: &str| {
+        properties
+            .and_then(|properties| properties.get(key))
+            .and_then(|value| value.parse::<f32>().ok())
+    };
+
+    match properties.and_then(|properties| properties.get("_type")).map(String::as_str) {
+        Some("_emit") => {
+            let strength = property("_emit").unwrap_or(1.0) * (1.0 + property("_flux").unwrap_or(0.0));
+            Material::new_emissive(color, 10.0, [1.0, 0.0, 0.0, 0.0], 0.0, None, color, strength)
+        }
+        Some("_glass") => {
+            let transparency = property("_trans").unwrap_or(0.5).clamp(0.0, 1.0);
+            let refractive_index = property("_ri").or(property("_ior").map(|ior| ior + 1.0)).unwrap_or(1.5);
+            Material::new(color, 80.0, [0.3, 0.2, 0.1, transparency], refractive_index, None)
+        }
+        Some("_metal") => {
+            let metal = property("_metal").unwrap_or(0.5).clamp(0.0, 1.0);
+            Material::new(color, 60.0, [0.9 - 0.5 * metal, 0.1 + 0.3 * metal, metal, 0.0], 0.0, None)
+        }
+        _ => Material::new(color, 10.0, [0.9, 0.1, 0.0, 0.0], 0.0, None),
+    }
+}
+
+// Contenido de un `.vox` ya leído
+struct VoxFile {
+    models: Vec<Model>,
+    // Índice 0 sin usar, como en el formato: los voxels apuntan a 1..=255
+    palette: [[u8; 4]; 256],
+    materials: HashMap<u8, HashMap<String, String>>,
+    nodes: HashMap<i32, Node>,
+    hidden_layers: Vec<i32>,
+}
+
+struct Model {
+    size: BlockPos,
+    voxels: Vec<(BlockPos, u8)>,
+}
+
+enum Node {
+    Transform { child: i32, layer: i32, hidden: bool, transform: VoxTransform },
+    Group { children: Vec<i32> },
+    Shape { models: Vec<usize> },
+}
+
+// Giro entero (matriz de permutación con signos) y traslación de un nodo
+#[derive(Clone, Copy)]
+struct VoxTransform {
+    rows: [[i32; 3]; 3],
+    translation: BlockPos,
+}
+
+impl VoxTransform {
+    const IDENTITY: VoxTransform = VoxTransform {
+        rows: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        translation: (0, 0, 0),
+    };
+
+    // Giro codificado en un byte: columna del 1 de las dos primeras filas y signo de cada fila
+    fn from_attributes(attributes: &HashMap<String, String>) -> Result<Self, String> {
+        let mut transform = VoxTransform::IDENTITY;
+        if let Some(rotation) = attributes.get("_r") {
+            let bits: u8 = rotation.trim().parse().map_err(|_| format!("giro no válido: {}", rotation))?;
+            let first = (bits & 3) as usize;
+            let second = ((bits >> 2) & 3) as usize;
+            if first > 2 || second > 2 || first == second {
+                return Err(format!("giro no válido: {}", rotation));
+            }
+            let columns = [first, second, 3 - first - second];
+            for (row, column) in columns.into_iter().enumerate() {
+                transform.rows[row] = [0; 3];
+                transform.rows[row][column] = if bits & (1 << (4 + row)) != 0 { -1 } else { 1 };
+            }
+        }
+        if let Some(translation) = attributes.get("_t") {
+            let values: Vec<i32> = translation
+                .split_whitespace()
+                .map(|value| value.parse().map_err(|_| format!("traslación no válida: {}", translation)))
+                .collect::<Result<_, _>>()?;
+            let [x, y, z] = values[..] else {
+                return Err(format!("traslación no válida: {}", translation));
+            };
+            transform.translation = (x, y, z);
+        }
+        Ok(transform)
+    }
+
+    fn rotate(&self, (x, y, z): (f32, f32, f32)) -> (f32, f32, f32) {
+        let row = |r: [i32; 3]| r[0] as f32 * x + r[1] as f32 * y + r[2] as f32 * z;
+        (row(self.rows[0]), row(self.rows[1]), row(self.rows[2]))
+    }
+
+    // Aplica primero `self` y después `outer`
+    fn then(&self, outer: &VoxTransform) -> VoxTransform {
+        let rows = outer
+            .rows
+            .map(|outer_row| std::array::from_fn(|column| (0..3).map(|k| outer_row[k] * self.rows[k][column]).sum()));
+        let (x, y, z) = outer.rotate((
+            self.translation.0 as f32,
+            self.translation.1 as f32,
+            self.translation.2 as f32,
+        ));
+        VoxTransform {
+            rows,
+            translation: (
+                x as i32 + outer.translation.0,
+                y as i32 + outer.translation.1,
+                z as i32 + outer.translation.2,
+            ),
+        }
+    }
+
+    // Voxel de un modelo a coordenadas de la escena: el giro es alrededor del centro del modelo
+    fn apply(&self, size: BlockPos, (x, y, z): BlockPos) -> BlockPos {
+        let (x, y, z) = self.rotate((
+            x as f32 + 0.5 - size.0 as f32 / 2.0,
+            y as f32 + 0.5 - size.1 as f32 / 2.0,
+            z as f32 + 0.5 - size.2 as f32 / 2.0,
+        ));
+        (
+            x.floor() as i32 + self.translation.0,
+            y.floor() as i32 + self.translation.1,
+            z.floor() as i32 + self.translation.2,
+        )
+    }
+}
+
+impl VoxFile {
+    // Modelos con su transformación acumulada. Sin grafo de escena (archivos antiguos) los modelos
+    // se dejan en su sitio, sin centrar
+    fn placed_models(&self) -> Vec<(usize, VoxTransform)> {
+        if self.nodes.is_empty() {
+            return (0..self.models.len())
+                .map(|model| {
+                    let size = self.models[model].size;
+                    let translation = (size.0 / 2, size.1 / 2, size.2 / 2);
+                    (model, VoxTransform { translation, ..VoxTransform::IDENTITY })
+                })
+                .collect();
+        }
+
+        let mut placed = Vec::new();
+        let mut pending = vec![(0, VoxTransform::IDENTITY)];
+        // Un grafo mal formado con ciclos no debe colgar la carga
+        let mut visits = 0;
+        while let Some((id, parent)) = pending.pop() {
+            visits += 1;
+            if visits > self.nodes.len() * 4 {
+                break;
+            }
+            match self.nodes.get(&id) {
+                Some(Node::Transform { child, layer, hidden, transform })
+                    if !hidden && !self.hidden_layers.contains(layer) =>
+                {
+                    pending.push((*child, transform.then(&parent)));
+                }
+                Some(Node::Group { children }) => pending.extend(children.iter().map(|&child| (child, parent))),
+                Some(Node::Shape { models }) => placed.extend(models.iter().map(|&model| (model, parent))),
+                _ => {}
+            }
+        }
+        placed
+    }
+}
+
+fn parse_vox(bytes: &[u8]) -> Result<VoxFile, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != b"VOX " {
+        return Err("no es un archivo MagicaVoxel (falta la cabecera VOX)".to_string());
+    }
+    let _version = reader.i32()?;
+    if reader.take(4)? != b"MAIN" {
+        return Err("falta el bloque MAIN".to_string());
+    }
+    let content_size = reader.i32()? as usize;
+    let _children_size = reader.i32()?;
+    reader.take(content_size)?;
+
+    let mut file = VoxFile {
+        models: Vec::new(),
+        palette: default_palette(),
+        materials: HashMap::new(),
+        nodes: HashMap::new(),
+        hidden_layers: Vec::new(),
+    };
+    let mut size = None;
+
+    while reader.position < bytes.len() {
+        let id: [u8; 4] = reader.take(4)?.try_into().unwrap();
+        let content_size = reader.i32()? as usize;
+        let children_size = reader.i32()? as usize;
+        let mut content = Reader { bytes: reader.take(content_size)?, position: 0 };
+        reader.take(children_size)?;
+
+        match &id {
+            b"SIZE" => size = Some((content.i32()?, content.i32()?, content.i32()?)),
+            b"XYZI" => {
+                let size = size.take().ok_or("bloque XYZI sin SIZE")?;
+                let count = content.i32()?;
+                let mut voxels = Vec::with_capacity(count.max(0) as usize);
+                for _ in 0..count {
+                    let voxel = content.take(4)?;
+                    voxels.push(((voxel[0] as i32, voxel[1] as i32, voxel[2] as i32), voxel[3]));
+                }
+                file.models.push(Model { size, voxels });
+            }
+            b"RGBA" => {
+                // El color i del bloque corresponde al índice i + 1
+                for index in 0..255 {
+                    let color = content.take(4)?;
+                    file.palette[index + 1] = [color[0], color[1], color[2], color[3]];
+                }
+            }
+            b"MATL" => {
+                let index = content.i32()?;
+                let properties = content.dict()?;
+                if (1..=255).contains(&index) {
+                    file.materials.insert(index as u8, properties);
+                }
+            }
+            b"nTRN" => {
+                let id = content.i32()?;
+                let attributes = content.dict()?;
+                let child = content.i32()?;
+                let _reserved = content.i32()?;
+                let layer = content.i32()?;
+                let frames = content.i32()?;
+                let transform = if frames > 0 {
+                    VoxTransform::from_attributes(&content.dict()?)?
+                } else {
+                    VoxTransform::IDENTITY
+                };
+                let hidden = attributes.get("_hidden").is_some_and(|value| value == "1");
+                file.nodes.insert(id, Node::Transform { child, layer, hidden, transform });
+            }
+            b"nGRP" => {
+                let id = content.i32()?;
+                content.dict()?;
+                let count = content.i32()?;
+                let children = (0..count).map(|_| content.i32()).collect::<Result<_, _>>()?;
+                file.nodes.insert(id, Node::Group { children });
+            }
+            b"nSHP" => {
+                let id = content.i32()?;
+                content.dict()?;
+                let count = content.i32()?;
+                let mut models = Vec::new();
+                for _ in 0..count {
+                    models.push(content.i32()? as usize);
+                    content.dict()?;
+                }
+                file.nodes.insert(id, Node::Shape { models });
+            }
+            b"LAYR" => {
+                let id = content.i32()?;
+                if content.dict()?.get("_hidden").is_some_and(|value| value == "1") {
+                    file.hidden_layers.push(id);
+                }
+            }
+            // PACK, IMAP, rOBJ, rCAM, NOTE...: no afectan a la geometría
+            _ => {}
+        }
+    }
+
+    Ok(file)
+}
+
+// Paleta por defecto de MagicaVoxel para archivos sin bloque RGBA: un cubo de 6×6×6 colores
+// (sin el negro) seguido de rampas de rojo, verde, azul y gris
+fn default_palette() -> [[u8; 4]; 256] {
+    let mut palette = [[0, 0, 0, 255]; 256];
+    let levels = [0xff, 0xcc, 0x99, 0x66, 0x33, 0x00];
+    let mut index = 1;
+    for r in levels {
+        for g in levels {
+            for b in levels {
+                if (r, g, b) != (0, 0, 0) {
+                    palette[index] = [r, g, b, 255];
+                    index += 1;
+                }
+            }
+        }
+    }
+    let ramp = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+    for channel in [2, 1, 0, 3] {
+        for value in ramp {
+            palette[index] = match channel {
+                3 => [value, value, value, 255],
+                _ => {
+                    let mut color = [0, 0, 0, 255];
+                    color[channel] = value;
+                    color
+                }
+            };
+            index += 1;
+        }
+    }
+    palette
+}
+
+// Lectura secuencial de enteros, cadenas y diccionarios en little endian
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or("el archivo está truncado")?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.i32()?.max(0) as usize;
+        Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+    }
+
+    fn dict(&mut self) -> Result<HashMap<String, String>, String> {
+        let count = self.i32()?;
+        (0..count).map(|_| Ok((self.string()?, self.string()?))).collect()
+    }
+}
+
+fn chunk(id: &[u8; 4], content: &[u8], children: &[u8]) -> Vec<u8> {
+    let mut bytes = id.to_vec();
+    bytes.extend((content.len() as i32).to_le_bytes());
+    bytes.extend((children.len() as i32).to_le_bytes());
+    bytes.extend(content);
+    bytes.extend(children);
+    bytes
+}
+
+fn ints(values: &[i32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn dict(entries: &[(&str, &str)]) -> Vec<u8> {
+    let mut bytes = ints(&[entries.len() as i32]);
+    for (key, value) in entries {
+        for text in [key, value] {
+            bytes.extend(ints(&[text.len() as i32]));
+            bytes.extend(text.as_bytes());
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::plain;
+
+    // Codifica los bloques, los vuelve a leer y devuelve los leídos por posición junto al archivo crudo
+    fn round_trip(blocks: &[(BlockPos, &Material)]) -> (BTreeMap<BlockPos, Material>, VoxFile) {
+        let (bytes, written) = encode_vox(blocks, &TextureManager::new()).unwrap();
+        let file = parse_vox(&bytes).unwrap();
+        let cubes = vox_cubes(&file, (0, 0, 0)).unwrap();
+
+        assert_eq!(written, blocks.len());
+        assert_eq!(cubes.len(), blocks.len());
+        let loaded = cubes
+            .into_iter()
+            .map(|cube| ((cube.center.x as i32, cube.center.y as i32, cube.center.z as i32), cube.material))
+            .collect();
+        (loaded, file)
+    }
+
+    #[test]
+    fn round_trip_swaps_axes() {
+        let red = plain(1.0, 0.0, 0.0);
+        let blue = plain(0.0, 0.0, 1.0);
+        // 3 de ancho (X), 6 de alto (Y) y 2 de fondo (Z)
+        let blocks: Vec<(BlockPos, &Material)> =
+            vec![((0, 0, 0), &red), ((2, 0, 0), &blue), ((0, 5, 0), &blue), ((1, 2, -1), &red)];
+        let (loaded, file) = round_trip(&blocks);
+
+        // En MagicaVoxel Z es la altura: el modelo mide 3 × 2 × 6
+        assert_eq!(file.models.len(), 1);
+        assert_eq!(file.models[0].size, (3, 2, 6));
+        for (position, material) in blocks {
+            assert_eq!(loaded.get(&position), Some(material), "bloque {:?}", position);
+        }
+    }
+
+    #[test]
+    fn round_trip_tiles_large_scenes() {
+        let green = plain(0.0, 1.0, 0.0);
+        let positions = [(0, 0, 0), (255, 0, 0), (256, 0, 0), (300, 10, -400), (-20, 600, 5)];
+        let blocks: Vec<(BlockPos, &Material)> = positions.iter().map(|&position| (position, &green)).collect();
+        let (loaded, file) = round_trip(&blocks);
+
+        assert!(file.models.len() > 1);
+        assert!(file.models.iter().all(|model| {
+            let (x, y, z) = model.size;
+            x <= MAX_MODEL_SIZE && y <= MAX_MODEL_SIZE && z <= MAX_MODEL_SIZE
+        }));
+        let loaded: Vec<BlockPos> = loaded.into_keys().collect();
+        let mut expected = positions.to_vec();
+        expected.sort();
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn round_trip_keeps_emissive_and_glass_materials() {
+        let lava = Material::new_emissive(
+            Vector3::new(1.0, 0.4, 0.0),
+            10.0,
+            [1.0, 0.0, 0.0, 0.0],
+            0.0,
+            None,
+            Vector3::new(1.0, 0.4, 0.0),
+            3.0,
+        );
+        let glass = Material::new(Vector3::new(0.8, 0.8, 1.0), 80.0, [0.3, 0.2, 0.1, 0.4], 1.33, None);
+        let stone = plain(0.5, 0.5, 0.5);
+        let blocks: Vec<(BlockPos, &Material)> = vec![((0, 0, 0), &lava), ((1, 0, 0), &glass), ((2, 0, 0), &stone)];
+        let (loaded, _) = round_trip(&blocks);
+
+        let lava = &loaded[&(0, 0, 0)];
+        assert!((lava.emission_strength - 3.0).abs() < 1e-4);
+        let glass = &loaded[&(1, 0, 0)];
+        assert!((glass.albedo[3] - 0.4).abs() < 1e-4);
+        assert!((glass.refractive_index - 1.33).abs() < 1e-4);
+        assert_eq!(glass.emission_strength, 0.0);
+        let stone = &loaded[&(2, 0, 0)];
+        assert_eq!(stone.albedo[3], 0.0);
+        assert_eq!(stone.emission_strength, 0.0);
+    }
+}
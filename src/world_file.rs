@@ -0,0 +1,255 @@
+use raylib::prelude::Vector3;
+use std::path::Path;
+
+use crate::material::Material;
+use crate::structures::BlockPos;
+use crate::textures::TextureManager;
+
+// Campos de una línea de material antes de la ruta de la textura (contando la palabra `material`)
+const MATERIAL_FIELDS: usize = 15;
+
+// Nombre para guardar el mundo editado cuando no se dio --world: world.txt, world_2.txt... el primero
+// que no exista, para no pisar un mundo de otra sesión que no se ha cargado
+pub fn unused_world_path() -> String {
+    (1..)
+        .map(|number| if number == 1 { "world.txt".to_string() } else { format!("world_{}.txt", number) })
+        .find(|path| !Path::new(path).exists())
+        .unwrap()
+}
+
+// Mundo de bloques guardado en texto: primero la paleta y después tiras de bloques seguidos en X con
+// el mismo material, que es como suelen venir el terreno y las paredes
+//   material <n> <difuso r g b> <especular> <albedo a b c d> <ior> <emisión r g b> <intensidad> <textura|->
+//   run <x> <y> <z> <largo> <material>
+// Devuelve cuántas tiras se escribieron
+pub fn save_world<'a>(path: &str, blocks: impl Iterator<Item = (BlockPos, &'a Material)>) -> Result<usize, String> {
+    let (contents, runs) = format_world(blocks);
+    std::fs::write(path, contents).map_err(|error| format!("No se pudo escribir {}: {}", path, error))?;
+    Ok(runs)
+}
+
+// Lee un mundo guardado. Las texturas que aún no estén cargadas se cargan; si el archivo ya no
+// existe el material se queda con la referencia y se ve blanco, como en la escena
+pub fn load_world(path: &str, texture_manager: &mut TextureManager) -> Result<Vec<(BlockPos, Material)>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+    parse_world(&contents, texture_manager).map_err(|error| format!("{}: {}", path, error))
+}
+
+// Texto de un mundo (el formato de `save_world`) y cuántas tiras tiene
+fn format_world<'a>(blocks: impl Iterator<Item = (BlockPos, &'a Material)>) -> (String, usize) {
+    // Ordenados por fila para que las tiras salgan seguidas y el archivo sea siempre el mismo
+    let mut blocks: Vec<(BlockPos, &Material)> = blocks.collect();
+    blocks.sort_unstable_by_key(|&((x, y, z), _)| (y, z, x));
+    let mut palette: Vec<&Material> = Vec::new();
+    let indexed: Vec<(BlockPos, usize)> = blocks
+        .into_iter()
+        .map(|(position, material)| {
+            let index = palette.iter().position(|known| *known == material).unwrap_or_else(|| {
+                palette.push(material);
+                palette.len() - 1
+            });
+            (position, index)
+        })
+        .collect();
+
+    let mut contents = String::from("# Mundo de bloques: material <n> <difuso r g b> <especular> <albedo a b c d> <ior> <emisión r g b> <intensidad> <textura|->\n");
+    contents.push_str("#                   run <x> <y> <z> <largo> <material>\n");
+    for (index, material) in palette.iter().enumerate() {
+        let Material { diffuse, albedo, specular, refractive_index, texture_id, emission, emission_strength } = material;
+        contents.push_str(&format!(
+            "material {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n",
+            index,
+            diffuse.x,
+            diffuse.y,
+            diffuse.z,
+            specular,
+            albedo[0],
+            albedo[1],
+            albedo[2],
+            albedo[3],
+            refractive_index,
+            emission.x,
+            emission.y,
+            emission.z,
+            emission_strength,
+            texture_id.as_deref().unwrap_or("-")
+        ));
+    }
+
+    let mut runs = 0;
+    let mut index = 0;
+    while index < indexed.len() {
+        let ((x, y, z), material) = indexed[index];
+        let mut length = 1;
+        while let Some(&((next_x, next_y, next_z), next_material)) = indexed.get(index + length)
+            && (next_x, next_y, next_z) == (x + length as i32, y, z)
+            && next_material == material
+        {
+            length += 1;
+        }
+        contents.push_str(&format!("run {} {} {} {} {}\n", x, y, z, length, material));
+        runs += 1;
+        index += length;
+    }
+    (contents, runs)
+}
+
+// Bloques del texto de un mundo; los errores dicen la línea
+fn parse_world(contents: &str, texture_manager: &mut TextureManager) -> Result<Vec<(BlockPos, Material)>, String> {
+    let mut palette: Vec<Material> = Vec::new();
+    let mut blocks = Vec::new();
+    for (line_index, line) in contents.lines().enumerate() {
+        // Solo se recorta el principio: el final de una línea de material es la ruta de la textura
+        let line = line.trim_start();
+        if line.trim_end().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let error = |message: String| format!("línea {}: {}", line_index + 1, message);
+        match tokens[0] {
+            "material" => {
+                if tokens.get(1) != Some(&palette.len().to_string().as_str()) {
+                    return Err(error(format!("se esperaba el material {}", palette.len())));
+                }
+                let material = parse_material(line).map_err(error)?;
+                if let Some(texture) = &material.texture_id
+                    && Path::new(texture).exists()
+                {
+                    texture_manager.load_cpu_texture(texture);
+                }
+                palette.push(material);
+            }
+            "run" => {
+                let (position, length, index) = parse_run(&tokens).map_err(error)?;
+                let material = palette
+                    .get(index)
+                    .ok_or_else(|| error(format!("material {} no definido", index)))?;
+                let (x, y, z) = position;
+                blocks.extend((0..length).map(|offset| ((x + offset, y, z), material.clone())));
+            }
+            other => return Err(error(format!("se esperaba 'material' o 'run', no '{}'", other))),
+        }
+    }
+    Ok(blocks)
+}
+
+// Los 15 primeros campos van separados por espacios; la ruta de la textura es el resto de la línea
+// tras un separador, tal cual, para que se conserven sus espacios y tabuladores
+fn parse_material(line: &str) -> Result<Material, String> {
+    let mut tokens = Vec::new();
+    let mut rest = line;
+    for _ in 0..MATERIAL_FIELDS {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        tokens.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let texture = match rest.chars().next() {
+        Some(separator) => &rest[separator.len_utf8()..],
+        None => "",
+    };
+    if texture.is_empty() {
+        return Err("faltan valores del material".to_string());
+    }
+    let number = |index: usize| -> Result<f32, String> {
+        tokens[index]
+            .parse::<f32>()
+            .map_err(|_| format!("número no válido: {}", tokens[index]))
+    };
+    let texture = texture.to_string();
+    Ok(Material::new_emissive(
+        Vector3::new(number(2)?, number(3)?, number(4)?),
+        number(5)?,
+        [number(6)?, number(7)?, number(8)?, number(9)?],
+        number(10)?,
+        (texture != "-").then_some(texture),
+        Vector3::new(number(11)?, number(12)?, number(13)?),
+        number(14)?,
+    ))
+}
+
+fn parse_run(tokens: &[&str]) -> Result<(BlockPos, i32, usize), String> {
+    if tokens.len() != 6 {
+        return Err("se esperaba run <x> <y> <z> <largo> <material>".to_string());
+    }
+    let integer = |index: usize| -> Result<i32, String> {
+        tokens[index]
+            .parse::<i32>()
+            .map_err(|_| format!("entero no válido: {}", tokens[index]))
+    };
+    let length = integer(4)?;
+    if length < 1 {
+        return Err(format!("largo no válido: {}", length));
+    }
+    let index = tokens[5]
+        .parse::<usize>()
+        .map_err(|_| format!("material no válido: {}", tokens[5]))?;
+    Ok(((integer(1)?, integer(2)?, integer(3)?), length, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::plain;
+
+    // Escribe los bloques y los vuelve a leer: devuelve las tiras escritas, el texto y los bloques leídos
+    fn round_trip(blocks: &[(BlockPos, &Material)]) -> (usize, String, Vec<(BlockPos, Material)>) {
+        let (contents, runs) = format_world(blocks.iter().copied());
+        let mut loaded = parse_world(&contents, &mut TextureManager::new()).unwrap();
+        loaded.sort_by_key(|&(position, _)| position);
+        (runs, contents, loaded)
+    }
+
+    fn sorted(blocks: &[(BlockPos, &Material)]) -> Vec<(BlockPos, Material)> {
+        let mut blocks: Vec<(BlockPos, Material)> =
+            blocks.iter().map(|&(position, material)| (position, material.clone())).collect();
+        blocks.sort_by_key(|&(position, _)| position);
+        blocks
+    }
+
+    #[test]
+    fn round_trip_palette_and_runs() {
+        let stone = plain(0.5, 0.5, 0.5);
+        let dirt = plain(0.4, 0.25, 0.1);
+        let mut blocks: Vec<(BlockPos, &Material)> = (0..5).map(|x| ((x, 0, 0), &stone)).collect();
+        blocks.extend([((5, 0, 0), &dirt), ((7, 0, 0), &stone), ((-3, 1, 2), &stone)]);
+        let (runs, contents, loaded) = round_trip(&blocks);
+
+        // Piedra de 0 a 4, tierra en 5, piedra suelta en 7 (hay un hueco) y la de otra fila
+        assert_eq!(runs, 4);
+        assert_eq!(contents.lines().filter(|line| line.starts_with("material ")).count(), 2);
+        assert!(contents.contains("run 0 0 0 5 0\n"));
+        assert!(contents.contains("run 5 0 0 1 1\n"));
+        assert_eq!(loaded, sorted(&blocks));
+    }
+
+    #[test]
+    fn round_trip_keeps_material_fields_and_texture_path() {
+        let glass = Material::new(Vector3::new(0.8, 0.9, 1.0), 80.0, [0.3, 0.2, 0.1, 0.6], 1.5, None);
+        let lava = Material::new_emissive(
+            Vector3::new(1.0, 0.3, 0.0),
+            5.0,
+            [1.0, 0.0, 0.0, 0.0],
+            0.0,
+            None,
+            Vector3::new(1.0, 0.5, 0.1),
+            2.5,
+        );
+        // Espacios dobles, tabulador y espacio final: la ruta tiene que volver igual
+        let texture = "assets/no existe/ladrillo  viejo\t2.png ".to_string();
+        let brick = Material::new(Vector3::new(1.0, 1.0, 1.0), 10.0, [0.9, 0.1, 0.0, 0.0], 0.0, Some(texture));
+        let blocks: Vec<(BlockPos, &Material)> = vec![((0, 0, 0), &glass), ((0, 0, 1), &lava), ((0, 0, 2), &brick)];
+        let (_, _, loaded) = round_trip(&blocks);
+        assert_eq!(loaded, sorted(&blocks));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let parse = |contents: &str| parse_world(contents, &mut TextureManager::new());
+        assert!(parse("material 0 1 1 1 10 0.9 0.1 0 0 0 0 0 0\n").is_err());
+        assert_eq!(parse("# nada\nrun 0 0 0 1 0\n").unwrap_err(), "línea 2: material 0 no definido");
+        assert!(parse("material 1 1 1 1 10 0.9 0.1 0 0 0 0 0 0 0 -\n").is_err());
+        assert!(parse("material 0 1 1 1 10 0.9 0.1 0 0 0 0 0 0 0 -\nrun 0 0 0 0 0\n").is_err());
+        assert!(parse("bloque 0 0 0\n").is_err());
+    }
+}